@@ -0,0 +1,328 @@
+use std::{env, sync::OnceLock};
+
+use crate::{
+    labels::ColumnLabels,
+    locale,
+    theme::{render_board, Disc, Theme},
+};
+
+// Turns the accessible mode on when there's no --accessible flag
+pub const ACCESSIBLE_VARIABLE: &str = "CONNECT4_ACCESSIBLE";
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+/* In the accessible mode the game is told in words for screen readers.
+Moves are announced rather than redrawn, the board is described on request, and banners are left out.
+*/
+pub fn init(requested: bool) {
+    let from_environment = env::var(ACCESSIBLE_VARIABLE).is_ok_and(|x| !x.is_empty() && x != "0");
+    let _ = ENABLED.set(requested || from_environment);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.get().copied().unwrap_or(false)
+}
+
+// The board as a grid, or in the accessible mode as a description of every column
+pub fn present_board<P, C>(
+    board: &[C],
+    theme: &Theme,
+    labels: ColumnLabels,
+    names: [&str; 2],
+) -> String
+where
+    P: Copy + Into<Disc>,
+    C: AsRef<[P]>,
+{
+    match enabled() {
+        true => describe_board(board, labels, names),
+        false => render_board(board, theme, labels),
+    }
+}
+
+// The turn prompt, leaving out the disc in the accessible mode
+pub fn turn_prompt(name: &str, theme: &Theme, disc: impl Into<Disc>, range: &str) -> String {
+    match enabled() {
+        true => locale::text("access.turn", &[("name", &name), ("range", &range)]),
+        false => locale::text(
+            "game.turn",
+            &[
+                ("name", &name),
+                ("disc", &theme.paint(disc)),
+                ("range", &range),
+            ],
+        ),
+    }
+}
+
+// The result of a game, with how long it took in the accessible mode. A game without a winner is a draw.
+pub fn result(winner: Option<&str>, moves: usize) -> String {
+    let moves = locale::plural("access.moves", moves, &[]);
+    match (winner, enabled()) {
+        (Some(name), true) => locale::text("access.wins", &[("name", &name), ("moves", &moves)]),
+        (Some(name), false) => locale::text("game.wins", &[("name", &name)]),
+        (None, true) => locale::text("access.draw", &[("moves", &moves)]),
+        (None, false) => locale::text("game.draw", &[]),
+    }
+}
+
+/* Where the last disc in the column landed, and then any columns either player could win in next.
+The threats are given for player one then player two, and left out once the game is over.
+*/
+pub fn announce_move<P, C>(
+    board: &[C],
+    column: usize,
+    labels: ColumnLabels,
+    names: [&str; 2],
+    current: impl Into<Disc>,
+    threats: Option<[Vec<usize>; 2]>,
+) -> String
+where
+    P: Copy + Into<Disc>,
+    C: AsRef<[P]>,
+{
+    let cells = board[column].as_ref();
+    let row = height(cells);
+    let mover = row.checked_sub(1).map_or(Disc::Empty, |x| cells[x].into());
+    let mut sentences = vec![locale::text(
+        "access.move",
+        &[
+            ("name", &name(mover, names)),
+            ("column", &labels.label(column)),
+            ("row", &row),
+        ],
+    )];
+    if let Some(threats) = threats {
+        sentences.extend(describe_threats(current.into(), threats, labels, names));
+    }
+    sentences.join(" ")
+}
+
+/* Every column with a disc in it, from the bottom up, or how big the board is when it's empty.
+Empty columns are mentioned together at the end.
+*/
+pub fn describe_board<P, C>(board: &[C], labels: ColumnLabels, names: [&str; 2]) -> String
+where
+    P: Copy + Into<Disc>,
+    C: AsRef<[P]>,
+{
+    let rows = board.first().map_or(0, |column| column.as_ref().len());
+    let occupied: Vec<usize> = (0..board.len())
+        .filter(|&column| height(board[column].as_ref()) > 0)
+        .collect();
+    if occupied.is_empty() {
+        return locale::text(
+            "access.board_empty",
+            &[("columns", &board.len()), ("rows", &rows)],
+        );
+    }
+    let mut lines: Vec<String> = occupied
+        .iter()
+        .map(|&column| describe_column(board, column, labels, names))
+        .collect();
+    if occupied.len() < board.len() {
+        lines.push(locale::text("access.other_columns_empty", &[]));
+    }
+    lines.join("\n")
+}
+
+// Such as "Column 3, from the bottom: Ada, Grace. 4 spaces left."
+pub fn describe_column<P, C>(
+    board: &[C],
+    column: usize,
+    labels: ColumnLabels,
+    names: [&str; 2],
+) -> String
+where
+    P: Copy + Into<Disc>,
+    C: AsRef<[P]>,
+{
+    let cells = board[column].as_ref();
+    let label = labels.label(column);
+    let filled = height(cells);
+    if filled == 0 {
+        return locale::text("access.column_empty", &[("column", &label)]);
+    }
+    let discs: Vec<String> = cells[..filled]
+        .iter()
+        .map(|&x| name(x.into(), names))
+        .collect();
+    let space = match cells.len() - filled {
+        0 => locale::text("access.full", &[]),
+        left => locale::plural("access.spaces", left, &[]),
+    };
+    locale::text(
+        "access.column",
+        &[
+            ("column", &label),
+            ("discs", &discs.join(", ")),
+            ("space", &space),
+        ],
+    )
+}
+
+// A row from left to right, counting rows from 1 at the bottom. Gives an error for rows that aren't on the board.
+pub fn describe_row<P, C>(board: &[C], row: usize, names: [&str; 2]) -> Result<String, String>
+where
+    P: Copy + Into<Disc>,
+    C: AsRef<[P]>,
+{
+    let rows = board.first().map_or(0, |column| column.as_ref().len());
+    if row == 0 || row > rows {
+        return Err(locale::text(
+            "access.no_row",
+            &[("row", &row), ("rows", &rows)],
+        ));
+    }
+    let cells: Vec<Disc> = board
+        .iter()
+        .map(|column| column.as_ref()[row - 1].into())
+        .collect();
+    if cells.iter().all(|x| *x == Disc::Empty) {
+        return Ok(locale::text("access.row_empty", &[("row", &row)]));
+    }
+    let cells: Vec<String> = cells.into_iter().map(|x| name(x, names)).collect();
+    Ok(locale::text(
+        "access.row",
+        &[("row", &row), ("cells", &cells.join(", "))],
+    ))
+}
+
+// The player to move's winning columns first, then the ones their opponent has to block
+fn describe_threats(
+    current: Disc,
+    threats: [Vec<usize>; 2],
+    labels: ColumnLabels,
+    names: [&str; 2],
+) -> Vec<String> {
+    let (mine, theirs, opponent) = match current {
+        Disc::Two => (&threats[1], &threats[0], Disc::One),
+        _ => (&threats[0], &threats[1], Disc::Two),
+    };
+    let mut sentences = vec![];
+    if !mine.is_empty() {
+        sentences.push(locale::text(
+            "access.can_win",
+            &[
+                ("name", &name(current, names)),
+                ("columns", &columns(mine, labels)),
+            ],
+        ));
+    }
+    if !theirs.is_empty() {
+        sentences.push(locale::text(
+            "access.threatens",
+            &[
+                ("name", &name(opponent, names)),
+                ("columns", &columns(theirs, labels)),
+            ],
+        ));
+    }
+    sentences
+}
+
+// How many discs are in a column, which is also the row of the top one
+fn height<P: Copy + Into<Disc>>(cells: &[P]) -> usize {
+    cells.iter().filter(|x| (**x).into() != Disc::Empty).count()
+}
+
+fn name(disc: Disc, names: [&str; 2]) -> String {
+    match disc {
+        Disc::Empty => locale::text("access.empty", &[]),
+        Disc::One => names[0].to_owned(),
+        Disc::Two => names[1].to_owned(),
+    }
+}
+
+// Such as "column 3" or "columns 2, 4 and 5"
+fn columns(columns: &[usize], labels: ColumnLabels) -> String {
+    let mut labelled: Vec<String> = columns.iter().map(|&x| labels.label(x)).collect();
+    let list = match labelled.pop() {
+        Some(last) if !labelled.is_empty() => format!(
+            "{} {} {}",
+            labelled.join(", "),
+            locale::text("list.and", &[]),
+            last
+        ),
+        last => last.unwrap_or_default(),
+    };
+    locale::plural("access.columns", columns.len(), &[("list", &list)])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{labels::ColumnLabels, remove_consts::game::Player};
+
+    use super::{announce_move, describe_board, describe_column, describe_row};
+
+    const NAMES: [&str; 2] = ["Ada", "Grace"];
+
+    // Columns from left to right, each from the bottom up
+    fn board() -> Vec<Vec<Player>> {
+        let (n, x, o) = (Player::None, Player::One, Player::Two);
+        vec![vec![n, n, n], vec![x, n, n], vec![o, x, o], vec![x, o, n]]
+    }
+
+    #[test]
+    fn announces_where_a_disc_landed() {
+        assert_eq!(
+            announce_move(
+                &board(),
+                3,
+                ColumnLabels::OneIndexed,
+                NAMES,
+                Player::One,
+                None
+            ),
+            "Grace dropped in column 4, row 2."
+        );
+        assert_eq!(
+            announce_move(
+                &board(),
+                2,
+                ColumnLabels::Letters,
+                NAMES,
+                Player::One,
+                Some([vec![0], vec![1, 3, 4]])
+            ),
+            "Grace dropped in column c, row 3. Ada can win in column a. Grace threatens to win in columns b, d and e."
+        );
+    }
+
+    #[test]
+    fn describes_columns_rows_and_the_board() {
+        let board = board();
+        assert_eq!(
+            describe_column(&board, 1, ColumnLabels::ZeroIndexed, NAMES),
+            "Column 1, from the bottom: Ada. 2 spaces left."
+        );
+        assert_eq!(
+            describe_column(&board, 2, ColumnLabels::ZeroIndexed, NAMES),
+            "Column 2, from the bottom: Grace, Ada, Grace. It is full."
+        );
+        assert_eq!(
+            describe_row(&board, 2, NAMES),
+            Ok("Row 2, from the left: empty, empty, Ada, Grace.".to_owned())
+        );
+        assert_eq!(
+            describe_row(&board, 3, NAMES),
+            Ok("Row 3, from the left: empty, empty, Grace, empty.".to_owned())
+        );
+        assert!(describe_row(&board, 4, NAMES).is_err());
+        assert!(describe_row(&board, 0, NAMES).is_err());
+        assert_eq!(
+            describe_board(&board, ColumnLabels::ZeroIndexed, NAMES)
+                .lines()
+                .last(),
+            Some("The other columns are empty.")
+        );
+        assert_eq!(
+            describe_board(
+                &vec![vec![Player::None; 6]; 7],
+                ColumnLabels::ZeroIndexed,
+                NAMES
+            ),
+            "The board is empty. It has 7 columns and 6 rows."
+        );
+    }
+}
@@ -0,0 +1,157 @@
+// How the columns are named under the board, in the turn prompt and when reading a move
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum ColumnLabels {
+    #[default]
+    ZeroIndexed,
+    OneIndexed,
+    // a to z, then aa, ab and so on, like spreadsheet columns
+    Letters,
+}
+
+impl ColumnLabels {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().replace(['_', ' '], "-").as_str() {
+            "numbers" | "zero-indexed" => Some(ColumnLabels::ZeroIndexed),
+            "one-indexed" => Some(ColumnLabels::OneIndexed),
+            "letters" => Some(ColumnLabels::Letters),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self, column: usize) -> String {
+        match self {
+            ColumnLabels::ZeroIndexed => column.to_string(),
+            ColumnLabels::OneIndexed => (column + 1).to_string(),
+            ColumnLabels::Letters => {
+                let mut letters = vec![];
+                let mut remaining = column + 1;
+                while remaining > 0 {
+                    remaining -= 1;
+                    letters.push((b'a' + (remaining % 26) as u8) as char);
+                    remaining /= 26;
+                }
+                letters.iter().rev().collect()
+            }
+        }
+    }
+
+    // The width of the longest label, which is the last one
+    pub fn width(&self, columns: usize) -> usize {
+        self.label(columns.saturating_sub(1)).len()
+    }
+
+    // For the turn prompt, such as 0-6 or a-g
+    pub fn range(&self, columns: usize) -> String {
+        format!(
+            "{}-{}",
+            self.label(0),
+            self.label(columns.saturating_sub(1))
+        )
+    }
+
+    // Reads a label typed by a player, giving the zero indexed column
+    pub fn parse(&self, input: &str, columns: usize) -> Result<usize, String> {
+        let input = input.trim();
+        let column = match self {
+            ColumnLabels::ZeroIndexed | ColumnLabels::OneIndexed => {
+                let number: usize = input.parse().map_err(|_| {
+                    format!(
+                        "The input <{}> could not be parsed as a usize. Please try again.",
+                        input
+                    )
+                })?;
+                match self {
+                    ColumnLabels::OneIndexed => number.checked_sub(1),
+                    _ => Some(number),
+                }
+            }
+            ColumnLabels::Letters => {
+                if input.is_empty() || !input.chars().all(|x| x.is_ascii_alphabetic()) {
+                    return Err(format!(
+                        "The input <{}> is not a column letter. Please try again.",
+                        input
+                    ));
+                }
+                input
+                    .to_ascii_lowercase()
+                    .bytes()
+                    .try_fold(0usize, |total, letter| {
+                        total
+                            .checked_mul(26)?
+                            .checked_add((letter - b'a') as usize + 1)
+                    })
+                    .and_then(|x| x.checked_sub(1))
+            }
+        };
+        match column {
+            Some(column) if column < columns => Ok(column),
+            _ => Err(format!(
+                "There is no column <{}>. Please choose from {}.",
+                input,
+                self.range(columns)
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ColumnLabels;
+
+    #[test]
+    fn labels_columns_with_numbers_or_letters() {
+        let labels: Vec<String> = [0, 9, 10, 25, 26, 27, 701, 702]
+            .iter()
+            .map(|x| ColumnLabels::Letters.label(*x))
+            .collect();
+        assert_eq!(labels, ["a", "j", "k", "z", "aa", "ab", "zz", "aaa"]);
+        assert_eq!(ColumnLabels::ZeroIndexed.label(10), "10");
+        assert_eq!(ColumnLabels::OneIndexed.label(10), "11");
+        assert_eq!(ColumnLabels::ZeroIndexed.width(11), 2);
+        assert_eq!(ColumnLabels::OneIndexed.width(9), 1);
+        assert_eq!(ColumnLabels::Letters.width(27), 2);
+        assert_eq!(ColumnLabels::OneIndexed.range(12), "1-12");
+        assert_eq!(ColumnLabels::Letters.range(7), "a-g");
+    }
+
+    #[test]
+    fn parses_the_labels_it_prints() {
+        for labels in [
+            ColumnLabels::ZeroIndexed,
+            ColumnLabels::OneIndexed,
+            ColumnLabels::Letters,
+        ] {
+            for column in 0..60 {
+                assert_eq!(labels.parse(&labels.label(column), 60), Ok(column));
+            }
+        }
+        assert_eq!(ColumnLabels::Letters.parse(" AB\n", 30), Ok(27));
+    }
+
+    #[test]
+    fn rejects_columns_that_are_not_on_the_board() {
+        assert!(ColumnLabels::ZeroIndexed.parse("7", 7).is_err());
+        assert!(ColumnLabels::ZeroIndexed.parse("x", 7).is_err());
+        assert!(ColumnLabels::OneIndexed.parse("0", 7).is_err());
+        assert_eq!(
+            ColumnLabels::Letters.parse("h", 7),
+            Err("There is no column <h>. Please choose from a-g.".to_owned())
+        );
+        assert!(ColumnLabels::Letters.parse("3", 7).is_err());
+        assert!(ColumnLabels::Letters.parse("", 7).is_err());
+        assert!(ColumnLabels::Letters.parse(&"z".repeat(20), 7).is_err());
+    }
+
+    #[test]
+    fn reads_label_names() {
+        assert_eq!(
+            ColumnLabels::from_name("One Indexed"),
+            Some(ColumnLabels::OneIndexed)
+        );
+        assert_eq!(
+            ColumnLabels::from_name("letters"),
+            Some(ColumnLabels::Letters)
+        );
+        assert_eq!(ColumnLabels::from_name("roman"), None);
+    }
+}
@@ -0,0 +1,228 @@
+use std::{collections::HashMap, env, fmt::Display, sync::OnceLock};
+
+// The catalogues are built into the binary, so there's nothing to install alongside it
+const ENGLISH: &str = include_str!("../locales/en.txt");
+const SPANISH: &str = include_str!("../locales/es.txt");
+const GERMAN: &str = include_str!("../locales/de.txt");
+
+// Checked in order, as with gettext. The first one that's set decides the language.
+const LOCALE_VARIABLES: [&str; 3] = ["LC_ALL", "LC_MESSAGES", "LANG"];
+
+static CURRENT: OnceLock<Locale> = OnceLock::new();
+static CATALOGUES: [OnceLock<HashMap<&'static str, String>>; 3] =
+    [OnceLock::new(), OnceLock::new(), OnceLock::new()];
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Locale {
+    English,
+    Spanish,
+    German,
+}
+
+impl Locale {
+    // Accepts language codes with or without a region and encoding, such as es, de_DE or en_GB.UTF-8
+    pub fn from_name(name: &str) -> Option<Self> {
+        let language = name
+            .trim()
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        match language.as_str() {
+            "en" | "english" => Some(Locale::English),
+            "es" | "spanish" | "español" => Some(Locale::Spanish),
+            "de" | "german" | "deutsch" => Some(Locale::German),
+            _ => None,
+        }
+    }
+
+    // The requested locale, otherwise the one from the environment, otherwise English
+    pub fn detect(requested: Option<Locale>) -> Self {
+        let from_environment = LOCALE_VARIABLES
+            .iter()
+            .filter_map(|x| env::var(x).ok())
+            .find(|x| !x.is_empty());
+        requested
+            .or_else(|| from_environment.as_deref().and_then(Locale::from_name))
+            .unwrap_or(Locale::English)
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Locale::English => 0,
+            Locale::Spanish => 1,
+            Locale::German => 2,
+        }
+    }
+
+    fn source(&self) -> &'static str {
+        match self {
+            Locale::English => ENGLISH,
+            Locale::Spanish => SPANISH,
+            Locale::German => GERMAN,
+        }
+    }
+
+    // All three languages use the singular for exactly one and the plural otherwise, zero included
+    fn plural_form(&self, count: usize) -> &'static str {
+        match count {
+            1 => "one",
+            _ => "other",
+        }
+    }
+
+    fn catalogue(&self) -> &'static HashMap<&'static str, String> {
+        CATALOGUES[self.index()].get_or_init(|| parse_catalogue(self.source()))
+    }
+
+    // Missing translations fall back to English, and then to the key itself
+    fn translate(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let template = self
+            .catalogue()
+            .get(key)
+            .or_else(|| Locale::English.catalogue().get(key))
+            .map_or(key, |x| x.as_str());
+        substitute(template, args)
+    }
+}
+
+// Chooses the language for the rest of the run. Only the first call has any effect.
+pub fn init(locale: Locale) {
+    let _ = CURRENT.set(locale);
+}
+
+fn current() -> Locale {
+    CURRENT.get().copied().unwrap_or(Locale::English)
+}
+
+// The text for the key in the current language, with each {name} replaced by its argument
+pub fn text(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    current().translate(key, args)
+}
+
+// Like text, but chooses between the key's `.one` and `.other` forms by the count, which is given as {count}
+pub fn plural(key: &str, count: usize, args: &[(&str, &dyn Display)]) -> String {
+    let locale = current();
+    let mut args = args.to_vec();
+    args.push(("count", &count));
+    locale.translate(&format!("{}.{}", key, locale.plural_form(count)), &args)
+}
+
+// Whether the answer to a y/N question was yes. The English letter always works too.
+pub fn is_yes(answer: &str) -> bool {
+    let answer = answer.trim().to_lowercase();
+    answer == "y" || answer == text("answer.yes", &[])
+}
+
+// Whether the answer to a Y/n question was no
+pub fn is_no(answer: &str) -> bool {
+    let answer = answer.trim().to_lowercase();
+    answer == "n" || answer == text("answer.no", &[])
+}
+
+/* Catalogues have one `key = text` per line, with # for comments.
+A \n in the text is a line break, and the text can't otherwise span lines.
+*/
+fn parse_catalogue(source: &'static str) -> HashMap<&'static str, String> {
+    source
+        .lines()
+        .filter(|x| !x.trim().is_empty() && !x.trim_start().starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, text)| (key.trim(), text.trim().replace("\\n", "\n")))
+        .collect()
+}
+
+fn substitute(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut output = template.to_owned();
+    for (name, value) in args {
+        output = output.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{substitute, Locale};
+
+    const LOCALES: [Locale; 3] = [Locale::English, Locale::Spanish, Locale::German];
+
+    // The {names} used in a piece of text
+    fn placeholders(text: &str) -> BTreeSet<String> {
+        text.split('{')
+            .skip(1)
+            .filter_map(|x| x.split_once('}'))
+            .map(|(name, _)| name.to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn every_key_exists_in_every_locale() {
+        let english = Locale::English.catalogue();
+        assert!(english.len() > 50);
+        for locale in LOCALES {
+            let catalogue = locale.catalogue();
+            for (key, text) in english {
+                let translation = catalogue
+                    .get(key)
+                    .unwrap_or_else(|| panic!("{:?} is missing <{}>", locale, key));
+                assert_eq!(
+                    placeholders(translation),
+                    placeholders(text),
+                    "{:?} has different placeholders for <{}>",
+                    locale,
+                    key
+                );
+            }
+            for key in catalogue.keys() {
+                assert!(
+                    english.contains_key(key),
+                    "{:?} has <{}>, which English doesn't",
+                    locale,
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn translates_with_arguments() {
+        let name = "Ada";
+        assert_eq!(
+            Locale::English.translate("game.wins", &[("name", &name)]),
+            "Ada wins!"
+        );
+        assert_eq!(
+            Locale::Spanish.translate("game.wins", &[("name", &name)]),
+            "¡Gana Ada!"
+        );
+        assert_eq!(
+            Locale::German.translate("game.wins", &[("name", &name)]),
+            "Ada gewinnt!"
+        );
+        assert_eq!(Locale::German.translate("no.such.key", &[]), "no.such.key");
+        assert_eq!(substitute("{a} and {a}", &[("a", &1)]), "1 and 1");
+    }
+
+    #[test]
+    fn chooses_plural_forms() {
+        let forms: Vec<String> = [0, 1, 2]
+            .iter()
+            .map(|count| {
+                let key = format!("score.draws.{}", Locale::Spanish.plural_form(*count));
+                Locale::Spanish.translate(&key, &[("count", count)])
+            })
+            .collect();
+        assert_eq!(forms, ["0 empates", "1 empate", "2 empates"]);
+    }
+
+    #[test]
+    fn reads_locale_names() {
+        assert_eq!(Locale::from_name("de_DE.UTF-8"), Some(Locale::German));
+        assert_eq!(Locale::from_name("es-MX"), Some(Locale::Spanish));
+        assert_eq!(Locale::from_name("EN"), Some(Locale::English));
+        assert_eq!(Locale::from_name("C"), None);
+        assert_eq!(Locale::detect(Some(Locale::German)), Locale::German);
+    }
+}
@@ -1,37 +1,201 @@
-use std::io;
+use std::{env, io, process::ExitCode};
 
+use labels::ColumnLabels;
+use locale::Locale;
+use remove_consts::{
+    ai::Players,
+    cli::{self, Command},
+    clock::TimeControl,
+    commands,
+    ruleset::Ruleset,
+};
+use scoreboard::{Scoreboard, DEFAULT_NAMES};
+use stats::SortBy;
+use theme::Theme;
+
+mod accessible;
+mod labels;
+mod locale;
 mod overengineered;
 mod remove_consts;
+mod scoreboard;
+mod stats;
 mod submission;
+mod theme;
+mod turn;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let command = match cli::take_global_options(&args).and_then(|(global, args)| {
+        locale::init(Locale::detect(global.locale));
+        accessible::init(global.accessible);
+        cli::parse(&args)
+    }) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!(
+                "{}\nRun with --help to see the commands and options.",
+                message
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+    match command {
+        Command::Menu => menu(),
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            ExitCode::SUCCESS
+        }
+        Command::Rules(directory) => validate_rules(&directory),
+        Command::Play(options) => commands::play(options),
+        Command::Analyze(options, moves) => commands::analyze(options, &moves),
+        Command::Solve(options, moves) => commands::solve(options, &moves),
+        Command::Replay(options, moves) => commands::replay_moves(options, &moves),
+        Command::Batch(path) => commands::batch(path.as_deref()),
+        Command::Tournament(options) => commands::tournament(options),
+        Command::Serve(options, address) => commands::serve(options, &address),
+        Command::Connect(options, address, game) => commands::connect(options, &address, game),
+        Command::Lobby(options, address) => commands::lobby(options, &address),
+        Command::Create(options, address) => commands::create(options, &address),
+        Command::Watch(options, address, game) => commands::watch(options, &address, game),
+        Command::Correspond(options, path, action) => {
+            commands::correspond(options, &path, action.as_deref())
+        }
+        Command::Api(address) => commands::api(&address),
+        Command::Web(options, address) => commands::web(options, &address),
+        Command::Engine(options) => commands::engine(options),
+        Command::Stats(sort) => commands::stats(sort),
+    }
+}
 
-fn main() {
+fn menu() -> ExitCode {
     let stdin = io::stdin();
     let input = &mut String::new();
+    let theme = Theme::detect(None);
+    let labels = ColumnLabels::default();
 
-    println!("Welcome to connect 4");
+    println!("{}", locale::text("menu.welcome", &[]));
 
     loop {
-        println!("<<Main Menu>>");
-        println!(
-            "If you'd like to play the normal version, please enter '1'
-For the customisable rules version enter '2'
-For the customisable rules and board size version enter '3'
-Alternatively, if you'd like to exit, please enter '0'"
-        );
+        if !accessible::enabled() {
+            println!("{}", locale::text("menu.title", &[]));
+        }
+        println!("{}", locale::text("menu.options", &[]));
 
         input.clear();
         stdin.read_line(input).expect("Error reading from stdio");
         match input.trim() {
-            "1" => submission::game_loop::play(),
-            "2" => overengineered::game_loop::play(),
-            "3" => remove_consts::game_loop::play(),
+            "1" => {
+                let scoreboard = ask_for_match(input);
+                submission::game_loop::play(&theme, labels, ask_for_clock(input), scoreboard)
+            }
+            "2" => {
+                let scoreboard = ask_for_match(input);
+                overengineered::game_loop::play(&theme, labels, ask_for_clock(input), scoreboard)
+            }
+            "3" => {
+                let scoreboard = ask_for_match(input);
+                remove_consts::game_loop::play(
+                    None,
+                    Players::default(),
+                    ask_for_clock(input),
+                    &theme,
+                    labels,
+                    scoreboard,
+                )
+            }
+            "4" => remove_consts::archive_loop::search(),
+            "5" => submission::dataset_loop::verify_solver(),
+            "6" => remove_consts::training_loop::export_dataset(),
+            "7" if accessible::enabled() => eprintln!("{}", locale::text("access.no_tui", &[])),
+            "7" => {
+                if let Err(error) = remove_consts::tui::play(
+                    &Ruleset::default(),
+                    Players::default(),
+                    ask_for_clock(input),
+                    &theme,
+                    labels,
+                ) {
+                    eprintln!("{}", error);
+                }
+            }
+            "8" => {
+                commands::stats(SortBy::default());
+            }
             "0" => {
-                println!("Thank you for playing!");
-                return;
+                println!("{}", locale::text("menu.goodbye", &[]));
+                return ExitCode::SUCCESS;
             }
             input => {
-                println!("Did not recognise <{}> as an option.", input)
+                println!(
+                    "{}",
+                    locale::text("menu.unrecognised", &[("input", &input)])
+                )
             }
         }
     }
 }
+
+// Asks for the players' names, and how many games to play if it's a match
+fn ask_for_match(input: &mut String) -> Scoreboard {
+    let stdin = io::stdin();
+    println!(
+        "{}",
+        locale::text(
+            "menu.ask_names",
+            &[("one", &DEFAULT_NAMES[0]), ("two", &DEFAULT_NAMES[1])]
+        )
+    );
+    input.clear();
+    stdin.read_line(input).expect("Error reading from stdio");
+    let mut names = DEFAULT_NAMES.map(str::to_owned);
+    for (name, entered) in names.iter_mut().zip(input.split(',')) {
+        if !entered.trim().is_empty() {
+            *name = entered.trim().to_owned();
+        }
+    }
+
+    loop {
+        println!("{}", locale::text("menu.ask_best_of", &[]));
+        input.clear();
+        stdin.read_line(input).expect("Error reading from stdio");
+        match input.trim() {
+            "" => return Scoreboard::new(names, None),
+            entered => match entered.parse::<usize>() {
+                Ok(best_of) if best_of > 0 => return Scoreboard::new(names, Some(best_of)),
+                _ => eprintln!(
+                    "{}",
+                    locale::text("menu.invalid_best_of", &[("input", &entered)])
+                ),
+            },
+        }
+    }
+}
+
+// Asks for a time control, giving nothing to play without a clock
+fn ask_for_clock(input: &mut String) -> Option<TimeControl> {
+    let stdin = io::stdin();
+    loop {
+        println!("{}", locale::text("menu.ask_clock", &[]));
+        input.clear();
+        stdin.read_line(input).expect("Error reading from stdio");
+        match input.trim() {
+            "" => return None,
+            entered => match TimeControl::parse(entered) {
+                Ok(time_control) => return Some(time_control),
+                Err(_) => eprintln!(
+                    "{}",
+                    locale::text("menu.invalid_clock", &[("input", &entered)])
+                ),
+            },
+        }
+    }
+}
+
+fn validate_rules(directory: &str) -> ExitCode {
+    match remove_consts::rules_command::check_directory(directory) {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
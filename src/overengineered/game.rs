@@ -1,5 +1,10 @@
 use std::fmt::Display;
 
+use crate::{
+    labels::ColumnLabels,
+    theme::{render_board, PLAIN},
+};
+
 use super::win_conditions::WinCondition;
 
 pub const DEFAULT_COLUMNS: usize = 7;
@@ -66,22 +71,11 @@ impl<'a, const COLUMNS: usize, const ROWS: usize> std::fmt::Debug for Game<'a, C
 
 impl<'a, const COLUMNS: usize, const ROWS: usize> std::fmt::Display for Game<'a, COLUMNS, ROWS> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut output: String = "\n".to_owned();
-        for j in (0..self.game_board[0].len()).rev() {
-            for i in 0..self.game_board.len() {
-                output += match self.game_board[i][j] {
-                    Player::None => ".",
-                    Player::One => "x",
-                    Player::Two => "o",
-                }
-            }
-            output += "\n"
-        }
-        for i in 0..self.game_board.len() {
-            output += &i.to_string();
-        }
-        output += "\n";
-        f.write_str(&output)
+        f.write_str(&render_board(
+            &self.game_board,
+            &PLAIN,
+            ColumnLabels::default(),
+        ))
     }
 }
 
@@ -96,6 +90,10 @@ impl<'a, const COLUMNS: usize, const ROWS: usize> Game<'a, COLUMNS, ROWS> {
         }
     }
 
+    pub fn board(&self) -> &[[Player; ROWS]; COLUMNS] {
+        &self.game_board
+    }
+
     // Plays on the column - zero indexed
     pub fn play_on_column(self, column: usize) -> Result<Self, GameError<'a, COLUMNS, ROWS>> {
         let mut active_state = self.clone();
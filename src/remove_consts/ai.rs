@@ -0,0 +1,336 @@
+use std::{
+    fmt::Display,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use super::{
+    external_engine::ExternalEngine,
+    game::{Game, GameStatus, Player},
+    random::Random,
+    ruleset::Ruleset,
+};
+
+pub const DEFAULT_DEPTH: usize = 4;
+
+pub const DEFAULT_MOVETIME: Duration = Duration::from_secs(1);
+
+// Any win outscores every position that hasn't been decided yet. Sooner wins score higher.
+const WIN: i32 = 1_000;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum PlayerType {
+    Human,
+    // Plays any open column
+    Random,
+    // Searches a few moves ahead with `best_move`
+    Computer,
+    // A program that speaks the engine protocol, run with the command in `Players::engines` at the index
+    Engine(usize),
+}
+
+impl PlayerType {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "human" => Some(PlayerType::Human),
+            "random" => Some(PlayerType::Random),
+            "computer" | "ai" => Some(PlayerType::Computer),
+            _ => None,
+        }
+    }
+}
+
+impl Display for PlayerType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlayerType::Human => f.write_str("human"),
+            PlayerType::Random => f.write_str("random"),
+            PlayerType::Computer => f.write_str("computer"),
+            PlayerType::Engine(_) => f.write_str("engine"),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Players {
+    pub one: PlayerType,
+    pub two: PlayerType,
+    // How many moves ahead computer players search
+    pub depth: usize,
+    // Random players are seeded from the clock unless a seed is given
+    pub seed: Option<u64>,
+    // The commands that run engine players
+    pub engines: [Option<String>; 2],
+    // How long engines have for each move
+    pub movetime: Duration,
+}
+
+impl Default for Players {
+    fn default() -> Self {
+        Players {
+            one: PlayerType::Human,
+            two: PlayerType::Human,
+            depth: DEFAULT_DEPTH,
+            seed: None,
+            engines: [None, None],
+            movetime: DEFAULT_MOVETIME,
+        }
+    }
+}
+
+impl Players {
+    pub fn for_player(&self, player: Player) -> PlayerType {
+        match player {
+            Player::Two => self.two,
+            _ => self.one,
+        }
+    }
+
+    pub fn has_human(&self) -> bool {
+        self.one == PlayerType::Human || self.two == PlayerType::Human
+    }
+
+    pub fn bot(&self) -> Bot {
+        let seed = self.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |x| x.as_nanos() as u64)
+        });
+        Bot {
+            random: Random::new(seed),
+            depth: self.depth,
+            engine_commands: self.engines.clone(),
+            engines: [None, None],
+            movetime: self.movetime,
+            time_left: None,
+            engine_error: None,
+        }
+    }
+}
+
+/* Picks moves for the players that aren't human. Engines are started when they're first needed and
+kept for every game after. An engine that fails is dropped, and the computer player moves for it instead.
+*/
+pub struct Bot {
+    random: Random,
+    depth: usize,
+    // Cleared once the engine has failed, so it isn't started again
+    engine_commands: [Option<String>; 2],
+    engines: [Option<ExternalEngine>; 2],
+    movetime: Duration,
+    // How long the player to move has on their clock, if the game is timed
+    time_left: Option<Duration>,
+    engine_error: Option<String>,
+}
+
+impl Bot {
+    // Gives nothing for human players, or when the game is over
+    pub fn choose(&mut self, player_type: PlayerType, game: &Game) -> Option<usize> {
+        match player_type {
+            PlayerType::Human => None,
+            PlayerType::Random => {
+                let open_columns = open_columns(game);
+                match open_columns.is_empty() || game.status != GameStatus::Started {
+                    true => None,
+                    false => Some(open_columns[self.random.below(open_columns.len())]),
+                }
+            }
+            PlayerType::Computer => best_move(game, self.depth),
+            PlayerType::Engine(_) if game.status != GameStatus::Started => None,
+            // The engine has already failed, and said why then
+            PlayerType::Engine(index) if self.engine_commands[index].is_none() => {
+                best_move(game, self.depth)
+            }
+            PlayerType::Engine(index) => match self.engine_move(index, game) {
+                Ok(column) => Some(column),
+                Err(message) => {
+                    self.engine_commands[index] = None;
+                    self.engines[index] = None;
+                    self.engine_error = Some(message);
+                    best_move(game, self.depth)
+                }
+            },
+        }
+    }
+
+    // Engines think for at most half the time left on the clock, so they move faster when short of it
+    pub fn limit_time(&mut self, time_left: Option<Duration>) {
+        self.time_left = time_left;
+    }
+
+    // Why an engine last failed, if it has since this was last asked
+    pub fn take_engine_error(&mut self) -> Option<String> {
+        self.engine_error.take()
+    }
+
+    fn engine_move(&mut self, index: usize, game: &Game) -> Result<usize, String> {
+        let engine = match &mut self.engines[index] {
+            Some(engine) => engine,
+            engine => {
+                let command = self.engine_commands[index].as_deref().unwrap_or_default();
+                engine.insert(ExternalEngine::launch(command)?)
+            }
+        };
+        let movetime = self
+            .time_left
+            .map_or(self.movetime, |x| self.movetime.min(x / 2));
+        engine.best_move(game, movetime)
+    }
+}
+
+// The best column for the player whose turn it is, searching `depth` moves ahead. Central columns win ties.
+pub fn best_move(game: &Game, depth: usize) -> Option<usize> {
+    if game.status != GameStatus::Started {
+        return None;
+    }
+    let mut best = None;
+    let mut alpha = -WIN * 2;
+    for column in column_order(game.board().len()) {
+        if let Some(score) = score_move(game, column, depth.max(1), alpha, WIN * 2) {
+            if best.is_none() || score > alpha {
+                best = Some(column);
+                alpha = score;
+            }
+        }
+    }
+    best
+}
+
+// The best column after the moves, for the versions of the game that don't have a computer player of their own
+pub fn suggest(ruleset: &Ruleset, moves: &[usize], depth: usize) -> Option<usize> {
+    let win_conditions = ruleset.win_conditions();
+    let mut game = Game::initialise(ruleset.columns, ruleset.rows, &win_conditions)
+        .with_variants(&ruleset.variants);
+    for &column in moves {
+        game = game.play_on_column(column).ok()?;
+    }
+    best_move(&game, depth)
+}
+
+// The score of the position for the player whose turn it is
+fn negamax(game: &Game, depth: usize, mut alpha: i32, beta: i32) -> i32 {
+    let mut best = -WIN * 2;
+    for column in column_order(game.board().len()) {
+        if let Some(score) = score_move(game, column, depth, alpha, beta) {
+            best = best.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+    }
+    best
+}
+
+// The score of playing in the column for the player whose turn it is, or nothing if the column is full
+fn score_move(game: &Game, column: usize, depth: usize, alpha: i32, beta: i32) -> Option<i32> {
+    let next = game.clone().play_on_column(column).ok()?;
+    let score = match next.status {
+        // The winner isn't always the player who connected, as in misere games
+        GameStatus::Completed if next.winner == Some(game.current) => WIN + depth as i32,
+        GameStatus::Completed => -WIN - depth as i32,
+        GameStatus::Draw => 0,
+        GameStatus::Started if depth <= 1 => 0,
+        GameStatus::Started => -negamax(&next, depth - 1, -beta, -alpha),
+    };
+    Some(score)
+}
+
+fn open_columns(game: &Game) -> Vec<usize> {
+    (0..game.board().len())
+        .filter(|&column| game.board()[column].contains(&Player::None))
+        .collect()
+}
+
+fn column_order(columns: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..columns).collect();
+    order.sort_by_key(|&column| (2 * column).abs_diff(columns.saturating_sub(1)));
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remove_consts::{
+        game::{Game, Variant},
+        win_conditions::default_win_conditions,
+    };
+
+    use super::{best_move, PlayerType, Players};
+
+    fn play<'a>(mut game: Game<'a>, moves: &[usize]) -> Game<'a> {
+        for &column in moves {
+            game = game.play_on_column(column).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn takes_a_winning_move() {
+        let win_conditions = default_win_conditions();
+        // Player One has three stacked in column 0
+        let game = play(Game::initialise(7, 6, &win_conditions), &[0, 1, 0, 1, 0, 6]);
+        assert_eq!(best_move(&game, 4), Some(0));
+    }
+
+    #[test]
+    fn blocks_the_opponent_from_winning() {
+        let win_conditions = default_win_conditions();
+        // x x x . on the bottom row, with Player Two to move
+        let game = play(Game::initialise(7, 6, &win_conditions), &[0, 6, 1, 6, 2]);
+        assert_eq!(best_move(&game, 2), Some(3));
+    }
+
+    #[test]
+    fn avoids_connecting_in_a_misere_game() {
+        let win_conditions = default_win_conditions();
+        let game = play(
+            Game::initialise(4, 4, &win_conditions).with_variants(&[Variant::Misere]),
+            &[0, 1, 0, 1, 0, 1],
+        );
+        let column = best_move(&game, 2).unwrap();
+        assert_ne!(column, 0);
+    }
+
+    #[test]
+    fn random_players_repeat_with_the_same_seed() {
+        let win_conditions = default_win_conditions();
+        let players = Players {
+            one: PlayerType::Random,
+            two: PlayerType::Random,
+            seed: Some(3),
+            ..Default::default()
+        };
+        let game_with = |players: &Players| {
+            let mut bot = players.bot();
+            let mut game = Game::initialise(7, 6, &win_conditions);
+            while let Some(column) = bot.choose(players.for_player(game.current), &game) {
+                game = game.play_on_column(column).unwrap();
+            }
+            game.moves().to_vec()
+        };
+        assert_eq!(game_with(&players), game_with(&players));
+        assert!(!game_with(&players).is_empty());
+        assert_eq!(
+            Players::default()
+                .bot()
+                .choose(PlayerType::Human, &Game::initialise(7, 6, &win_conditions)),
+            None
+        );
+    }
+
+    #[test]
+    fn the_computer_takes_over_from_engines_that_fail() {
+        let win_conditions = default_win_conditions();
+        let players = Players {
+            one: PlayerType::Engine(0),
+            engines: [Some("connect-4-no-such-engine".to_owned()), None],
+            ..Default::default()
+        };
+        let mut bot = players.bot();
+        let game = play(Game::initialise(7, 6, &win_conditions), &[0, 1, 0, 1, 0, 6]);
+        assert_eq!(bot.choose(PlayerType::Engine(0), &game), Some(0));
+        assert!(bot.take_engine_error().is_some());
+        // It isn't started again
+        assert_eq!(bot.choose(PlayerType::Engine(0), &game), Some(0));
+        assert_eq!(bot.take_engine_error(), None);
+    }
+}
@@ -0,0 +1,729 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+    time::Duration,
+};
+
+use super::{
+    game::{Game, GameStatus, Player},
+    json::Json,
+    protocol::side_name,
+    ruleset::{without_line_number, Ruleset},
+    win_conditions::WinCondition,
+};
+
+pub const DEFAULT_HTTP_PORT: u16 = 8080;
+
+// Bodies are a handful of settings or a move, so anything bigger is a mistake
+const MAX_BODY_LENGTH: usize = 64 * 1024;
+
+// The same goes for the request line and headers, each counted with its line ending
+const MAX_LINE_LENGTH: usize = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+
+// The most games kept at once. Creating another forgets the oldest finished game, or the oldest if none are.
+const MAX_GAMES: usize = 1000;
+
+// How long a connection can go quiet before it's dropped
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(PartialEq, Debug)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    // Names are in lower case
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct Response {
+    pub status: u16,
+    // Headers beyond the content type and length, which every response has
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Json,
+}
+
+impl Response {
+    fn new(status: u16, body: Json) -> Self {
+        Response {
+            status,
+            headers: vec![],
+            body,
+        }
+    }
+
+    pub fn to_http(&self) -> String {
+        let body = self.body.to_string();
+        let mut head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.status,
+            reason_phrase(self.status),
+            body.len()
+        );
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        format!("{}\r\n{}", head, body)
+    }
+}
+
+// The settings a game was created with and the moves played in it since
+struct HostedGame {
+    ruleset: Ruleset,
+    moves: Vec<usize>,
+    finished: bool,
+}
+
+impl HostedGame {
+    // The moves were checked as they were played, so replaying them can't fail
+    fn replay<'a>(&self, win_conditions: &'a Vec<Box<dyn WinCondition>>) -> Game<'a> {
+        self.moves.iter().fold(
+            Game::initialise(self.ruleset.columns, self.ruleset.rows, win_conditions)
+                .with_variants(&self.ruleset.variants),
+            |game, &column| {
+                game.play_on_column(column)
+                    .expect("Hosted moves are checked when played")
+            },
+        )
+    }
+}
+
+/* The games behind the HTTP API, numbered from 0 in the order they're created. Only the latest
+1000 are kept, forgetting finished games first.
+Requests arrive here already read, and a response is given back, so this never touches a socket.
+
+  GET  /games               List every game.
+  POST /games               Create a game. The body is optional, and takes the same settings as a ruleset
+                            file, such as {"columns": 9, "win_conditions": ["vertical", "horizontal"]}.
+                            Boards can have at most 20 columns and 20 rows.
+  GET  /games/<id>          The game's board, moves, status and whose turn it is.
+  POST /games/<id>/moves    Play a move, such as {"column": 3}. Columns are zero indexed.
+
+Errors are given as {"error": "<reason>"}. A column that isn't on the board is a 400, and a full
+column or a finished game is a 409.
+*/
+#[derive(Default)]
+pub struct Api {
+    games: BTreeMap<usize, HostedGame>,
+    next_id: usize,
+}
+
+impl Api {
+    pub fn handle(&mut self, request: &Request) -> Response {
+        let path = request.path.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match (request.method.as_str(), &segments[..]) {
+            ("GET", ["games"]) => self.list(),
+            ("POST", ["games"]) => self.create(&request.body),
+            ("GET", ["games", id]) => match self.find(id) {
+                Ok(id) => Response::new(200, self.state(id)),
+                Err(response) => response,
+            },
+            ("POST", ["games", id, "moves"]) => match self.find(id) {
+                Ok(id) => self.play(id, &request.body),
+                Err(response) => response,
+            },
+            (method, ["games"]) => method_not_allowed(method, "GET, POST"),
+            (method, ["games", _]) => method_not_allowed(method, "GET"),
+            (method, ["games", _, "moves"]) => method_not_allowed(method, "POST"),
+            _ => error(404, &format!("There's nothing at <{}>.", path)),
+        }
+    }
+
+    fn list(&self) -> Response {
+        let games = self
+            .games
+            .iter()
+            .map(|(&id, hosted)| {
+                let win_conditions = hosted.ruleset.win_conditions();
+                let game = hosted.replay(&win_conditions);
+                Json::object([
+                    ("id", Json::number(id)),
+                    ("ruleset", Json::string(&hosted.ruleset.name)),
+                    ("status", Json::string(status_name(&game.status))),
+                    ("moves", Json::number(hosted.moves.len())),
+                ])
+            })
+            .collect();
+        Response::new(200, Json::object([("games", Json::Array(games))]))
+    }
+
+    fn create(&mut self, body: &str) -> Response {
+        let ruleset = match parse_ruleset(body) {
+            Ok(ruleset) => ruleset,
+            Err(message) => return error(400, &message),
+        };
+        if self.games.len() >= MAX_GAMES {
+            let oldest = self
+                .games
+                .iter()
+                .find(|(_, hosted)| hosted.finished)
+                .or(self.games.iter().next())
+                .map(|(&id, _)| id);
+            if let Some(oldest) = oldest {
+                self.games.remove(&oldest);
+            }
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.games.insert(
+            id,
+            HostedGame {
+                ruleset,
+                moves: vec![],
+                finished: false,
+            },
+        );
+        let mut response = Response::new(201, self.state(id));
+        response
+            .headers
+            .push(("Location", format!("/games/{}", id)));
+        response
+    }
+
+    fn play(&mut self, id: usize, body: &str) -> Response {
+        let column = match Json::parse(body) {
+            Ok(Json::Object(fields)) => fields
+                .iter()
+                .find(|(key, _)| key == "column")
+                .and_then(|(_, value)| value.usize()),
+            _ => None,
+        };
+        let Some(column) = column else {
+            return error(400, "The body needs a column, such as {\"column\": 3}.");
+        };
+        let hosted = &self.games[&id];
+        let win_conditions = hosted.ruleset.win_conditions();
+        let game = hosted.replay(&win_conditions);
+        if game.status != GameStatus::Started {
+            return error(409, "The game has already finished.");
+        }
+        // A column off the board is a bad request, but a full one only conflicts with the game so far
+        let finished = match game.play_on_column(column) {
+            Ok(game) => game.status != GameStatus::Started,
+            Err(rejected) => {
+                let status = match column < hosted.ruleset.columns {
+                    true => 409,
+                    false => 400,
+                };
+                return error(status, &rejected.message);
+            }
+        };
+        let hosted = self.games.get_mut(&id).expect("The game was found");
+        hosted.moves.push(column);
+        hosted.finished = finished;
+        Response::new(200, self.state(id))
+    }
+
+    fn find(&self, id: &str) -> Result<usize, Response> {
+        match id.parse::<usize>() {
+            Ok(id) if self.games.contains_key(&id) => Ok(id),
+            _ => Err(error(404, &format!("There is no game <{}>.", id))),
+        }
+    }
+
+    fn state(&self, id: usize) -> Json {
+        let hosted = &self.games[&id];
+        let ruleset = &hosted.ruleset;
+        let win_conditions = ruleset.win_conditions();
+        let game = hosted.replay(&win_conditions);
+        let side = |player: Player| match player {
+            Player::None => Json::Null,
+            side => Json::string(side_name(side)),
+        };
+        let board = game
+            .board()
+            .iter()
+            .map(|column| {
+                Json::Array(
+                    column
+                        .iter()
+                        .map(|&cell| match cell {
+                            Player::None => Json::string("none"),
+                            side => Json::string(side_name(side)),
+                        })
+                        .collect(),
+                )
+            })
+            .collect();
+        Json::object([
+            ("id", Json::number(id)),
+            (
+                "ruleset",
+                Json::object([
+                    ("name", Json::string(&ruleset.name)),
+                    ("columns", Json::number(ruleset.columns)),
+                    ("rows", Json::number(ruleset.rows)),
+                    ("connect", Json::number(ruleset.connect_length)),
+                    (
+                        "win_conditions",
+                        Json::Array(
+                            ruleset
+                                .win_conditions
+                                .iter()
+                                .map(|x| Json::string(x))
+                                .collect(),
+                        ),
+                    ),
+                    (
+                        "variants",
+                        Json::Array(
+                            ruleset
+                                .variants
+                                .iter()
+                                .map(|x| Json::string(&x.to_string().to_lowercase()))
+                                .collect(),
+                        ),
+                    ),
+                ]),
+            ),
+            ("status", Json::string(status_name(&game.status))),
+            (
+                "current",
+                match game.status {
+                    GameStatus::Started => side(game.current),
+                    _ => Json::Null,
+                },
+            ),
+            ("winner", game.winner.map_or(Json::Null, side)),
+            (
+                "moves",
+                Json::Array(hosted.moves.iter().map(|&x| Json::number(x)).collect()),
+            ),
+            // A column at a time from the left, each from the bottom up
+            ("board", Json::Array(board)),
+        ])
+    }
+}
+
+/* Reads the settings as if they were the lines of a ruleset file, so both take the same keys and give the
+same errors. Numbers and strings are written as they are, and lists are joined with commas.
+*/
+fn parse_ruleset(body: &str) -> Result<Ruleset, String> {
+    if body.trim().is_empty() {
+        return Ok(Ruleset::default());
+    }
+    let Json::Object(fields) = Json::parse(body)? else {
+        return Err("The body should be an object of settings.".to_owned());
+    };
+    let mut lines = vec![];
+    for (key, value) in &fields {
+        let value = match value {
+            Json::Number(_) => value.usize().map(|x| x.to_string()),
+            Json::String(text) => Some(text.clone()),
+            Json::Array(values) => values
+                .iter()
+                .map(|x| match x {
+                    Json::String(text) => Some(text.clone()),
+                    _ => None,
+                })
+                .collect::<Option<Vec<String>>>()
+                .map(|x| x.join(", ")),
+            _ => None,
+        };
+        match value {
+            Some(value) if !value.contains(['\n', '\r', '#']) => {
+                lines.push(format!("{} = {}", key, value))
+            }
+            _ => {
+                return Err(format!(
+                    "<{}> should be a whole number, a string or a list of strings, on one line and without a '#'.",
+                    key
+                ))
+            }
+        }
+    }
+    Ruleset::parse_hosted(&lines.join("\n"), "Custom").map_err(without_line_number)
+}
+
+fn status_name(status: &GameStatus) -> &'static str {
+    match status {
+        GameStatus::Started => "in_progress",
+        GameStatus::Completed => "won",
+        GameStatus::Draw => "draw",
+    }
+}
+
+fn error(status: u16, message: &str) -> Response {
+    Response::new(status, Json::object([("error", Json::string(message))]))
+}
+
+fn method_not_allowed(method: &str, allowed: &str) -> Response {
+    let mut response = error(
+        405,
+        &format!("<{}> isn't allowed here. Allowed: {}.", method, allowed),
+    );
+    response.headers.push(("Allow", allowed.to_owned()));
+    response
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Content Too Large",
+        431 => "Request Header Fields Too Large",
+        _ => "",
+    }
+}
+
+/* Reads a request line, the headers and a body of Content-Length bytes.
+Anything that can't be read is given as the response to send back instead.
+*/
+pub fn read_request(reader: &mut impl BufRead) -> Result<Request, Response> {
+    let unreadable = || error(400, "Could not read the request.");
+    let mut line = String::new();
+    read_line(reader, &mut line)?;
+    let (method, path) = match line.split_whitespace().collect::<Vec<&str>>()[..] {
+        [method, path, version] if version.starts_with("HTTP/1.") => {
+            (method.to_owned(), path.to_owned())
+        }
+        _ => return Err(unreadable()),
+    };
+
+    let (mut headers, mut content_length) = (vec![], 0);
+    for count in 0.. {
+        if count > MAX_HEADERS {
+            return Err(error(
+                431,
+                &format!("Requests can have at most {} headers.", MAX_HEADERS),
+            ));
+        }
+        if read_line(reader, &mut line)? == 0 {
+            return Err(unreadable());
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let (name, value) = (name.trim().to_lowercase(), value.trim().to_owned());
+            if name == "content-length" {
+                content_length = value.parse().map_err(|_| unreadable())?;
+            }
+            headers.push((name, value));
+        }
+    }
+    if content_length > MAX_BODY_LENGTH {
+        return Err(error(
+            413,
+            &format!("Bodies can be at most {} bytes.", MAX_BODY_LENGTH),
+        ));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(|_| unreadable())?;
+    let body = String::from_utf8(body).map_err(|_| error(400, "The body isn't UTF-8."))?;
+    Ok(Request {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+// Serves the API until the process is stopped, answering each connection on its own thread
+pub fn serve(address: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    println!(
+        "Serving the HTTP API on http://{}. Press Ctrl+C to stop.",
+        listener.local_addr()?
+    );
+    run(listener);
+    Ok(())
+}
+
+// Reads a line of the request's head in place of the last, refusing one that's too long
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> Result<usize, Response> {
+    let unreadable = || error(400, "Could not read the request.");
+    let mut bytes = vec![];
+    io::Read::take(reader, MAX_LINE_LENGTH as u64 + 1)
+        .read_until(b'\n', &mut bytes)
+        .map_err(|_| unreadable())?;
+    if bytes.len() > MAX_LINE_LENGTH {
+        return Err(error(
+            431,
+            &format!(
+                "Request lines and headers can be at most {} bytes.",
+                MAX_LINE_LENGTH
+            ),
+        ));
+    }
+    *line = String::from_utf8(bytes).map_err(|_| unreadable())?;
+    Ok(line.len())
+}
+
+fn run(listener: TcpListener) {
+    let api = Arc::new(Mutex::new(Api::default()));
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let api = Arc::clone(&api);
+        thread::spawn(move || respond(stream, &api));
+    }
+}
+
+// One request per connection, which is closed after the response
+fn respond(mut stream: TcpStream, api: &Mutex<Api>) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let request = match stream.try_clone() {
+        Ok(reader) => read_request(&mut BufReader::new(reader)),
+        Err(_) => return,
+    };
+    let response = match request {
+        // Games only change once a request has been checked, so one that panicked left them as they were
+        Ok(request) => api
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .handle(&request),
+        Err(response) => response,
+    };
+    let _ = stream.write_all(response.to_http().as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        thread,
+    };
+
+    use crate::remove_consts::json::Json;
+
+    use super::{read_request, run, Api, Request, MAX_GAMES};
+
+    fn request(api: &mut Api, method: &str, path: &str, body: &str) -> (u16, Json) {
+        let response = api.handle(&Request {
+            method: method.to_owned(),
+            path: path.to_owned(),
+            headers: vec![],
+            body: body.to_owned(),
+        });
+        (response.status, response.body)
+    }
+
+    fn field<'a>(json: &'a Json, key: &str) -> &'a Json {
+        match json {
+            Json::Object(fields) => &fields.iter().find(|x| x.0 == key).expect("The field").1,
+            _ => panic!("Expected an object"),
+        }
+    }
+
+    #[test]
+    fn creates_games_and_plays_moves() {
+        let mut api = Api::default();
+        let (status, game) = request(
+            &mut api,
+            "POST",
+            "/games",
+            r#"{"columns": 5, "rows": 4, "win_conditions": ["vertical"], "connect": 3}"#,
+        );
+        assert_eq!(status, 201);
+        assert_eq!(field(field(&game, "ruleset"), "columns"), &Json::number(5));
+        assert_eq!(field(&game, "current"), &Json::string("one"));
+        for column in [0, 1, 0, 1] {
+            assert_eq!(
+                request(
+                    &mut api,
+                    "POST",
+                    "/games/0/moves",
+                    &format!("{{\"column\": {}}}", column)
+                )
+                .0,
+                200
+            );
+        }
+        let (_, game) = request(&mut api, "POST", "/games/0/moves", r#"{"column": 0}"#);
+        assert_eq!(field(&game, "status"), &Json::string("won"));
+        assert_eq!(field(&game, "winner"), &Json::string("one"));
+        assert_eq!(field(&game, "current"), &Json::Null);
+        assert_eq!(
+            field(&game, "board").to_string(),
+            r#"[["one","one","one","none"],["two","two","none","none"],["none","none","none","none"],["none","none","none","none"],["none","none","none","none"]]"#
+        );
+
+        request(&mut api, "POST", "/games", "");
+        let (status, list) = request(&mut api, "GET", "/games", "");
+        assert_eq!(status, 200);
+        assert_eq!(
+            list.to_string(),
+            r#"{"games":[{"id":0,"ruleset":"Custom","status":"won","moves":5},{"id":1,"ruleset":"Standard","status":"in_progress","moves":0}]}"#
+        );
+    }
+
+    #[test]
+    fn turns_bad_requests_into_client_errors() {
+        let mut api = Api::default();
+        request(&mut api, "POST", "/games", r#"{"rows": 2}"#);
+        assert_eq!(
+            request(&mut api, "POST", "/games/0/moves", r#"{"column": 7}"#),
+            (
+                400,
+                Json::object([(
+                    "error",
+                    Json::string("Game board does not have that many columns.")
+                )])
+            )
+        );
+        request(&mut api, "POST", "/games/0/moves", r#"{"column": 3}"#);
+        request(&mut api, "POST", "/games/0/moves", r#"{"column": 3}"#);
+        assert_eq!(
+            request(&mut api, "POST", "/games/0/moves", r#"{"column": 3}"#),
+            (
+                409,
+                Json::object([("error", Json::string("Column is full"))])
+            )
+        );
+        assert_eq!(request(&mut api, "POST", "/games/0/moves", "3").0, 400);
+        assert_eq!(request(&mut api, "GET", "/games/1", "").0, 404);
+        assert_eq!(request(&mut api, "GET", "/boards", "").0, 404);
+        assert_eq!(request(&mut api, "DELETE", "/games/0", "").0, 405);
+        assert_eq!(
+            request(
+                &mut api,
+                "POST",
+                "/games",
+                r#"{"win_conditions": ["sideways"]}"#
+            ),
+            (
+                400,
+                Json::object([(
+                    "error",
+                    Json::string("Did not recognise <sideways> as a win condition.")
+                )])
+            )
+        );
+        assert_eq!(
+            request(&mut api, "POST", "/games", r#"{"columns": [1, 2]}"#).0,
+            400
+        );
+        assert_eq!(
+            request(
+                &mut api,
+                "POST",
+                "/games",
+                r#"{"columns": 100000, "rows": 100000}"#
+            )
+            .0,
+            400
+        );
+        assert_eq!(
+            request(&mut api, "POST", "/games", &"[".repeat(60_000)).0,
+            400
+        );
+        for connect in [r#""18446744073709551615""#, "1e30"] {
+            let body = format!(r#"{{"connect": {}}}"#, connect);
+            assert_eq!(request(&mut api, "POST", "/games", &body).0, 400);
+        }
+    }
+
+    #[test]
+    fn forgets_finished_games_first_when_full() {
+        let mut api = Api::default();
+        request(
+            &mut api,
+            "POST",
+            "/games",
+            r#"{"columns": 1, "rows": 1, "connect": 1}"#,
+        );
+        request(&mut api, "POST", "/games", "");
+        request(&mut api, "POST", "/games/0/moves", r#"{"column": 0}"#);
+        for _ in 2..MAX_GAMES {
+            request(&mut api, "POST", "/games", "");
+        }
+        assert_eq!(request(&mut api, "GET", "/games/0", "").0, 200);
+
+        let (status, game) = request(&mut api, "POST", "/games", "");
+        assert_eq!(
+            (status, field(&game, "id")),
+            (201, &Json::number(MAX_GAMES))
+        );
+        assert_eq!(request(&mut api, "GET", "/games/0", "").0, 404);
+        assert_eq!(request(&mut api, "GET", "/games/1", "").0, 200);
+        request(&mut api, "POST", "/games", "");
+        assert_eq!(request(&mut api, "GET", "/games/1", "").0, 404);
+    }
+
+    #[test]
+    fn reads_requests_with_and_without_bodies() {
+        let raw = "POST /games/0/moves HTTP/1.1\r\nHost: localhost\r\ncontent-length: 13\r\n\r\n{\"column\": 3}";
+        let request = read_request(&mut raw.as_bytes()).unwrap();
+        assert_eq!(
+            (request.method.as_str(), request.path.as_str()),
+            ("POST", "/games/0/moves")
+        );
+        assert_eq!(request.header("Host"), Some("localhost"));
+        assert_eq!(request.body, "{\"column\": 3}");
+        assert_eq!(
+            read_request(&mut "GET /games HTTP/1.1\r\n\r\n".as_bytes())
+                .unwrap()
+                .body,
+            ""
+        );
+        assert!(read_request(&mut "GET /games\r\n\r\n".as_bytes()).is_err());
+        let too_long = "POST /games HTTP/1.1\r\nContent-Length: 1000000\r\n\r\n";
+        assert_eq!(
+            read_request(&mut too_long.as_bytes()).unwrap_err().status,
+            413
+        );
+        let long_header = format!(
+            "GET /games HTTP/1.1\r\nCookie: {}\r\n\r\n",
+            "a".repeat(1 << 20)
+        );
+        assert_eq!(
+            read_request(&mut long_header.as_bytes())
+                .unwrap_err()
+                .status,
+            431
+        );
+        let many_headers = format!("GET /games HTTP/1.1\r\n{}\r\n", "X: y\r\n".repeat(1000));
+        assert_eq!(
+            read_request(&mut many_headers.as_bytes())
+                .unwrap_err()
+                .status,
+            431
+        );
+    }
+
+    #[test]
+    fn answers_over_http_on_localhost() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || run(listener));
+        let send = |raw: &str| {
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream.write_all(raw.as_bytes()).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let created = send("POST /games HTTP/1.1\r\nContent-Length: 0\r\n\r\n");
+        assert!(created.starts_with("HTTP/1.1 201 Created\r\n"));
+        assert!(created.contains("\r\nLocation: /games/0\r\n"));
+        let played =
+            send("POST /games/0/moves HTTP/1.1\r\nContent-Length: 12\r\n\r\n{\"column\":3}");
+        assert!(played.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(played.ends_with(r#""moves":[3],"board":[["none","none","none","none","none","none"],["none","none","none","none","none","none"],["none","none","none","none","none","none"],["one","none","none","none","none","none"],["none","none","none","none","none","none"],["none","none","none","none","none","none"],["none","none","none","none","none","none"]]}"#));
+        let missing = send("GET /games/9 HTTP/1.1\r\n\r\n");
+        assert!(missing.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(missing.ends_with(r#"{"error":"There is no game <9>."}"#));
+    }
+}
@@ -0,0 +1,662 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use super::{
+    game::{Game, GameStatus, Player, Variant, DEFAULT_COLUMNS, DEFAULT_ROWS},
+    win_conditions::{default_win_conditions, win_condition_from_name},
+};
+
+pub const DEFAULT_ARCHIVE_PATH: &str = "games.archive";
+
+// Openings are indexed up to this many moves deep. Longer openings are filtered from the deepest index.
+const OPENING_INDEX_DEPTH: usize = 8;
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum Outcome {
+    Won(Player),
+    Draw,
+    Unfinished,
+}
+
+impl Outcome {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        match input.trim().to_lowercase().as_str() {
+            "one" => Ok(Outcome::Won(Player::One)),
+            "two" => Ok(Outcome::Won(Player::Two)),
+            "draw" => Ok(Outcome::Draw),
+            "unfinished" => Ok(Outcome::Unfinished),
+            other => Err(format!(
+                "Did not recognise <{}> as a result. Expected one, two, draw or unfinished.",
+                other
+            )),
+        }
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Won(Player::One) => f.write_str("one"),
+            Outcome::Won(Player::Two) => f.write_str("two"),
+            Outcome::Won(Player::None) => panic!("Game has been won by no player. Invalid state."),
+            Outcome::Draw => f.write_str("draw"),
+            Outcome::Unfinished => f.write_str("unfinished"),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct GameRecord {
+    pub columns: usize,
+    pub rows: usize,
+    // The display names of the win conditions the game was played with
+    pub rules: Vec<String>,
+    // Zero indexed columns, in the order they were played
+    pub moves: Vec<usize>,
+    pub outcome: Outcome,
+    pub variants: Vec<Variant>,
+}
+
+impl GameRecord {
+    pub fn from_game(game: &Game) -> Self {
+        let outcome = match game.status {
+            GameStatus::Started => Outcome::Unfinished,
+            GameStatus::Draw => Outcome::Draw,
+            GameStatus::Completed => Outcome::Won(
+                game.winner
+                    .expect("Game has been win with no winner. Invalid state."),
+            ),
+        };
+        GameRecord {
+            columns: game.board().len(),
+            rows: game.board().first().map_or(0, |column| column.len()),
+            rules: game
+                .win_conditions()
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            moves: game.moves().to_vec(),
+            outcome,
+            variants: game.variants().to_vec(),
+        }
+    }
+
+    // Records are stored one per line as tab separated size, rules, result and moves. For example:
+    // 7x6	Vertical,Horizontal,Forward Diagonal,Reverse Diagonal	two	3,3,2,4
+    // Games played with variants have them in a fifth field.
+    pub fn to_line(&self) -> String {
+        let moves: Vec<String> = self.moves.iter().map(|x| x.to_string()).collect();
+        let mut line = format!(
+            "{}x{}\t{}\t{}\t{}",
+            self.columns,
+            self.rows,
+            self.rules.join(","),
+            self.outcome,
+            moves.join(",")
+        );
+        if !self.variants.is_empty() {
+            let variants: Vec<String> = self.variants.iter().map(|x| x.to_string()).collect();
+            line += &format!("\t{}", variants.join(","));
+        }
+        line
+    }
+
+    pub fn from_line(line: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 4 && fields.len() != 5 {
+            return Err(format!(
+                "Expected 4 or 5 tab separated fields but found {}.",
+                fields.len()
+            ));
+        }
+        let (columns, rows) = parse_size(fields[0])?;
+        let rules = parse_rules(fields[1])?;
+        let outcome = Outcome::parse(fields[2])?;
+        let moves = parse_moves(fields[3])?;
+        let variants = parse_variants(fields.get(4).map_or("", |x| x))?;
+        let record = GameRecord {
+            columns,
+            rows,
+            rules,
+            moves,
+            outcome,
+            variants,
+        };
+        record.positions()?;
+        Ok(record)
+    }
+
+    // The boards reached by the game, starting with the empty board and then one per move
+    pub fn positions(&self) -> Result<Vec<Vec<Vec<Player>>>, String> {
+        replay(self.columns, self.rows, &self.moves)
+    }
+
+    // Whether the game was played on the standard board with the standard rules
+    pub fn has_standard_rules(&self) -> bool {
+        let standard_rules: Vec<String> = default_win_conditions()
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        (self.columns, self.rows) == (DEFAULT_COLUMNS, DEFAULT_ROWS)
+            && self.rules_key() == rules_key(&standard_rules, &[])
+    }
+
+    fn rules_key(&self) -> String {
+        rules_key(&self.rules, &self.variants)
+    }
+}
+
+#[derive(Default)]
+pub struct Query {
+    pub size: Option<(usize, usize)>,
+    pub rules: Option<Vec<String>>,
+    // Games with the rules are only found with exactly these variants, which are none unless given
+    pub variants: Option<Vec<Variant>>,
+    pub outcome: Option<Outcome>,
+    pub opening: Vec<usize>,
+    pub position: Option<Vec<Vec<Player>>>,
+}
+
+impl Query {
+    /* Parses a space separated list of filters, any of which can be left out. For example:
+    size=7x6 rules=standard opening=4-4-4 result=two
+    rules=vertical,horizontal variants=misere position=3,3,4
+
+    `position` is given as the moves that reach it, on the board from `size` or the default board.
+    All columns are zero indexed.
+    */
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut query = Query::default();
+        let mut position_moves = None;
+        for filter in input.split_whitespace() {
+            let (key, value) = filter
+                .split_once('=')
+                .ok_or(format!("Expected <key=value> but found <{}>.", filter))?;
+            match key {
+                "size" => query.size = Some(parse_size(value)?),
+                "rules" => query.rules = Some(parse_rules(value)?),
+                "variants" => query.variants = Some(parse_variants(value)?),
+                "result" => query.outcome = Some(Outcome::parse(value)?),
+                "opening" => query.opening = parse_moves(value)?,
+                "position" => position_moves = Some(parse_moves(value)?),
+                other => return Err(format!("Did not recognise <{}> as a filter.", other)),
+            }
+        }
+        if let Some(moves) = position_moves {
+            let (columns, rows) = query.size.unwrap_or((DEFAULT_COLUMNS, DEFAULT_ROWS));
+            let positions = replay(columns, rows, &moves)?;
+            query.position = positions.last().cloned();
+        }
+        Ok(query)
+    }
+}
+
+pub struct Archive {
+    path: PathBuf,
+    records: Vec<GameRecord>,
+    by_size: HashMap<(usize, usize), Vec<usize>>,
+    by_rules: HashMap<String, Vec<usize>>,
+    by_outcome: HashMap<Outcome, Vec<usize>>,
+    by_opening: HashMap<Vec<usize>, Vec<usize>>,
+    by_position: HashMap<String, Vec<usize>>,
+}
+
+impl Archive {
+    // Loads the archive at the path, or starts an empty one if the file doesn't exist yet
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut archive = Archive {
+            path: path.as_ref().to_owned(),
+            records: vec![],
+            by_size: HashMap::new(),
+            by_rules: HashMap::new(),
+            by_outcome: HashMap::new(),
+            by_opening: HashMap::new(),
+            by_position: HashMap::new(),
+        };
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(archive),
+            Err(error) => return Err(error),
+        };
+        for (number, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let record = GameRecord::from_line(line).map_err(|message| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Line {} of the archive is invalid. {}", number + 1, message),
+                )
+            })?;
+            archive.index(record);
+        }
+        Ok(archive)
+    }
+
+    pub fn records(&self) -> &[GameRecord] {
+        &self.records
+    }
+
+    // Appends the record to the archive file and indexes it
+    pub fn add(&mut self, record: GameRecord) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", record.to_line())?;
+        self.index(record);
+        Ok(())
+    }
+
+    pub fn query(&self, query: &Query) -> Vec<&GameRecord> {
+        let opening_key = &query.opening[..query.opening.len().min(OPENING_INDEX_DEPTH)];
+        let candidates: Vec<Option<&Vec<usize>>> = [
+            query.size.map(|size| self.by_size.get(&size)),
+            query.rules.as_ref().map(|rules| {
+                let variants = query.variants.as_deref().unwrap_or_default();
+                self.by_rules.get(&rules_key(rules, variants))
+            }),
+            query.outcome.map(|outcome| self.by_outcome.get(&outcome)),
+            (!opening_key.is_empty()).then(|| self.by_opening.get(opening_key)),
+            query
+                .position
+                .as_ref()
+                .map(|position| self.by_position.get(&position_key(position))),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let ids: Vec<usize> = if candidates.is_empty() {
+            (0..self.records.len()).collect()
+        } else if candidates.iter().any(|ids| ids.is_none()) {
+            vec![]
+        } else {
+            let mut lists: Vec<&Vec<usize>> = candidates.into_iter().flatten().collect();
+            lists.sort_by_key(|ids| ids.len());
+            lists[0]
+                .iter()
+                .copied()
+                .filter(|id| lists[1..].iter().all(|ids| ids.binary_search(id).is_ok()))
+                .collect()
+        };
+
+        ids.into_iter()
+            .map(|id| &self.records[id])
+            .filter(|record| record.moves.starts_with(&query.opening))
+            .filter(|record| {
+                query
+                    .variants
+                    .as_ref()
+                    .is_none_or(|variants| variants_key(variants) == variants_key(&record.variants))
+            })
+            .collect()
+    }
+
+    fn index(&mut self, record: GameRecord) {
+        let id = self.records.len();
+        self.by_size
+            .entry((record.columns, record.rows))
+            .or_default()
+            .push(id);
+        self.by_rules
+            .entry(record.rules_key())
+            .or_default()
+            .push(id);
+        self.by_outcome.entry(record.outcome).or_default().push(id);
+        for depth in 1..=record.moves.len().min(OPENING_INDEX_DEPTH) {
+            self.by_opening
+                .entry(record.moves[..depth].to_vec())
+                .or_default()
+                .push(id);
+        }
+        let positions = record
+            .positions()
+            .expect("Archived records are validated before being indexed");
+        for position in positions {
+            let ids = self.by_position.entry(position_key(&position)).or_default();
+            // A position is only reached once per game, but guard against indexing a game twice
+            if ids.last() != Some(&id) {
+                ids.push(id);
+            }
+        }
+        self.records.push(record);
+    }
+}
+
+pub fn parse_size(input: &str) -> Result<(usize, usize), String> {
+    let error = || format!("Expected a board size like <7x6> but found <{}>.", input);
+    let (columns, rows) = input.split_once('x').ok_or_else(error)?;
+    let columns = columns.parse().map_err(|_| error())?;
+    let rows = rows.parse().map_err(|_| error())?;
+    Ok((columns, rows))
+}
+
+// Accepts `standard` for the default win conditions or a comma separated list of win condition names
+pub fn parse_rules(input: &str) -> Result<Vec<String>, String> {
+    if input == "standard" {
+        return Ok(default_win_conditions()
+            .iter()
+            .map(|x| x.to_string())
+            .collect());
+    }
+    input
+        .split(',')
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            win_condition_from_name(name)
+                .map(|x| x.to_string())
+                .ok_or(format!("Did not recognise <{}> as a win condition.", name))
+        })
+        .collect()
+}
+
+// Variants are separated by commas, and <none> stands for playing without any
+fn parse_variants(input: &str) -> Result<Vec<Variant>, String> {
+    input
+        .split(',')
+        .filter(|name| !name.is_empty() && *name != "none")
+        .map(|name| {
+            Variant::from_name(name).ok_or(format!("Did not recognise <{}> as a variant.", name))
+        })
+        .collect()
+}
+
+// Moves are zero indexed columns separated by commas or dashes, e.g. <3,3,4> or <3-3-4>
+pub fn parse_moves(input: &str) -> Result<Vec<usize>, String> {
+    input
+        .split([',', '-'])
+        .filter(|column| !column.is_empty())
+        .map(|column| {
+            column
+                .parse()
+                .map_err(|_| format!("The move <{}> could not be parsed as a usize.", column))
+        })
+        .collect()
+}
+
+// Win conditions and variants are compared regardless of the order they were chosen in
+fn rules_key(rules: &[String], variants: &[Variant]) -> String {
+    let mut rules: Vec<String> = rules.iter().map(|x| x.to_lowercase()).collect();
+    rules.sort();
+    format!("{};{}", rules.join(","), variants_key(variants))
+}
+
+fn variants_key(variants: &[Variant]) -> String {
+    let mut variants: Vec<String> = variants
+        .iter()
+        .map(|x| x.to_string().to_lowercase())
+        .collect();
+    variants.sort();
+    variants.join(",")
+}
+
+fn position_key(board: &[Vec<Player>]) -> String {
+    let columns: Vec<String> = board
+        .iter()
+        .map(|column| {
+            column
+                .iter()
+                .map(|cell| match cell {
+                    Player::None => '.',
+                    Player::One => 'x',
+                    Player::Two => 'o',
+                })
+                .collect()
+        })
+        .collect();
+    columns.join("/")
+}
+
+fn replay(columns: usize, rows: usize, moves: &[usize]) -> Result<Vec<Vec<Vec<Player>>>, String> {
+    let mut board = vec![vec![Player::None; rows]; columns];
+    let mut positions = vec![board.clone()];
+    let mut player = Player::One;
+    for (index, &column) in moves.iter().enumerate() {
+        let row = board
+            .get(column)
+            .ok_or(format!(
+                "Move {} is in column {} but the board only has {} columns.",
+                index + 1,
+                column,
+                columns
+            ))?
+            .iter()
+            .position(|cell| *cell == Player::None)
+            .ok_or(format!(
+                "Move {} is in column {} which is full.",
+                index + 1,
+                column
+            ))?;
+        board[column][row] = player;
+        player = match player {
+            Player::One => Player::Two,
+            _ => Player::One,
+        };
+        positions.push(board.clone());
+    }
+    Ok(positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf, process};
+
+    use crate::remove_consts::{
+        game::{Game, Player, Variant},
+        win_conditions::default_win_conditions,
+    };
+
+    use super::{Archive, GameRecord, Outcome, Query};
+
+    fn archive_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("connect-4-{}-{}.archive", name, process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn record(
+        columns: usize,
+        rows: usize,
+        rules: &str,
+        moves: &[usize],
+        outcome: Outcome,
+    ) -> GameRecord {
+        let query = Query::parse(&format!("rules={}", rules)).unwrap();
+        GameRecord {
+            columns,
+            rows,
+            rules: query.rules.unwrap(),
+            moves: moves.to_vec(),
+            outcome,
+            variants: vec![],
+        }
+    }
+
+    #[test]
+    fn records_a_finished_game() {
+        let win_conditions = default_win_conditions();
+        let mut game = Game::initialise(7, 6, &win_conditions);
+        for column in [1, 2, 1, 3, 1, 4, 1] {
+            game = game.play_on_column(column).unwrap();
+        }
+        let record = GameRecord::from_game(&game);
+        assert_eq!((record.columns, record.rows), (7, 6));
+        assert_eq!(record.moves, vec![1, 2, 1, 3, 1, 4, 1]);
+        assert_eq!(record.outcome, Outcome::Won(Player::One));
+        assert_eq!(
+            record.to_line(),
+            "7x6\tVertical,Horizontal,Forward Diagonal,Reverse Diagonal\tone\t1,2,1,3,1,4,1"
+        );
+        assert_eq!(GameRecord::from_line(&record.to_line()).unwrap(), record);
+    }
+
+    #[test]
+    fn records_the_variants_a_game_was_played_with() {
+        let win_conditions = default_win_conditions();
+        let mut game = Game::initialise(7, 6, &win_conditions).with_variants(&[Variant::Misere]);
+        for column in [1, 2, 1, 3, 1, 4, 1] {
+            game = game.play_on_column(column).unwrap();
+        }
+        let record = GameRecord::from_game(&game);
+        assert_eq!(record.outcome, Outcome::Won(Player::Two));
+        assert!(record.to_line().ends_with("\tMisere"));
+        assert_eq!(GameRecord::from_line(&record.to_line()).unwrap(), record);
+    }
+
+    #[test]
+    fn rejects_records_with_impossible_moves() {
+        assert!(GameRecord::from_line("2x1\tVertical\tdraw\t0,0").is_err());
+        assert!(GameRecord::from_line("2x1\tVertical\tdraw\t2").is_err());
+        assert!(GameRecord::from_line("2x1\tDiagonal\tdraw\t0").is_err());
+        assert!(GameRecord::from_line("2x1\tVertical\tdraw").is_err());
+    }
+
+    #[test]
+    fn persists_records_between_openings() {
+        let path = archive_path("persists");
+        let mut archive = Archive::open(&path).unwrap();
+        archive
+            .add(record(7, 6, "standard", &[3, 3], Outcome::Unfinished))
+            .unwrap();
+        archive
+            .add(record(5, 4, "vertical", &[0], Outcome::Unfinished))
+            .unwrap();
+
+        let archive = Archive::open(&path).unwrap();
+        assert_eq!(archive.records().len(), 2);
+        assert_eq!(archive.records()[1].moves, vec![0]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn queries_by_size_rules_opening_and_result() {
+        let path = archive_path("queries");
+        let mut archive = Archive::open(&path).unwrap();
+        let wanted = record(7, 6, "standard", &[4, 4, 4, 3], Outcome::Won(Player::Two));
+        archive.add(wanted.clone()).unwrap();
+        archive
+            .add(record(
+                7,
+                6,
+                "standard",
+                &[4, 4, 4],
+                Outcome::Won(Player::One),
+            ))
+            .unwrap();
+        archive
+            .add(record(
+                7,
+                6,
+                "standard",
+                &[4, 4, 3],
+                Outcome::Won(Player::Two),
+            ))
+            .unwrap();
+        archive
+            .add(record(
+                8,
+                6,
+                "standard",
+                &[4, 4, 4],
+                Outcome::Won(Player::Two),
+            ))
+            .unwrap();
+        archive
+            .add(record(
+                7,
+                6,
+                "vertical",
+                &[4, 4, 4],
+                Outcome::Won(Player::Two),
+            ))
+            .unwrap();
+
+        let query = Query::parse("size=7x6 rules=standard opening=4-4-4 result=two").unwrap();
+        assert_eq!(archive.query(&query), vec![&wanted]);
+
+        // The same rules with a variant are a different game
+        let misere = GameRecord {
+            variants: vec![Variant::Misere],
+            ..record(7, 6, "standard", &[4, 4, 4], Outcome::Won(Player::Two))
+        };
+        archive.add(misere.clone()).unwrap();
+        let query = Query::parse("size=7x6 rules=standard opening=4-4-4 result=two").unwrap();
+        assert_eq!(archive.query(&query), vec![&wanted]);
+        let query = Query::parse("rules=standard variants=misere").unwrap();
+        assert_eq!(archive.query(&query), vec![&misere]);
+        let query = Query::parse("variants=misere").unwrap();
+        assert_eq!(archive.query(&query), vec![&misere]);
+        let query = Query::parse("opening=4-4-4 variants=none").unwrap();
+        assert_eq!(archive.query(&query).len(), 4);
+        assert!(Query::parse("variants=backwards").is_err());
+
+        let query = Query::parse("opening=4").unwrap();
+        assert_eq!(archive.query(&query).len(), 6);
+        let query = Query::parse("").unwrap();
+        assert_eq!(archive.query(&query).len(), 6);
+        let query = Query::parse("size=9x9").unwrap();
+        assert!(archive.query(&query).is_empty());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn queries_openings_deeper_than_the_index() {
+        let path = archive_path("deep");
+        let mut archive = Archive::open(&path).unwrap();
+        let moves = [0, 1, 2, 3, 4, 5, 6, 0, 1, 2];
+        archive
+            .add(record(7, 6, "vertical", &moves, Outcome::Unfinished))
+            .unwrap();
+        let query = Query::parse("opening=0,1,2,3,4,5,6,0,1,2").unwrap();
+        assert_eq!(archive.query(&query).len(), 1);
+        let query = Query::parse("opening=0,1,2,3,4,5,6,0,1,3").unwrap();
+        assert!(archive.query(&query).is_empty());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn queries_by_position_including_transpositions() {
+        let path = archive_path("positions");
+        let mut archive = Archive::open(&path).unwrap();
+        archive
+            .add(record(7, 6, "standard", &[3, 4, 2, 0], Outcome::Unfinished))
+            .unwrap();
+        archive
+            .add(record(7, 6, "standard", &[2, 4, 3, 1], Outcome::Unfinished))
+            .unwrap();
+        archive
+            .add(record(7, 6, "standard", &[4, 3, 2, 1], Outcome::Unfinished))
+            .unwrap();
+
+        let query = Query::parse("position=3,4,2").unwrap();
+        assert_eq!(archive.query(&query).len(), 2);
+        let query = Query::parse("position=3,4,2 opening=2").unwrap();
+        assert_eq!(archive.query(&query).len(), 1);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rules_match_regardless_of_order() {
+        let path = archive_path("rules");
+        let mut archive = Archive::open(&path).unwrap();
+        archive
+            .add(record(
+                7,
+                6,
+                "horizontal,vertical",
+                &[],
+                Outcome::Unfinished,
+            ))
+            .unwrap();
+        let query = Query::parse("rules=Vertical,Horizontal").unwrap();
+        assert_eq!(archive.query(&query).len(), 1);
+        assert_eq!(archive.records()[0].rules, vec!["Horizontal", "Vertical"]);
+        fs::remove_file(path).unwrap();
+    }
+}
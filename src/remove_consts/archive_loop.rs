@@ -0,0 +1,63 @@
+use std::io;
+
+use crate::remove_consts::{
+    archive::{Archive, Query, DEFAULT_ARCHIVE_PATH},
+    game::Game,
+    win_conditions::{win_condition_from_name, WinCondition},
+};
+
+pub fn search() {
+    let stdin = io::stdin();
+    let input = &mut String::new();
+
+    println!("<<Game Archive>>");
+    let archive = match Archive::open(DEFAULT_ARCHIVE_PATH) {
+        Ok(archive) => archive,
+        Err(error) => {
+            eprintln!(
+                "Could not open the archive <{}>: {}",
+                DEFAULT_ARCHIVE_PATH, error
+            );
+            return;
+        }
+    };
+    println!("The archive holds {} games.", archive.records().len());
+
+    loop {
+        println!(
+            "Enter a search such as <size=7x6 rules=standard opening=3-3-3 result=two> or <position=3,3,4 variants=misere>.
+Columns are zero indexed. Leave it blank to return to the main menu."
+        );
+        input.clear();
+        stdin.read_line(input).expect("Error reading from stdio");
+        if input.trim().is_empty() {
+            println!("Returning to the main menu.\n");
+            break;
+        }
+        let query = match Query::parse(input) {
+            Ok(query) => query,
+            Err(message) => {
+                eprintln!("{}", message);
+                continue;
+            }
+        };
+
+        let records = archive.query(&query);
+        for record in &records {
+            let win_conditions: Vec<Box<dyn WinCondition>> = record
+                .rules
+                .iter()
+                .filter_map(|name| win_condition_from_name(name))
+                .collect();
+            let mut game = Game::initialise(record.columns, record.rows, &win_conditions)
+                .with_variants(&record.variants);
+            for &column in &record.moves {
+                game = game
+                    .play_on_column(column)
+                    .expect("Archived records are validated when loaded");
+            }
+            println!("{}{}", game, record.to_line());
+        }
+        println!("Found {} matching games.", records.len());
+    }
+}
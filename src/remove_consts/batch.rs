@@ -0,0 +1,159 @@
+use super::{
+    archive::parse_moves,
+    game::{Game, GameStatus, Player},
+    ruleset::Ruleset,
+    training::encode,
+};
+
+// Exit codes for a batch run. Invalid lines take precedence over illegal moves.
+pub const EXIT_ILLEGAL_MOVE: u8 = 1;
+pub const EXIT_INVALID_LINE: u8 = 2;
+
+#[derive(PartialEq, Debug)]
+pub struct IllegalMove {
+    // One indexed
+    pub number: usize,
+    pub column: usize,
+    pub reason: String,
+}
+
+#[derive(PartialEq, Debug)]
+pub struct BatchResult {
+    pub status: GameStatus,
+    pub winner: Option<Player>,
+    pub moves_played: usize,
+    pub illegal_move: Option<IllegalMove>,
+    // Encoded a column at a time, as in the training data
+    pub position: String,
+}
+
+impl BatchResult {
+    /* A single line of space separated `key=value` fields, for example:
+    line=1 status=won winner=one moves=7 illegal=- position=xxxxbb/ooobbb/...
+    Games stop at their first illegal move, so the status is `illegal`, `illegal` gives the move number,
+    and the position is the one before that move.
+    */
+    pub fn to_line(&self, line_number: usize) -> String {
+        let status = match (&self.illegal_move, &self.status) {
+            (Some(_), _) => "illegal",
+            (None, GameStatus::Started) => "unfinished",
+            (None, GameStatus::Completed) => "won",
+            (None, GameStatus::Draw) => "draw",
+        };
+        let winner = match self.winner {
+            Some(Player::One) => "one",
+            Some(Player::Two) => "two",
+            _ => "-",
+        };
+        let illegal = self
+            .illegal_move
+            .as_ref()
+            .map_or("-".to_owned(), |x| x.number.to_string());
+        format!(
+            "line={} status={} winner={} moves={} illegal={} position={}",
+            line_number, status, winner, self.moves_played, illegal, self.position
+        )
+    }
+}
+
+/* Plays a line of a batch file. Each line is a move list, optionally followed by space separated settings
+in the same form as a ruleset file. For example:
+3,3,4,4,5 columns=9 rows=7 win_conditions=vertical,horizontal connect=5 variants=misere
+Win conditions with spaces in their names can be written with '-' instead, like forward-diagonal.
+Moves are zero indexed, and a move list of '-' plays no moves.
+*/
+pub fn run_line(line: &str) -> Result<BatchResult, String> {
+    let mut tokens = line.split_whitespace();
+    let moves = parse_moves(tokens.next().unwrap_or_default())?;
+    let settings: Vec<&str> = tokens.collect();
+    let ruleset = Ruleset::from_settings(&settings.join(" "), "Batch")?;
+
+    let win_conditions = ruleset.win_conditions();
+    let mut game = Game::initialise(ruleset.columns, ruleset.rows, &win_conditions)
+        .with_variants(&ruleset.variants);
+    let mut illegal_move = None;
+    for (index, &column) in moves.iter().enumerate() {
+        let reason = match game.status {
+            GameStatus::Started => match game.play_on_column(column) {
+                Ok(next) => {
+                    game = next;
+                    continue;
+                }
+                Err(error) => {
+                    game = error.previous_state;
+                    error.message
+                }
+            },
+            _ => "The game had already finished.".to_owned(),
+        };
+        illegal_move = Some(IllegalMove {
+            number: index + 1,
+            column,
+            reason,
+        });
+        break;
+    }
+
+    Ok(BatchResult {
+        status: game.status.clone(),
+        winner: game.winner,
+        moves_played: game.moves().len(),
+        illegal_move,
+        position: encode(game.board()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remove_consts::game::{GameStatus, Player};
+
+    use super::run_line;
+
+    #[test]
+    fn reports_a_won_game() {
+        let result = run_line("0,1,0,1,0,1,0").unwrap();
+        assert_eq!(result.status, GameStatus::Completed);
+        assert_eq!(result.winner, Some(Player::One));
+        assert_eq!(
+            result.to_line(1),
+            "line=1 status=won winner=one moves=7 illegal=- position=xxxxbb/ooobbb/bbbbbb/bbbbbb/bbbbbb/bbbbbb/bbbbbb"
+        );
+    }
+
+    #[test]
+    fn plays_with_the_board_and_rules_on_the_line() {
+        let result =
+            run_line("0-1-0-1 columns=3 rows=3 win_conditions=vertical connect=3").unwrap();
+        assert_eq!(
+            result.to_line(2),
+            "line=2 status=unfinished winner=- moves=4 illegal=- position=xxb/oob/bbb"
+        );
+        let result =
+            run_line("0-1-0-1-0 columns=3 rows=3 win_conditions=vertical connect=3").unwrap();
+        assert_eq!(result.winner, Some(Player::One));
+        assert!(run_line("- columns=4 rows=4 win_conditions=forward-diagonal").is_ok());
+    }
+
+    #[test]
+    fn stops_at_the_first_illegal_move() {
+        let result = run_line("0,0,0,0 rows=2").unwrap();
+        let illegal_move = result.illegal_move.as_ref().unwrap();
+        assert_eq!((illegal_move.number, illegal_move.column), (3, 0));
+        assert_eq!(illegal_move.reason, "Column is full");
+        assert!(result
+            .to_line(1)
+            .contains("status=illegal winner=- moves=2 illegal=3 "));
+
+        let result = run_line("0,1,0,1,0,1,0,1").unwrap();
+        assert_eq!(result.illegal_move.unwrap().number, 8);
+        assert_eq!(result.winner, Some(Player::One));
+    }
+
+    #[test]
+    fn rejects_lines_that_cannot_be_read() {
+        assert!(run_line("0,a").is_err());
+        assert!(run_line("0 columns").is_err());
+        assert!(run_line("0 colour=red").is_err());
+        assert!(run_line("0 rows=3 win_conditions=vertical").is_err());
+    }
+}
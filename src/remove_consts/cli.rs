@@ -0,0 +1,897 @@
+use std::{path::Path, time::Duration};
+
+use crate::{
+    labels::ColumnLabels,
+    locale::Locale,
+    scoreboard::{Scoreboard, DEFAULT_NAMES},
+    stats::SortBy,
+    theme::Theme,
+};
+
+use super::{
+    ai::{PlayerType, Players},
+    api::DEFAULT_HTTP_PORT,
+    archive::parse_moves,
+    clock::TimeControl,
+    game::Variant,
+    protocol::DEFAULT_PORT,
+    ruleset::{Ruleset, DEFAULT_RULES_DIRECTORY},
+    server::DEFAULT_GRACE,
+    web::DEFAULT_WEB_PORT,
+    win_conditions::{win_condition_with_length, DEFAULT_CONNECT_LENGTH},
+};
+
+pub const USAGE: &str = "Usage: connect-4 [command] [options]
+With no arguments, shows the main menu.
+
+Commands:
+  play                  Play a game. This is the default when only options are given.
+  analyze <moves>       Show the position after the moves and the computer's suggested move.
+  solve <moves>         Solve the position after the moves with perfect play. Standard rules only.
+                        Positions early in the game can take minutes to solve.
+  replay <moves>        Show every position of the game, one move at a time.
+  batch [file]          Play every game in a file, or from standard input, and report each result.
+                        Each line is a move list followed by optional ruleset settings,
+                        such as 3,3,4 columns=9 rows=7 win_conditions=vertical,horizontal.
+                        Exits with 1 if any move was illegal, or 2 if any line couldn't be read.
+  tournament            Play computer players against each other and total the results.
+  web [address]         Play in the browser, at http://127.0.0.1:8000 unless given an address.
+                        Games can be played on one screen, online against other players, or watched.
+  serve [address]       Host games over the network, matching players with similar ratings as they
+                        join. Listens on 0.0.0.0:4444 unless given an address. The protocol is
+                        described in the README.
+  connect <address> [game]
+                        Play a game hosted with serve, such as connect 192.168.1.20:4444, against
+                        whoever's waiting, or accept an open game by its number.
+                        Uses --name as the player's name.
+  lobby <address>       Choose an open game on a server to accept.
+  create <address>      Open a game on a server for someone else to accept, with the board and rules
+                        set by the options.
+  watch <address> [game]
+                        Watch a game hosted with serve, choosing from the games being played
+                        unless given its number.
+  correspond <file> [column|new]
+                        Play a game by correspondence through a file, such as one in a shared folder.
+                        new starts the game between --name-one and --name-two, with the rules set by
+                        the options. A column plays the move for --name if it's their turn, and
+                        otherwise the game so far is shown.
+  api [address]         Serve an HTTP API for creating and playing games, with JSON responses. Listens
+                        on 127.0.0.1:8080 unless given an address. The endpoints are in the README.
+  engine                Answer the engine protocol on standard input and output with the computer
+                        player, so other programs can play against it. The protocol is in the README.
+  stats                 Show the leaderboard of every player's finished games.
+  rules [directory]     List and validate the ruleset files in a directory, <rules> by default.
+  help                  Show this message.
+
+Moves are zero indexed columns separated by ',' or '-', such as 3,3,4, whatever the --labels option.
+
+Options:
+  --mode <mode>         normal, rules or custom. Only custom takes the options below. Defaults to custom.
+  --columns <n>         The number of columns.
+  --rows <n>            The number of rows.
+  --connect <n>         How many in a row are needed to win.
+  --rules <rules>       A ruleset file, or win conditions such as vertical,horizontal.
+  --variants <names>    Variants such as misere.
+  --player-one <type>   human, random or computer. Defaults to human.
+  --player-two <type>   human, random or computer. Defaults to human.
+  --engine-one <command>
+                        Play the first player with an engine, a program that speaks the engine
+                        protocol, such as \"connect-4 engine --depth 6\". Sets --player-one.
+  --engine-two <command>
+                        The same for the second player.
+  --movetime <ms>       How long engines have for each move. Defaults to 1000.
+  --depth <n>           How many moves ahead computer players search.
+  --clock <control>     Play against the clock, with minutes and an increment in seconds added after
+                        each move, such as 5+3, or seconds for each move, such as 30/move. Running
+                        out of time loses, or draws if the opponent has no way left to win.
+                        Works with play in every mode, tournament, serve and web.
+  --seed <n>            The seed for random players, so games can be repeated.
+  --games <n>           How many games a tournament plays.
+  --name-one <name>     The name of the first player. Defaults to Player One.
+  --name <name>         The same as --name-one.
+  --name-two <name>     The name of the second player. Defaults to Player Two.
+  --best-of <n>         Play a match of up to n games, taking turns to go first.
+  --theme <theme>       plain, classic, colour-blind, unicode or high-contrast. Defaults to classic,
+                        or the CONNECT4_THEME environment variable. Colour is left out when NO_COLOR
+                        is set or the output isn't a terminal.
+  --labels <labels>     How columns are shown and typed: numbers, one-indexed or letters.
+                        Defaults to numbers, counting from 0.
+  --sort <order>        Sort the leaderboard by rating or win-rate. Defaults to rating.
+  --lang <locale>       The language of the menu and games: en, es or de. Works with every command.
+                        Defaults to the LC_ALL, LC_MESSAGES or LANG environment variables, then en.
+  --accessible          Describe the game in words for screen readers, announcing each move and any
+                        threats. Also set by the CONNECT4_ACCESSIBLE environment variable.
+  --tui                 Play in a full-screen terminal, choosing columns with the arrow keys or mouse.
+  --no-spectators       Stop anyone watching the games a server hosts.
+  --grace <seconds>     How long a server gives players whose connection drops to come back before
+                        they lose the game. Defaults to 60, and 0 ends the game straight away.
+  --web <address>       Also serve the browser board from serve, so browsers and terminals play together.
+  --help                Show this message.";
+
+pub const DEFAULT_TOURNAMENT_GAMES: usize = 10;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Mode {
+    // The original fixed size game
+    Normal,
+    // The fixed size game with a choice of win conditions
+    Rules,
+    // Everything can be customised
+    Custom,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Options {
+    pub mode: Mode,
+    pub ruleset: Ruleset,
+    pub players: Players,
+    pub games: usize,
+    pub names: [String; 2],
+    // Play a best-of-n match rather than one game at a time
+    pub best_of: Option<usize>,
+    // How the stats leaderboard is sorted
+    pub sort: SortBy,
+    // The requested board theme, before checking whether colour is supported
+    pub theme: Option<Theme>,
+    pub labels: ColumnLabels,
+    // Play in the full-screen terminal mode
+    pub tui: bool,
+    // Whether a server lets others watch its games
+    pub spectating: bool,
+    // Where a server also serves the browser board
+    pub web: Option<String>,
+    // How long a server holds a dropped player's seat
+    pub grace: Duration,
+    // Games are untimed without a time control
+    pub clock: Option<TimeControl>,
+    // Whether any option only the custom mode understands was given
+    customised: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            mode: Mode::Custom,
+            ruleset: Ruleset::default(),
+            players: Players::default(),
+            games: DEFAULT_TOURNAMENT_GAMES,
+            names: DEFAULT_NAMES.map(str::to_owned),
+            best_of: None,
+            sort: SortBy::default(),
+            theme: None,
+            labels: ColumnLabels::default(),
+            tui: false,
+            spectating: true,
+            web: None,
+            grace: DEFAULT_GRACE,
+            clock: None,
+            customised: false,
+        }
+    }
+}
+
+impl Options {
+    // A fresh scoreboard for the named players
+    pub fn scoreboard(&self) -> Scoreboard {
+        Scoreboard::new(self.names.clone(), self.best_of)
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum Command {
+    Menu,
+    Help,
+    Rules(String),
+    Play(Options),
+    Analyze(Options, Vec<usize>),
+    Solve(Options, Vec<usize>),
+    Replay(Options, Vec<usize>),
+    // Reads standard input when there's no file
+    Batch(Option<String>),
+    Tournament(Options),
+    // The address to listen on
+    Serve(Options, String),
+    // The address of the server, and the open game to accept if there is one
+    Connect(Options, String, Option<usize>),
+    // The address of the server
+    Lobby(Options, String),
+    // The address of the server, opening a game with the options' rules
+    Create(Options, String),
+    // The address of the server, and the game to watch if it's been chosen already
+    Watch(Options, String, Option<usize>),
+    // The game file, and the column to play or new to start the game
+    Correspond(Options, String, Option<String>),
+    // The address to listen on
+    Api(String),
+    // The address to serve the browser board on
+    Web(Options, String),
+    Engine(Options),
+    Stats(SortBy),
+}
+
+pub fn parse(args: &[&str]) -> Result<Command, String> {
+    let (command, rest) = match args {
+        [] => return Ok(Command::Menu),
+        [first, rest @ ..] if !first.starts_with("--") => (*first, rest),
+        _ => ("play", args),
+    };
+    if rest.contains(&"--help") {
+        return Ok(Command::Help);
+    }
+    let (options, positional) = parse_options(rest)?;
+    let expect_positional = |count: usize| match positional.len() == count {
+        true => Ok(()),
+        false => Err(format!(
+            "<{}> takes {} arguments but was given {}.",
+            command,
+            count,
+            positional.len()
+        )),
+    };
+    let command = match command {
+        "help" => Command::Help,
+        "rules" => match positional[..] {
+            [] => Command::Rules(DEFAULT_RULES_DIRECTORY.to_owned()),
+            [directory] => Command::Rules(directory.to_owned()),
+            _ => return Err("<rules> takes at most 1 directory.".to_owned()),
+        },
+        "play" => {
+            expect_positional(0)?;
+            if options.tui && options.mode != Mode::Custom {
+                return Err("Only --mode custom can be played with --tui.".to_owned());
+            }
+            if options.tui && options.best_of.is_some() {
+                return Err("Matches can't be played with --tui.".to_owned());
+            }
+            Command::Play(options)
+        }
+        "analyze" | "analyse" => {
+            expect_positional(1)?;
+            let moves = parse_moves(positional[0])?;
+            Command::Analyze(options, moves)
+        }
+        "solve" => {
+            expect_positional(1)?;
+            if options.customised {
+                return Err("<solve> only supports the standard board and rules.".to_owned());
+            }
+            let moves = parse_moves(positional[0])?;
+            Command::Solve(options, moves)
+        }
+        "replay" => {
+            expect_positional(1)?;
+            let moves = parse_moves(positional[0])?;
+            Command::Replay(options, moves)
+        }
+        "batch" => match positional[..] {
+            [] => Command::Batch(None),
+            [path] => Command::Batch(Some(path.to_owned())),
+            _ => return Err("<batch> takes at most 1 file.".to_owned()),
+        },
+        "tournament" => {
+            expect_positional(0)?;
+            if options.players.has_human() {
+                return Err(
+                    "Tournaments are between random, computer and engine players. Set --player-one and --player-two."
+                        .to_owned(),
+                );
+            }
+            Command::Tournament(options)
+        }
+        "serve" => match positional[..] {
+            [] => Command::Serve(options, format!("0.0.0.0:{}", DEFAULT_PORT)),
+            [address] => Command::Serve(options, with_default_port(address, DEFAULT_PORT)),
+            _ => return Err("<serve> takes at most 1 address.".to_owned()),
+        },
+        "connect" => match positional[..] {
+            [address] => Command::Connect(options, with_default_port(address, DEFAULT_PORT), None),
+            [address, game] => Command::Connect(
+                options,
+                with_default_port(address, DEFAULT_PORT),
+                Some(parse_game(game)?),
+            ),
+            _ => return Err("<connect> takes an address and optionally a game.".to_owned()),
+        },
+        "lobby" => {
+            expect_positional(1)?;
+            Command::Lobby(options, with_default_port(positional[0], DEFAULT_PORT))
+        }
+        "create" => {
+            expect_positional(1)?;
+            Command::Create(options, with_default_port(positional[0], DEFAULT_PORT))
+        }
+        "watch" => match positional[..] {
+            [address] => Command::Watch(options, with_default_port(address, DEFAULT_PORT), None),
+            [address, game] => Command::Watch(
+                options,
+                with_default_port(address, DEFAULT_PORT),
+                Some(parse_game(game)?),
+            ),
+            _ => return Err("<watch> takes an address and optionally a game.".to_owned()),
+        },
+        "correspond" => match positional[..] {
+            [path] => Command::Correspond(options, path.to_owned(), None),
+            [path, action] => {
+                Command::Correspond(options, path.to_owned(), Some(action.to_owned()))
+            }
+            _ => return Err("<correspond> takes a file and optionally a column or new.".to_owned()),
+        },
+        "web" => match positional[..] {
+            [] => Command::Web(options, format!("127.0.0.1:{}", DEFAULT_WEB_PORT)),
+            [address] => Command::Web(options, with_default_port(address, DEFAULT_WEB_PORT)),
+            _ => return Err("<web> takes at most 1 address.".to_owned()),
+        },
+        "api" => match positional[..] {
+            [] => Command::Api(format!("127.0.0.1:{}", DEFAULT_HTTP_PORT)),
+            [address] => Command::Api(with_default_port(address, DEFAULT_HTTP_PORT)),
+            _ => return Err("<api> takes at most 1 address.".to_owned()),
+        },
+        "engine" => {
+            expect_positional(0)?;
+            Command::Engine(options)
+        }
+        "stats" => {
+            expect_positional(0)?;
+            Command::Stats(options.sort)
+        }
+        other => return Err(format!("Did not recognise <{}> as a command.", other)),
+    };
+    Ok(command)
+}
+
+// Options that apply to every command, including the menu
+#[derive(PartialEq, Debug, Default)]
+pub struct GlobalOptions {
+    pub locale: Option<Locale>,
+    pub accessible: bool,
+}
+
+// Takes out the global options, giving them and the rest of the arguments
+pub fn take_global_options<'a>(args: &[&'a str]) -> Result<(GlobalOptions, Vec<&'a str>), String> {
+    let mut global = GlobalOptions::default();
+    let mut rest = vec![];
+    let mut args = args.iter();
+    while let Some(&arg) = args.next() {
+        match arg {
+            "--accessible" => global.accessible = true,
+            "--lang" => {
+                let value = args.next().ok_or("--lang needs a value.")?;
+                global.locale = Some(Locale::from_name(value).ok_or(format!(
+                    "Did not recognise <{}> as a language. Expected en, es or de.",
+                    value
+                ))?);
+            }
+            _ => rest.push(arg),
+        }
+    }
+    Ok((global, rest))
+}
+
+// Splits the arguments into options and the positional arguments between them
+fn parse_options<'a>(args: &[&'a str]) -> Result<(Options, Vec<&'a str>), String> {
+    let mut options = Options::default();
+    let mut positional = vec![];
+    let mut rules = None;
+    let (mut columns, mut rows, mut connect_length, mut variants) = (None, None, None, None);
+
+    let mut args = args.iter();
+    while let Some(&arg) = args.next() {
+        if !arg.starts_with("--") {
+            positional.push(arg);
+            continue;
+        }
+        if arg == "--tui" {
+            options.tui = true;
+            continue;
+        }
+        if arg == "--no-spectators" {
+            options.spectating = false;
+            continue;
+        }
+        let value = *args
+            .next()
+            .ok_or_else(|| format!("<{}> needs a value.", arg))?;
+        match arg {
+            "--mode" => {
+                options.mode = match value.to_lowercase().as_str() {
+                    "normal" => Mode::Normal,
+                    "rules" => Mode::Rules,
+                    "custom" => Mode::Custom,
+                    other => return Err(format!("Did not recognise <{}> as a mode.", other)),
+                }
+            }
+            "--columns" => columns = Some(parse_count(arg, value)?),
+            "--rows" => rows = Some(parse_count(arg, value)?),
+            "--connect" => connect_length = Some(parse_count(arg, value)?),
+            "--rules" => rules = Some(value),
+            "--variants" => {
+                variants = Some(
+                    split_list(value)
+                        .map(|name| {
+                            Variant::from_name(name)
+                                .ok_or(format!("Did not recognise <{}> as a variant.", name))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                )
+            }
+            "--player-one" => options.players.one = parse_player_type(value)?,
+            "--player-two" => options.players.two = parse_player_type(value)?,
+            "--engine-one" => {
+                options.players.one = PlayerType::Engine(0);
+                options.players.engines[0] = Some(parse_command(arg, value)?);
+            }
+            "--engine-two" => {
+                options.players.two = PlayerType::Engine(1);
+                options.players.engines[1] = Some(parse_command(arg, value)?);
+            }
+            "--movetime" => {
+                options.players.movetime =
+                    Duration::from_millis(parse_count(arg, value)? as u64)
+            }
+            "--depth" => options.players.depth = parse_count(arg, value)?,
+            "--clock" => options.clock = Some(TimeControl::parse(value)?),
+            "--grace" => {
+                options.grace = Duration::from_secs(value.parse().map_err(|_| {
+                    format!("The grace <{}> could not be parsed as seconds.", value)
+                })?)
+            }
+            "--seed" => {
+                options.players.seed = Some(value.parse().map_err(|_| {
+                    format!("The seed <{}> could not be parsed as a number.", value)
+                })?)
+            }
+            "--games" => options.games = parse_count(arg, value)?,
+            "--name-one" | "--name" => options.names[0] = parse_name(arg, value)?,
+            "--name-two" => options.names[1] = parse_name(arg, value)?,
+            "--best-of" => options.best_of = Some(parse_count(arg, value)?),
+            "--sort" => {
+                options.sort = SortBy::from_name(value).ok_or(format!(
+                    "Did not recognise <{}> as a sort order. Expected rating or win-rate.",
+                    value
+                ))?
+            }
+            "--theme" => {
+                options.theme = Some(Theme::from_name(value).ok_or(format!(
+                    "Did not recognise <{}> as a theme. Expected plain, classic, colour-blind, unicode or high-contrast.",
+                    value
+                ))?)
+            }
+            "--web" => options.web = Some(with_default_port(value, DEFAULT_WEB_PORT)),
+            "--labels" => {
+                options.labels = ColumnLabels::from_name(value).ok_or(format!(
+                    "Did not recognise <{}> as column labels. Expected numbers, one-indexed or letters.",
+                    value
+                ))?
+            }
+            other => return Err(format!("Did not recognise <{}> as an option.", other)),
+        }
+        if ![
+            "--mode",
+            "--games",
+            "--theme",
+            "--labels",
+            "--name-one",
+            "--name-two",
+            "--best-of",
+            "--sort",
+            "--web",
+            "--clock",
+        ]
+        .contains(&arg)
+        {
+            options.customised = true;
+        }
+    }
+
+    // A ruleset file sets everything, and the other options adjust it
+    let mut ruleset = match rules {
+        Some(path) if Path::new(path).is_file() => Ruleset::load(path)?,
+        Some(names) => Ruleset {
+            name: "Custom".to_owned(),
+            win_conditions: split_list(names)
+                .map(|name| {
+                    win_condition_with_length(name, DEFAULT_CONNECT_LENGTH)
+                        .map(|x| x.to_string())
+                        .ok_or(format!(
+                            "Did not recognise <{}> as a win condition or a ruleset file.",
+                            name
+                        ))
+                })
+                .collect::<Result<_, _>>()?,
+            ..Default::default()
+        },
+        None => Ruleset::default(),
+    };
+    if columns.is_some() || rows.is_some() || connect_length.is_some() || variants.is_some() {
+        ruleset.name = "Custom".to_owned();
+    }
+    ruleset.columns = columns.unwrap_or(ruleset.columns);
+    ruleset.rows = rows.unwrap_or(ruleset.rows);
+    ruleset.connect_length = connect_length.unwrap_or(ruleset.connect_length);
+    ruleset.variants = variants.unwrap_or(ruleset.variants);
+    ruleset.validate()?;
+    options.ruleset = ruleset;
+
+    if options.mode != Mode::Custom && options.customised {
+        return Err("Only --mode custom can change the board, rules or players.".to_owned());
+    }
+    Ok((options, positional))
+}
+
+fn parse_name(option: &str, value: &str) -> Result<String, String> {
+    match value.trim() {
+        "" => Err(format!("{} needs a name.", option)),
+        name => Ok(name.to_owned()),
+    }
+}
+
+fn parse_command(option: &str, value: &str) -> Result<String, String> {
+    match value.trim() {
+        "" => Err(format!("{} needs a command.", option)),
+        command => Ok(command.to_owned()),
+    }
+}
+
+fn parse_count(option: &str, value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!(
+            "The value <{}> for {} could not be parsed as a number above 0.",
+            value, option
+        )),
+    }
+}
+
+fn parse_player_type(value: &str) -> Result<PlayerType, String> {
+    PlayerType::from_name(value).ok_or(format!(
+        "Did not recognise <{}> as a player type. Expected human, random or computer.",
+        value
+    ))
+}
+
+// Addresses without a port use the default one, so a bare host name or IP address is enough
+fn with_default_port(address: &str, port: u16) -> String {
+    match address.contains(':') {
+        true => address.to_owned(),
+        false => format!("{}:{}", address, port),
+    }
+}
+
+fn parse_game(game: &str) -> Result<usize, String> {
+    game.parse()
+        .map_err(|_| format!("The game <{}> could not be parsed as a number.", game))
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|x| !x.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        labels::ColumnLabels,
+        locale::Locale,
+        remove_consts::{ai::PlayerType, clock::TimeControl, game::Variant, ruleset::Ruleset},
+        stats::SortBy,
+        theme::HIGH_CONTRAST,
+    };
+
+    use super::{parse, take_global_options, Command, GlobalOptions, Mode, Options};
+
+    #[test]
+    fn shows_the_menu_without_arguments() {
+        assert_eq!(parse(&[]).unwrap(), Command::Menu);
+        assert_eq!(parse(&["help"]).unwrap(), Command::Help);
+        assert_eq!(
+            parse(&["play", "--columns", "9", "--help"]).unwrap(),
+            Command::Help
+        );
+    }
+
+    #[test]
+    fn takes_global_options_from_any_command() {
+        assert_eq!(
+            take_global_options(&[]).unwrap(),
+            (GlobalOptions::default(), vec![])
+        );
+        assert_eq!(
+            take_global_options(&["play", "--lang", "de_DE", "--columns", "9", "--accessible"])
+                .unwrap(),
+            (
+                GlobalOptions {
+                    locale: Some(Locale::German),
+                    accessible: true
+                },
+                vec!["play", "--columns", "9"]
+            )
+        );
+        assert!(take_global_options(&["--lang", "fr"]).is_err());
+        assert!(take_global_options(&["--lang"]).is_err());
+    }
+
+    #[test]
+    fn serves_and_connects_on_the_default_port() {
+        let Command::Serve(_, address) = parse(&["serve"]).unwrap() else {
+            panic!("Expected to serve");
+        };
+        assert_eq!(address, "0.0.0.0:4444");
+        let Command::Connect(options, address, None) =
+            parse(&["connect", "192.168.1.20", "--name", "Ada"]).unwrap()
+        else {
+            panic!("Expected to connect");
+        };
+        assert_eq!(address, "192.168.1.20:4444");
+        assert_eq!(options.names[0], "Ada");
+        assert!(parse(&["connect"]).is_err());
+        let Command::Serve(options, _) = parse(&["serve", "--no-spectators"]).unwrap() else {
+            panic!("Expected to serve");
+        };
+        assert!(!options.spectating);
+        assert_eq!(options.grace, Duration::from_secs(60));
+        let Command::Serve(options, _) = parse(&["serve", "--grace", "0"]).unwrap() else {
+            panic!("Expected to serve");
+        };
+        assert!(options.grace.is_zero());
+        assert!(parse(&["serve", "--grace", "soon"]).is_err());
+        assert!(matches!(
+            parse(&["watch", "localhost", "2"]).unwrap(),
+            Command::Watch(_, address, Some(2)) if address == "localhost:4444"
+        ));
+        assert!(parse(&["watch", "localhost", "two"]).is_err());
+        let Command::Serve(options, _) = parse(&["serve", "--web", "0.0.0.0"]).unwrap() else {
+            panic!("Expected to serve");
+        };
+        assert_eq!(options.web.as_deref(), Some("0.0.0.0:8000"));
+        assert!(matches!(
+            parse(&["web"]).unwrap(),
+            Command::Web(_, address) if address == "127.0.0.1:8000"
+        ));
+        assert_eq!(
+            parse(&["api"]).unwrap(),
+            Command::Api("127.0.0.1:8080".to_owned())
+        );
+        assert_eq!(
+            parse(&["api", "0.0.0.0"]).unwrap(),
+            Command::Api("0.0.0.0:8080".to_owned())
+        );
+    }
+
+    #[test]
+    fn plays_by_correspondence() {
+        assert!(matches!(
+            parse(&["correspond", "ada-grace.c4", "3", "--name", "Ada"]).unwrap(),
+            Command::Correspond(options, path, Some(column))
+                if path == "ada-grace.c4" && column == "3" && options.names[0] == "Ada"
+        ));
+        assert!(matches!(
+            parse(&["correspond", "ada-grace.c4"]).unwrap(),
+            Command::Correspond(_, _, None)
+        ));
+        assert!(parse(&["correspond"]).is_err());
+    }
+
+    #[test]
+    fn finds_games_in_the_lobby() {
+        assert!(matches!(
+            parse(&["connect", "localhost", "3"]).unwrap(),
+            Command::Connect(_, address, Some(3)) if address == "localhost:4444"
+        ));
+        assert!(parse(&["connect", "localhost", "three"]).is_err());
+        assert!(matches!(
+            parse(&["lobby", "localhost"]).unwrap(),
+            Command::Lobby(_, address) if address == "localhost:4444"
+        ));
+        let Command::Create(options, _) = parse(&[
+            "create",
+            "localhost",
+            "--columns",
+            "9",
+            "--rules",
+            "vertical",
+        ])
+        .unwrap() else {
+            panic!("Expected to create a game");
+        };
+        assert_eq!(options.ruleset.columns, 9);
+        assert_eq!(options.ruleset.win_conditions, vec!["Vertical".to_owned()]);
+        assert!(parse(&["create"]).is_err());
+    }
+
+    #[test]
+    fn plays_with_options_alone() {
+        let Command::Play(options) = parse(&["--columns", "9", "--rows", "7"]).unwrap() else {
+            panic!("Expected to play");
+        };
+        assert_eq!((options.ruleset.columns, options.ruleset.rows), (9, 7));
+        assert_eq!(options.ruleset.name, "Custom");
+        assert_eq!(options.mode, Mode::Custom);
+        assert!(!options.tui);
+
+        let Command::Play(options) = parse(&["--tui", "--player-two", "computer"]).unwrap() else {
+            panic!("Expected to play");
+        };
+        assert!(options.tui);
+        assert_eq!(options.players.two, PlayerType::Computer);
+        assert!(parse(&["play", "--tui", "--mode", "normal"]).is_err());
+    }
+
+    #[test]
+    fn parses_rules_players_and_seeds() {
+        let command = parse(&[
+            "tournament",
+            "--rules",
+            "vertical,horizontal",
+            "--connect",
+            "5",
+            "--variants",
+            "misere",
+            "--player-one",
+            "computer",
+            "--player-two",
+            "random",
+            "--depth",
+            "3",
+            "--seed",
+            "42",
+            "--games",
+            "4",
+            "--theme",
+            "high-contrast",
+            "--labels",
+            "one-indexed",
+            "--name-one",
+            "Ada",
+            "--name-two",
+            "Grace",
+            "--best-of",
+            "5",
+        ])
+        .unwrap();
+        let Command::Tournament(options) = command else {
+            panic!("Expected a tournament");
+        };
+        assert_eq!(
+            options.ruleset.win_conditions,
+            vec!["Vertical", "Horizontal"]
+        );
+        assert_eq!(options.ruleset.connect_length, 5);
+        assert_eq!(options.ruleset.variants, vec![Variant::Misere]);
+        assert_eq!(options.players.one, PlayerType::Computer);
+        assert_eq!(options.players.two, PlayerType::Random);
+        assert_eq!(options.players.depth, 3);
+        assert_eq!(options.players.seed, Some(42));
+        assert_eq!(options.games, 4);
+        assert_eq!(options.theme, Some(HIGH_CONTRAST));
+        assert_eq!(options.labels, ColumnLabels::OneIndexed);
+        assert_eq!(options.names, ["Ada", "Grace"]);
+        assert_eq!(options.scoreboard().best_of, Some(5));
+    }
+
+    #[test]
+    fn runs_engines_as_players() {
+        let command = parse(&[
+            "tournament",
+            "--engine-one",
+            "connect-4 engine --depth 6",
+            "--player-two",
+            "computer",
+            "--movetime",
+            "250",
+        ])
+        .unwrap();
+        let Command::Tournament(options) = command else {
+            panic!("Expected a tournament");
+        };
+        assert_eq!(options.players.one, PlayerType::Engine(0));
+        assert_eq!(
+            options.players.engines,
+            [Some("connect-4 engine --depth 6".to_owned()), None]
+        );
+        assert_eq!(options.players.movetime, Duration::from_millis(250));
+        assert!(matches!(
+            parse(&["engine", "--depth", "5"]),
+            Ok(Command::Engine(_))
+        ));
+        assert!(parse(&["--engine-two", " "]).is_err());
+        assert!(parse(&["engine", "extra"]).is_err());
+    }
+
+    #[test]
+    fn plays_against_the_clock() {
+        let Command::Play(options) = parse(&["--clock", "5+3"]).unwrap() else {
+            panic!("Expected to play");
+        };
+        assert_eq!(
+            options.clock,
+            Some(TimeControl::Increment {
+                base: Duration::from_secs(300),
+                increment: Duration::from_secs(3)
+            })
+        );
+        let Command::Serve(options, _) = parse(&["serve", "--clock", "30/move"]).unwrap() else {
+            panic!("Expected to serve");
+        };
+        assert_eq!(
+            options.clock,
+            Some(TimeControl::PerMove(Duration::from_secs(30)))
+        );
+        assert!(parse(&["--clock", "soon"]).is_err());
+        let Command::Play(options) = parse(&["--mode", "normal", "--clock", "5+3"]).unwrap() else {
+            panic!("Expected to play");
+        };
+        assert_eq!(options.mode, Mode::Normal);
+        assert!(options.clock.is_some());
+    }
+
+    #[test]
+    fn loads_rules_from_a_ruleset_file() {
+        let path = std::env::temp_dir().join("connect-4-cli-test.rules");
+        std::fs::write(&path, "name = Wide\ncolumns = 10").unwrap();
+        let path = path.to_string_lossy().into_owned();
+        let Command::Play(options) = parse(&["play", "--rules", &path, "--rows", "8"]).unwrap()
+        else {
+            panic!("Expected to play");
+        };
+        assert_eq!(
+            options.ruleset,
+            Ruleset {
+                name: "Custom".to_owned(),
+                columns: 10,
+                rows: 8,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn parses_moves_for_commands_that_take_them() {
+        assert_eq!(
+            parse(&["solve", "3-3-4"]).unwrap(),
+            Command::Solve(Options::default(), vec![3, 3, 4])
+        );
+        assert_eq!(
+            parse(&["replay", "0,1"]).unwrap(),
+            Command::Replay(Options::default(), vec![0, 1])
+        );
+        assert!(parse(&["analyze"]).is_err());
+        assert!(parse(&["analyze", "1,x"]).is_err());
+        assert!(parse(&["solve", "3", "--columns", "8"]).is_err());
+    }
+
+    #[test]
+    fn rejects_mistakes() {
+        assert!(parse(&["dance"]).is_err());
+        assert!(parse(&["play", "--colour", "red"]).is_err());
+        assert!(parse(&["play", "--columns"]).is_err());
+        assert!(parse(&["play", "--columns", "0"]).is_err());
+        assert!(parse(&["play", "--rules", "sideways"]).is_err());
+        assert!(parse(&["play", "--player-one", "robot"]).is_err());
+        assert!(parse(&["play", "--theme", "neon"]).is_err());
+        assert!(parse(&["play", "--mode", "normal", "--theme", "unicode"]).is_ok());
+        assert!(parse(&["play", "--labels", "roman"]).is_err());
+        assert_eq!(
+            parse(&["stats", "--sort", "win-rate"]),
+            Ok(Command::Stats(SortBy::WinRate))
+        );
+        assert!(parse(&["stats", "--sort", "age"]).is_err());
+        assert!(parse(&["play", "--name-one", " "]).is_err());
+        assert!(parse(&["play", "--best-of", "0"]).is_err());
+        assert!(parse(&["play", "--tui", "--best-of", "3"]).is_err());
+        assert!(parse(&["play", "--mode", "normal", "--best-of", "3"]).is_ok());
+        assert!(parse(&["play", "--mode", "rules", "--labels", "letters"]).is_ok());
+        assert!(parse(&["play", "--mode", "normal", "--columns", "9"]).is_err());
+        assert!(parse(&["play", "--rows", "3", "--rules", "vertical"]).is_err());
+        assert!(parse(&["tournament"]).is_err());
+        assert!(parse(&["batch", "one", "two"]).is_err());
+        assert_eq!(
+            parse(&["batch", "games.txt"]).unwrap(),
+            Command::Batch(Some("games.txt".to_owned()))
+        );
+        assert_eq!(
+            parse(&["rules", "elsewhere"]).unwrap(),
+            Command::Rules("elsewhere".to_owned())
+        );
+    }
+}
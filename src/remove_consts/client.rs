@@ -0,0 +1,609 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpStream, ToSocketAddrs},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    accessible,
+    labels::ColumnLabels,
+    locale,
+    theme::Theme,
+    turn::{self, TurnCommand},
+};
+
+use super::{
+    clock,
+    game::Player,
+    protocol::{ClientMessage, Reason, ServerMessage, PROTOCOL_VERSION},
+    ruleset::Ruleset,
+};
+
+// How often a dropped player tries to reach the server again
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(2);
+
+/* Whether the client plays, and how it finds an opponent, or watches a game. A player can be matched
+with whoever's waiting, open a game with their own rules, or accept a game someone else opened.
+Without a game number the player picks from a list.
+*/
+pub enum Role {
+    Play(String),
+    Create(String, Ruleset),
+    Accept(String, Option<usize>),
+    Watch(Option<usize>),
+}
+
+impl Role {
+    fn name(&self) -> Option<&str> {
+        match self {
+            Role::Play(name) | Role::Create(name, _) | Role::Accept(name, _) => Some(name),
+            Role::Watch(_) => None,
+        }
+    }
+
+    // What's sent to start looking for a game, and again after each one
+    fn opening(&self) -> ClientMessage {
+        match self {
+            Role::Play(name) => ClientMessage::Join(name.clone()),
+            Role::Create(name, ruleset) => {
+                ClientMessage::Create(name.clone(), Some(ruleset.clone()))
+            }
+            Role::Accept(name, Some(game)) => ClientMessage::Accept(*game, name.clone()),
+            Role::Accept(_, None) => ClientMessage::Lobby,
+            Role::Watch(Some(game)) => ClientMessage::Watch(*game),
+            Role::Watch(None) => ClientMessage::Games,
+        }
+    }
+}
+
+// What the client knows about the game in progress, all of it learnt from the server
+struct Seat {
+    // Nobody's side when watching
+    side: Player,
+    columns: usize,
+    // Player one's name, then player two's
+    names: [String; 2],
+    board: Vec<Vec<Player>>,
+    moves: Vec<usize>,
+    // The last move, until the board after it arrives
+    last_move: Option<usize>,
+}
+
+impl Seat {
+    fn names(&self) -> [&str; 2] {
+        [&self.names[0], &self.names[1]]
+    }
+
+    fn name(&self, side: Player) -> &str {
+        match side {
+            Player::Two => &self.names[1],
+            _ => &self.names[0],
+        }
+    }
+}
+
+/* Plays or watches on a server started with `serve`, drawing the board just as a local game would.
+The server decides everything, so the client only shows what it's told and sends the player's moves.
+If the connection drops mid-game, the client keeps trying to reconnect for as long as the server
+holds the seat, and then resumes the game where it was.
+*/
+pub fn connect(address: &str, role: Role, theme: &Theme, labels: ColumnLabels) -> io::Result<()> {
+    let mut stream = TcpStream::connect(address)?;
+    let input = &mut String::new();
+
+    let mut seat: Option<Seat> = None;
+    // The token and grace period for the game in progress
+    let mut session: Option<(String, Duration)> = None;
+    // The games to choose from, once the server has listed as many as it said it would
+    let (mut listing, mut listed) = (vec![], 0);
+    let mut open_games = vec![];
+    loop {
+        let mut writer = stream.try_clone()?;
+        for line in BufReader::new(stream).lines().map_while(Result::ok) {
+            let message = match ServerMessage::parse(&line) {
+                Ok(message) => message,
+                Err(message) => {
+                    eprintln!("{}", message);
+                    continue;
+                }
+            };
+            match message {
+                ServerMessage::Hello(version) if version != PROTOCOL_VERSION => {
+                    eprintln!(
+                        "{}",
+                        locale::text(
+                            "net.version",
+                            &[("server", &version), ("client", &PROTOCOL_VERSION)]
+                        )
+                    );
+                    return Ok(());
+                }
+                ServerMessage::Hello(_) => {
+                    let opening = match &session {
+                        Some((token, _)) => ClientMessage::Resume(token.clone()),
+                        None => role.opening(),
+                    };
+                    if send(&mut writer, opening).is_err() {
+                        break;
+                    }
+                }
+                ServerMessage::Waiting => println!("{}", locale::text("net.waiting", &[])),
+                ServerMessage::Opened(game) => {
+                    println!("{}", locale::text("net.opened", &[("game", &game)]))
+                }
+                // Nobody accepted the game in time
+                ServerMessage::Cancelled => {
+                    println!("{}", locale::text("net.cancelled", &[]));
+                    if !play_again(&mut writer, &role, input)? {
+                        return Ok(());
+                    }
+                }
+                ServerMessage::Lobby(0) => {
+                    println!("{}", locale::text("net.no_open_games", &[]));
+                    return send(&mut writer, ClientMessage::Quit);
+                }
+                ServerMessage::Lobby(count) => (open_games, listed) = (vec![], count),
+                ServerMessage::OpenGame {
+                    game,
+                    rating,
+                    ruleset,
+                } => {
+                    open_games.push((game, rating, ruleset));
+                    if open_games.len() < listed {
+                        continue;
+                    }
+                    match (choose_open_game(&open_games, input), role.name()) {
+                        (Some(game), Some(name)) => {
+                            send(&mut writer, ClientMessage::Accept(game, name.to_owned()))?
+                        }
+                        _ => return send(&mut writer, ClientMessage::Quit),
+                    }
+                }
+                // The board already shows the win, so the line is only for the browser to highlight
+                ServerMessage::Line(_) => (),
+                ServerMessage::Start {
+                    side,
+                    columns,
+                    rows,
+                    opponent,
+                } => {
+                    let key = match side {
+                        Player::One => "net.start_first",
+                        _ => "net.start_second",
+                    };
+                    println!("{}", locale::text(key, &[("opponent", &opponent)]));
+                    let Some(name) = role.name().map(str::to_owned) else {
+                        continue;
+                    };
+                    let names = match side {
+                        Player::One => [name, opponent],
+                        _ => [opponent, name],
+                    };
+                    seat = Some(Seat {
+                        side,
+                        columns,
+                        names,
+                        board: vec![vec![Player::None; rows]; columns],
+                        moves: vec![],
+                        last_move: None,
+                    });
+                }
+                ServerMessage::Session(token, grace) => session = Some((token, grace)),
+                ServerMessage::Resumed {
+                    game: _,
+                    side,
+                    columns,
+                    rows,
+                    names,
+                } => {
+                    println!("{}", locale::text("net.resumed", &[]));
+                    seat = Some(Seat {
+                        side,
+                        columns,
+                        names,
+                        board: vec![vec![Player::None; rows]; columns],
+                        moves: vec![],
+                        last_move: None,
+                    });
+                }
+                ServerMessage::Away(side, grace) => {
+                    let Some(seat) = seat.as_ref() else {
+                        continue;
+                    };
+                    println!(
+                        "{}",
+                        locale::plural(
+                            "net.away",
+                            grace.as_secs() as usize,
+                            &[("name", &seat.name(side))]
+                        )
+                    );
+                }
+                ServerMessage::Back(side) => {
+                    let Some(seat) = seat.as_ref() else {
+                        continue;
+                    };
+                    println!(
+                        "{}",
+                        locale::text("net.back", &[("name", &seat.name(side))])
+                    );
+                }
+                ServerMessage::Games(0) => {
+                    println!("{}", locale::text("net.no_games", &[]));
+                    return send(&mut writer, ClientMessage::Quit);
+                }
+                ServerMessage::Games(count) => (listing, listed) = (vec![], count),
+                ServerMessage::Game { game, moves, names } => {
+                    listing.push((game, moves, names));
+                    if listing.len() < listed {
+                        continue;
+                    }
+                    match choose_game(&listing, input) {
+                        Some(game) => send(&mut writer, ClientMessage::Watch(game))?,
+                        None => return send(&mut writer, ClientMessage::Quit),
+                    }
+                }
+                ServerMessage::Watching {
+                    game: _,
+                    columns,
+                    rows,
+                    names,
+                } => {
+                    println!(
+                        "{}",
+                        locale::text("net.watching", &[("one", &names[0]), ("two", &names[1])])
+                    );
+                    seat = Some(Seat {
+                        side: Player::None,
+                        columns,
+                        names,
+                        board: vec![vec![Player::None; rows]; columns],
+                        moves: vec![],
+                        last_move: None,
+                    });
+                }
+                ServerMessage::History(moves) => {
+                    let Some(seat) = seat.as_mut() else {
+                        continue;
+                    };
+                    seat.moves = moves;
+                    if !seat.moves.is_empty() {
+                        println!("{}", turn::history(&seat.moves, seat.names(), labels));
+                    }
+                }
+                ServerMessage::Spectators(count) => {
+                    println!("{}", locale::plural("net.spectators", count, &[]))
+                }
+                ServerMessage::Board(board) => {
+                    let Some(seat) = seat.as_mut() else {
+                        continue;
+                    };
+                    seat.board = board;
+                    // In the accessible mode the move is announced rather than the board redrawn
+                    match (seat.last_move.take(), accessible::enabled()) {
+                        (Some(column), true) => println!(
+                            "{}",
+                            accessible::announce_move(
+                                &seat.board,
+                                column,
+                                labels,
+                                seat.names(),
+                                Player::None,
+                                None
+                            )
+                        ),
+                        _ => println!(
+                            "{}",
+                            accessible::present_board(&seat.board, theme, labels, seat.names())
+                        ),
+                    }
+                }
+                ServerMessage::Moved(side, column) => {
+                    let Some(seat) = seat.as_mut() else {
+                        continue;
+                    };
+                    seat.moves.push(column);
+                    seat.last_move = Some(column);
+                    if side != seat.side && !accessible::enabled() {
+                        println!(
+                            "{}",
+                            locale::text(
+                                "net.opponent_moved",
+                                &[
+                                    ("name", &seat.name(side)),
+                                    ("column", &labels.label(column))
+                                ]
+                            )
+                        );
+                    }
+                }
+                ServerMessage::Clock(times) => {
+                    let Some(seat) = seat.as_ref() else {
+                        continue;
+                    };
+                    println!("{}", clock::describe_times(seat.names(), times));
+                }
+                ServerMessage::Turn(side) => {
+                    let Some(seat) = seat.as_ref() else {
+                        continue;
+                    };
+                    if side != seat.side {
+                        println!(
+                            "{}",
+                            locale::text("net.opponent_turn", &[("name", &seat.name(side))])
+                        );
+                        continue;
+                    }
+                    // A move that doesn't reach the server is asked for again once the game resumes
+                    match ask_for_move(seat, theme, labels, input) {
+                        Some(message) => {
+                            if send(&mut writer, message).is_err() {
+                                break;
+                            }
+                        }
+                        None => return send(&mut writer, ClientMessage::Quit),
+                    }
+                }
+                // A rejected move leaves it the player's turn, so they're asked again.
+                // There's nothing to wait for when a game couldn't be opened, accepted, watched or
+                // resumed, though.
+                ServerMessage::Error(message) => {
+                    eprintln!("{}", message);
+                    if seat.is_none() && session.take().is_some() {
+                        if !play_again(&mut writer, &role, input)? {
+                            return Ok(());
+                        }
+                        continue;
+                    }
+                    if !matches!(role, Role::Play(_)) && seat.is_none() {
+                        return send(&mut writer, ClientMessage::Quit);
+                    }
+                    if let Some(seat) = seat.as_ref().filter(|x| is_turn_of(x)) {
+                        match ask_for_move(seat, theme, labels, input) {
+                            Some(message) => {
+                                if send(&mut writer, message).is_err() {
+                                    break;
+                                }
+                            }
+                            None => return send(&mut writer, ClientMessage::Quit),
+                        }
+                    }
+                }
+                ServerMessage::GameOver(winner, reason) => {
+                    let Some(finished) = seat.take() else {
+                        continue;
+                    };
+                    session = None;
+                    let loser = winner.map_or(Player::None, |x| x.opponent());
+                    match reason {
+                        Reason::Resigned => println!(
+                            "{}",
+                            locale::text("net.resigned", &[("name", &finished.name(loser))])
+                        ),
+                        Reason::Disconnected => println!(
+                            "{}",
+                            locale::text("net.disconnected", &[("name", &finished.name(loser))])
+                        ),
+                        Reason::Abandoned => println!(
+                            "{}",
+                            locale::text("net.abandoned", &[("name", &finished.name(loser))])
+                        ),
+                        // It's the side to move whose time ran out, even when it's a draw
+                        Reason::Timeout => {
+                            let flagged = match finished.moves.len() % 2 {
+                                0 => Player::One,
+                                _ => Player::Two,
+                            };
+                            println!(
+                                "{}",
+                                clock::describe_flag(finished.names(), flagged, winner)
+                            )
+                        }
+                        Reason::Connected | Reason::Full => (),
+                    }
+                    println!(
+                        "{}",
+                        accessible::result(winner.map(|x| finished.name(x)), finished.moves.len())
+                    );
+                    if !play_again(&mut writer, &role, input)? {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        // Only a game in progress is worth reconnecting for
+        let Some((_, grace)) = &session else {
+            break;
+        };
+        println!("{}", locale::text("net.reconnecting", &[]));
+        seat = None;
+        match reconnect(address, *grace) {
+            Some(reconnected) => stream = reconnected,
+            None => {
+                println!("{}", locale::text("net.gave_up", &[]));
+                return Ok(());
+            }
+        }
+    }
+    println!("{}", locale::text("net.closed", &[]));
+    Ok(())
+}
+
+// Tries to reach the server every few seconds, until the grace period would have run out
+fn reconnect(address: &str, grace: Duration) -> Option<TcpStream> {
+    let deadline = Instant::now() + grace;
+    loop {
+        let stream = address
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addresses| addresses.next())
+            .and_then(|x| TcpStream::connect_timeout(&x, RECONNECT_INTERVAL).ok());
+        if stream.is_some() {
+            return stream;
+        }
+        if Instant::now() + RECONNECT_INTERVAL >= deadline {
+            return None;
+        }
+        thread::sleep(RECONNECT_INTERVAL);
+    }
+}
+
+/* Asks whether to look for another game, or watch another, and does so if they would.
+An open game that was accepted is gone, so players who accepted one choose from the lobby again.
+Gives whether they wanted to.
+*/
+fn play_again(writer: &mut TcpStream, role: &Role, input: &mut String) -> io::Result<bool> {
+    let (question, again) = match role {
+        Role::Accept(_, _) => ("game.play_again", ClientMessage::Lobby),
+        Role::Watch(_) => ("net.watch_again", ClientMessage::Games),
+        _ => ("game.play_again", role.opening()),
+    };
+    println!("{}", locale::text(question, &[]));
+    input.clear();
+    let read = io::stdin().read_line(input)?;
+    match read == 0 || locale::is_no(input) {
+        true => send(writer, ClientMessage::Quit).map(|()| false),
+        false => send(writer, again).map(|()| true),
+    }
+}
+
+// Asks which open game to accept until one on the list is picked. Gives nothing if the input ends.
+fn choose_open_game(open_games: &[(usize, usize, Ruleset)], input: &mut String) -> Option<usize> {
+    println!("{}", locale::text("net.open_games", &[]));
+    for (game, rating, ruleset) in open_games {
+        println!(
+            "{}",
+            locale::text(
+                "net.open_game",
+                &[("game", game), ("rules", ruleset), ("rating", rating)]
+            )
+        );
+    }
+    loop {
+        println!("{}", locale::text("net.choose_open_game", &[]));
+        input.clear();
+        if io::stdin().read_line(input).ok()? == 0 {
+            return None;
+        }
+        match input.trim().parse() {
+            Ok(game) if open_games.iter().any(|x| x.0 == game) => return Some(game),
+            _ => eprintln!(
+                "{}",
+                locale::text("net.no_such_game", &[("input", &input.trim())])
+            ),
+        }
+    }
+}
+
+// Asks which game to watch until one on the list is picked. Gives nothing if the input ends.
+fn choose_game(listing: &[(usize, usize, [String; 2])], input: &mut String) -> Option<usize> {
+    println!("{}", locale::text("net.games", &[]));
+    for (game, moves, names) in listing {
+        println!(
+            "{}",
+            locale::text(
+                "net.game",
+                &[
+                    ("game", game),
+                    ("one", &names[0]),
+                    ("two", &names[1]),
+                    ("moves", &locale::plural("net.moves", *moves, &[])),
+                ]
+            )
+        );
+    }
+    loop {
+        println!("{}", locale::text("net.choose_game", &[]));
+        input.clear();
+        if io::stdin().read_line(input).ok()? == 0 {
+            return None;
+        }
+        match input.trim().parse() {
+            Ok(game) if listing.iter().any(|x| x.0 == game) => return Some(game),
+            _ => eprintln!(
+                "{}",
+                locale::text("net.no_such_game", &[("input", &input.trim())])
+            ),
+        }
+    }
+}
+
+// Whether the board shows it's the seat's turn, going by how many discs have been played
+fn is_turn_of(seat: &Seat) -> bool {
+    let side = match seat.moves.len() % 2 {
+        0 => Player::One,
+        _ => Player::Two,
+    };
+    side == seat.side
+}
+
+/* Reads turns until the player makes a move or resigns. The commands that only need the board are
+answered here, and the ones the server can't do, such as undo, are turned down.
+Gives nothing if the player quits or the input ends.
+*/
+fn ask_for_move(
+    seat: &Seat,
+    theme: &Theme,
+    labels: ColumnLabels,
+    input: &mut String,
+) -> Option<ClientMessage> {
+    let stdin = io::stdin();
+    loop {
+        println!(
+            "{}",
+            accessible::turn_prompt(
+                seat.name(seat.side),
+                theme,
+                seat.side,
+                &labels.range(seat.columns)
+            )
+        );
+        input.clear();
+        if stdin.read_line(input).ok()? == 0 {
+            return None;
+        }
+        let command = match turn::parse(input, labels, seat.columns) {
+            Ok(command) => command,
+            Err(message) => {
+                eprintln!("{}", message);
+                continue;
+            }
+        };
+        match command {
+            TurnCommand::Play(column) => return Some(ClientMessage::Move(column)),
+            TurnCommand::Resign => return Some(ClientMessage::Resign),
+            TurnCommand::Quit => return None,
+            TurnCommand::Board => println!(
+                "{}",
+                accessible::present_board(&seat.board, theme, labels, seat.names())
+            ),
+            TurnCommand::Column(column) => println!(
+                "{}",
+                accessible::describe_column(&seat.board, column, labels, seat.names())
+            ),
+            TurnCommand::Row(row) => match accessible::describe_row(&seat.board, row, seat.names())
+            {
+                Ok(description) => println!("{}", description),
+                Err(message) => eprintln!("{}", message),
+            },
+            TurnCommand::History => {
+                println!("{}", turn::history(&seat.moves, seat.names(), labels))
+            }
+            TurnCommand::Help => println!("{}", locale::text("net.help", &[])),
+            TurnCommand::Undo
+            | TurnCommand::Hint
+            | TurnCommand::Save(_)
+            | TurnCommand::Load(_)
+            | TurnCommand::OfferDraw
+            | TurnCommand::Rules => eprintln!(
+                "{}",
+                locale::text("net.unavailable", &[("command", &input.trim())])
+            ),
+        }
+    }
+}
+
+fn send(writer: &mut TcpStream, message: ClientMessage) -> io::Result<()> {
+    writeln!(writer, "{}", message.to_line())
+}
@@ -0,0 +1,395 @@
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use crate::locale;
+
+use super::{
+    game::{Game, GameStatus, Player},
+    ruleset::Ruleset,
+};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum TimeControl {
+    // A bank of time for the whole game, with the increment added after every move
+    Increment { base: Duration, increment: Duration },
+    // The same time for every move, where time not used isn't kept
+    PerMove(Duration),
+}
+
+impl TimeControl {
+    /* Parses minutes plus an increment in seconds, such as 5+3, or 5 for no increment,
+    or seconds for each move, such as 30/move.
+    */
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let error = || {
+            format!(
+                "The clock <{}> could not be parsed. Expected minutes and an increment in seconds, such as 5+3, or seconds a move, such as 30/move.",
+                value
+            )
+        };
+        let number = |x: &str| x.trim().parse::<u64>().map_err(|_| error());
+        let control = match value.trim().split_once('/') {
+            Some((seconds, "move")) => TimeControl::PerMove(Duration::from_secs(number(seconds)?)),
+            Some(_) => return Err(error()),
+            None => {
+                let (minutes, increment) = value.split_once('+').unwrap_or((value, "0"));
+                TimeControl::Increment {
+                    base: Duration::from_secs(number(minutes)?.checked_mul(60).ok_or_else(error)?),
+                    increment: Duration::from_secs(number(increment)?),
+                }
+            }
+        };
+        match control.starting_time().is_zero() {
+            true => Err(error()),
+            false => Ok(control),
+        }
+    }
+
+    fn starting_time(&self) -> Duration {
+        match *self {
+            TimeControl::Increment { base, .. } => base,
+            TimeControl::PerMove(time) => time,
+        }
+    }
+}
+
+impl Display for TimeControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeControl::Increment { base, increment } => {
+                write!(f, "{}+{}", base.as_secs() / 60, increment.as_secs())
+            }
+            TimeControl::PerMove(time) => write!(f, "{}/move", time.as_secs()),
+        }
+    }
+}
+
+/* Both players' time, with at most one side's running. The time only runs down between `start`
+and `stop`, so the caller decides what counts, and checks for a flag with `flagged` or the
+result of `stop`.
+*/
+#[derive(PartialEq, Debug, Clone)]
+pub struct Clock {
+    control: TimeControl,
+    remaining: [Duration; 2],
+    // The side whose time is running, and since when
+    running: Option<(Player, Instant)>,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        Clock {
+            control,
+            remaining: [control.starting_time(); 2],
+            running: None,
+        }
+    }
+
+    // Starts the side's time, unless it's running already. The other side's stops without an increment.
+    pub fn start(&mut self, side: Player, now: Instant) {
+        if self.running.is_some_and(|(running, _)| running == side) {
+            return;
+        }
+        self.pause(now);
+        self.running = Some((side, now));
+    }
+
+    /* Stops the time of the side that just moved, adding their increment, or giving them the full
+    time again for their next move. Gives the side instead if their time had already run out.
+    */
+    pub fn stop(&mut self, now: Instant) -> Result<(), Player> {
+        let Some((side, _)) = self.running else {
+            return Ok(());
+        };
+        self.pause(now);
+        let remaining = &mut self.remaining[index(side)];
+        if remaining.is_zero() {
+            return Err(side);
+        }
+        match self.control {
+            // Time that can't be held any longer is more than any game lasts anyway
+            TimeControl::Increment { increment, .. } => {
+                *remaining = remaining.saturating_add(increment)
+            }
+            TimeControl::PerMove(time) => *remaining = time,
+        }
+        Ok(())
+    }
+
+    pub fn remaining(&self, side: Player, now: Instant) -> Duration {
+        let remaining = self.remaining[index(side)];
+        match self.running {
+            Some((running, since)) if running == side => {
+                remaining.saturating_sub(now.saturating_duration_since(since))
+            }
+            _ => remaining,
+        }
+    }
+
+    // The side whose time is running, if it has run out
+    pub fn flagged(&self, now: Instant) -> Option<Player> {
+        let (side, _) = self.running?;
+        self.remaining(side, now).is_zero().then_some(side)
+    }
+
+    // Both players' time left, to show next to the board
+    pub fn describe(&self, names: [&str; 2], now: Instant) -> String {
+        describe_times(
+            names,
+            [Player::One, Player::Two].map(|x| self.remaining(x, now)),
+        )
+    }
+
+    fn pause(&mut self, now: Instant) {
+        if let Some((side, since)) = self.running.take() {
+            let remaining = &mut self.remaining[index(side)];
+            *remaining = remaining.saturating_sub(now.saturating_duration_since(since));
+        }
+    }
+}
+
+// Player one's time left and then player two's, with their names
+pub fn describe_times(names: [&str; 2], times: [Duration; 2]) -> String {
+    let [one, two] = times.map(format_time);
+    locale::text(
+        "game.clocks",
+        &[
+            ("one", &names[0]),
+            ("one_time", &one),
+            ("two", &names[1]),
+            ("two_time", &two),
+        ],
+    )
+}
+
+// Minutes and seconds, with tenths of a second in the last ten seconds, as a chess clock shows them
+pub fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    match seconds {
+        0..=9 => format!("0:0{}.{}", seconds, time.subsec_millis() / 100),
+        3600.. => format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        ),
+        _ => format!("{}:{:02}", seconds / 60, seconds % 60),
+    }
+}
+
+/* Ends the game if the side whose time is running has run out, giving who ran out and who won,
+where nobody winning is a draw.
+*/
+pub fn end_on_flag(
+    game: &mut Game,
+    clock: &Clock,
+    now: Instant,
+) -> Option<(Player, Option<Player>)> {
+    let flagged = clock.flagged(now)?;
+    let winner = flag_winner(game, flagged);
+    game.winner = winner;
+    game.status = match winner {
+        Some(_) => GameStatus::Completed,
+        None => GameStatus::Draw,
+    };
+    Some((flagged, winner))
+}
+
+/* As `end_on_flag`, for the versions of the game with their own rules, which give their moves and the
+ruleset they play by instead, so the position can be replayed here. Gives who ran out and who won.
+*/
+pub fn flag_after_moves(
+    clock: &Clock,
+    ruleset: &Ruleset,
+    moves: &[usize],
+    now: Instant,
+) -> Option<(Player, Option<Player>)> {
+    let flagged = clock.flagged(now)?;
+    let win_conditions = ruleset.win_conditions();
+    let mut game = Game::initialise(ruleset.columns, ruleset.rows, &win_conditions)
+        .with_variants(&ruleset.variants);
+    for &column in moves {
+        game = game
+            .play_on_column(column)
+            .expect("The moves were played by the same rules");
+    }
+    Some((flagged, flag_winner(&game, flagged)))
+}
+
+// Running out of time loses, unless the opponent couldn't have won anyway, which makes it a draw
+pub fn flag_winner(game: &Game, flagged: Player) -> Option<Player> {
+    let opponent = flagged.opponent();
+    game.could_win(opponent).then_some(opponent)
+}
+
+// Says who ran out of time, and why it's a draw if it is
+pub fn describe_flag(names: [&str; 2], flagged: Player, winner: Option<Player>) -> String {
+    let name = names[index(flagged)];
+    match winner {
+        Some(_) => locale::text("game.flagged", &[("name", &name)]),
+        None => locale::text(
+            "game.flagged_draw",
+            &[
+                ("name", &name),
+                ("opponent", &names[index(flagged.opponent())]),
+            ],
+        ),
+    }
+}
+
+fn index(side: Player) -> usize {
+    match side {
+        Player::Two => 1,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::remove_consts::{
+        game::{Game, Player},
+        ruleset::Ruleset,
+        win_conditions::default_win_conditions,
+    };
+
+    use super::{flag_after_moves, flag_winner, format_time, Clock, TimeControl};
+
+    #[test]
+    fn reads_time_controls() {
+        assert_eq!(
+            TimeControl::parse("5+3"),
+            Ok(TimeControl::Increment {
+                base: Duration::from_secs(300),
+                increment: Duration::from_secs(3)
+            })
+        );
+        assert_eq!(
+            TimeControl::parse("10"),
+            Ok(TimeControl::Increment {
+                base: Duration::from_secs(600),
+                increment: Duration::ZERO
+            })
+        );
+        assert_eq!(
+            TimeControl::parse("30/move"),
+            Ok(TimeControl::PerMove(Duration::from_secs(30)))
+        );
+        for control in ["5+3", "1+0", "30/move"] {
+            assert_eq!(TimeControl::parse(control).unwrap().to_string(), control);
+        }
+        for invalid in [
+            "",
+            "0+5",
+            "five",
+            "5+",
+            "30/turn",
+            "0/move",
+            "-1+2",
+            "999999999999999999+0",
+        ] {
+            assert!(TimeControl::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn only_the_side_to_move_loses_time() {
+        let start = Instant::now();
+        let mut clock = Clock::new(TimeControl::parse("1+2").unwrap());
+        clock.start(Player::One, start);
+        let later = start + Duration::from_secs(10);
+        assert_eq!(clock.remaining(Player::One, later), Duration::from_secs(50));
+        assert_eq!(clock.remaining(Player::Two, later), Duration::from_secs(60));
+
+        assert_eq!(clock.stop(later), Ok(()));
+        assert_eq!(clock.remaining(Player::One, later), Duration::from_secs(52));
+        clock.start(Player::Two, later);
+        clock.start(Player::Two, later + Duration::from_secs(5));
+        let later = later + Duration::from_secs(20);
+        assert_eq!(clock.remaining(Player::Two, later), Duration::from_secs(40));
+        assert_eq!(clock.remaining(Player::One, later), Duration::from_secs(52));
+
+        // Switching sides without a move, as when one is taken back, adds nothing
+        clock.start(Player::One, later);
+        assert_eq!(clock.remaining(Player::Two, later), Duration::from_secs(40));
+
+        // An increment too large to add just leaves as much time as can be held
+        let mut clock = Clock::new(TimeControl::parse("1+18446744073709551615").unwrap());
+        for _ in 0..2 {
+            clock.start(Player::One, start);
+            assert_eq!(clock.stop(start), Ok(()));
+        }
+        assert_eq!(clock.remaining(Player::One, start), Duration::MAX);
+    }
+
+    #[test]
+    fn flags_fall_once_the_time_runs_out() {
+        let start = Instant::now();
+        let mut clock = Clock::new(TimeControl::parse("30/move").unwrap());
+        clock.start(Player::One, start);
+        assert_eq!(clock.stop(start + Duration::from_secs(29)), Ok(()));
+        assert_eq!(clock.remaining(Player::One, start), Duration::from_secs(30));
+
+        clock.start(Player::Two, start);
+        assert_eq!(clock.flagged(start + Duration::from_secs(29)), None);
+        assert_eq!(
+            clock.flagged(start + Duration::from_secs(30)),
+            Some(Player::Two)
+        );
+        assert_eq!(
+            clock.stop(start + Duration::from_secs(31)),
+            Err(Player::Two)
+        );
+    }
+
+    #[test]
+    fn shows_tenths_of_a_second_when_time_is_short() {
+        assert_eq!(format_time(Duration::from_secs(300)), "5:00");
+        assert_eq!(format_time(Duration::from_millis(61_900)), "1:01");
+        assert_eq!(format_time(Duration::from_millis(9_460)), "0:09.4");
+        assert_eq!(format_time(Duration::ZERO), "0:00.0");
+        assert_eq!(format_time(Duration::from_secs(3725)), "1:02:05");
+    }
+
+    /*
+    o . . .
+    x o x .
+    */
+    #[test]
+    fn running_out_of_time_is_a_draw_when_the_opponent_could_not_win() {
+        let win_conditions = default_win_conditions();
+        let mut game = Game::initialise(4, 2, &win_conditions);
+        assert_eq!(flag_winner(&game, Player::One), Some(Player::Two));
+        for column in [0, 1, 2, 0] {
+            game = game.play_on_column(column).unwrap();
+        }
+        assert_eq!(flag_winner(&game, Player::One), Some(Player::Two));
+        assert_eq!(flag_winner(&game, Player::Two), None);
+
+        // The same position, given as moves
+        let ruleset = Ruleset {
+            columns: 4,
+            rows: 2,
+            ..Default::default()
+        };
+        let mut clock = Clock::new(TimeControl::parse("30/move").unwrap());
+        let start = Instant::now();
+        clock.start(Player::Two, start);
+        assert_eq!(
+            flag_after_moves(&clock, &ruleset, &[0, 1, 2, 0], start),
+            None
+        );
+        assert_eq!(
+            flag_after_moves(
+                &clock,
+                &ruleset,
+                &[0, 1, 2, 0],
+                start + Duration::from_secs(30)
+            ),
+            Some((Player::Two, None))
+        );
+    }
+}
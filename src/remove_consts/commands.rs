@@ -0,0 +1,709 @@
+use std::{
+    fs,
+    io::{self, BufRead, Read, Write},
+    process::ExitCode,
+    time::Instant,
+};
+
+use crate::{
+    accessible, locale, overengineered,
+    remove_consts::{
+        ai::{best_move, PlayerType},
+        api,
+        batch::{run_line, EXIT_ILLEGAL_MOVE, EXIT_INVALID_LINE},
+        cli::{Mode, Options},
+        client::{self, Role},
+        clock::{self, Clock},
+        correspondence::{self, Correspondence},
+        engine::ComputerEngine,
+        game::{Game, GameStatus, Player},
+        game_loop,
+        ruleset::Ruleset,
+        server, tui,
+        win_conditions::WinCondition,
+    },
+    scoreboard::DEFAULT_NAMES,
+    stats::{render_leaderboard, SortBy, Stats, DEFAULT_STATS_PATH},
+    submission::{
+        self,
+        game::{DEFAULT_COLUMNS, DEFAULT_ROWS},
+        solver::{Solver, Verdict},
+    },
+    theme::{render_board, Theme},
+};
+
+pub fn play(options: Options) -> ExitCode {
+    let theme = Theme::detect(options.theme);
+    if options.tui && accessible::enabled() {
+        eprintln!("{}", locale::text("access.no_tui", &[]));
+        return ExitCode::FAILURE;
+    }
+    if options.tui {
+        return match tui::play(
+            &options.ruleset,
+            options.players,
+            options.clock,
+            &theme,
+            options.labels,
+        ) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("{}", error);
+                ExitCode::FAILURE
+            }
+        };
+    }
+    match options.mode {
+        Mode::Normal => {
+            submission::game_loop::play(&theme, options.labels, options.clock, options.scoreboard())
+        }
+        Mode::Rules => overengineered::game_loop::play(
+            &theme,
+            options.labels,
+            options.clock,
+            options.scoreboard(),
+        ),
+        Mode::Custom => {
+            let scoreboard = options.scoreboard();
+            game_loop::play(
+                Some(options.ruleset),
+                options.players,
+                options.clock,
+                &theme,
+                options.labels,
+                scoreboard,
+            )
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+// Shows the position after the moves, and what the computer would play next
+pub fn analyze(options: Options, moves: &[usize]) -> ExitCode {
+    let win_conditions = options.ruleset.win_conditions();
+    let game = match replay(&options.ruleset, &win_conditions, moves) {
+        Ok(game) => game,
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::FAILURE;
+        }
+    };
+    println!(
+        "{}",
+        render_board(game.board(), &Theme::detect(options.theme), options.labels)
+    );
+    match game.status {
+        GameStatus::Completed => println!(
+            "{}",
+            locale::text(
+                "cmd.won",
+                &[(
+                    "name",
+                    &name(
+                        game.winner
+                            .expect("Game has been win with no winner. Invalid state.")
+                    )
+                )]
+            )
+        ),
+        GameStatus::Draw => println!("{}", locale::text("cmd.draw", &[])),
+        GameStatus::Started => println!(
+            "{}",
+            locale::text(
+                "cmd.suggestion",
+                &[
+                    ("name", &name(game.current)),
+                    ("depth", &options.players.depth),
+                    (
+                        "column",
+                        &options.labels.label(
+                            best_move(&game, options.players.depth)
+                                .expect("An unfinished game has an open column")
+                        )
+                    )
+                ]
+            )
+        ),
+    }
+    ExitCode::SUCCESS
+}
+
+// Solves the position after the moves, and lists the columns that keep the best result
+pub fn solve(options: Options, moves: &[usize]) -> ExitCode {
+    let mut game = submission::game::Game::<DEFAULT_COLUMNS, DEFAULT_ROWS>::initialise();
+    for (index, &column) in moves.iter().enumerate() {
+        if game.status != submission::game::GameStatus::Started {
+            eprintln!("{}", after_finish(index));
+            return ExitCode::FAILURE;
+        }
+        game = match game.play_on_column(column) {
+            Ok(game) => game,
+            Err(error) => {
+                eprintln!("{}", illegal_move(index, column, &error.message));
+                return ExitCode::FAILURE;
+            }
+        };
+    }
+    println!(
+        "{}",
+        render_board(game.board(), &Theme::detect(options.theme), options.labels)
+    );
+    if game.status != submission::game::GameStatus::Started {
+        println!("{}", locale::text("cmd.already_over", &[]));
+        return ExitCode::SUCCESS;
+    }
+
+    let mut solver = Solver::new();
+    let verdict = solver.verdict(&game);
+    let best_columns: Vec<String> = (0..DEFAULT_COLUMNS)
+        .filter(|&column| {
+            let Ok(next) = game.clone().play_on_column(column) else {
+                return false;
+            };
+            let result = match next.status {
+                submission::game::GameStatus::Completed => Verdict::Win,
+                submission::game::GameStatus::Draw => Verdict::Draw,
+                submission::game::GameStatus::Started => match solver.verdict(&next) {
+                    Verdict::Win => Verdict::Loss,
+                    Verdict::Loss => Verdict::Win,
+                    Verdict::Draw => Verdict::Draw,
+                },
+            };
+            result == verdict
+        })
+        .map(|column| options.labels.label(column))
+        .collect();
+    let key = match verdict {
+        Verdict::Win => "cmd.solved_win",
+        Verdict::Loss => "cmd.solved_loss",
+        Verdict::Draw => "cmd.solved_draw",
+    };
+    let side = match game.current {
+        submission::game::Player::Two => Player::Two,
+        _ => Player::One,
+    };
+    println!(
+        "{}",
+        locale::text(
+            key,
+            &[("name", &name(side)), ("columns", &best_columns.join(", "))]
+        )
+    );
+    ExitCode::SUCCESS
+}
+
+// Shows every position of the game in turn
+pub fn replay_moves(options: Options, moves: &[usize]) -> ExitCode {
+    let theme = Theme::detect(options.theme);
+    let win_conditions = options.ruleset.win_conditions();
+    let mut game = Game::initialise(
+        options.ruleset.columns,
+        options.ruleset.rows,
+        &win_conditions,
+    )
+    .with_variants(&options.ruleset.variants);
+    println!("{}", render_board(game.board(), &theme, options.labels));
+    for (index, &column) in moves.iter().enumerate() {
+        if game.status != GameStatus::Started {
+            eprintln!("{}", after_finish(index));
+            return ExitCode::FAILURE;
+        }
+        println!(
+            "{}",
+            locale::text(
+                "cmd.replayed_move",
+                &[
+                    ("number", &(index + 1)),
+                    ("name", &name(game.current)),
+                    ("column", &options.labels.label(column))
+                ]
+            )
+        );
+        game = match game.play_on_column(column) {
+            Ok(game) => game,
+            Err(error) => {
+                eprintln!("{}", error.message);
+                return ExitCode::FAILURE;
+            }
+        };
+        println!("{}", render_board(game.board(), &theme, options.labels));
+    }
+    ExitCode::SUCCESS
+}
+
+// Plays every game in the file, printing a result line for each to stdout and any problems to stderr
+pub fn batch(path: Option<&str>) -> ExitCode {
+    let mut contents = String::new();
+    let read = match path {
+        Some(path) => fs::read_to_string(path).map(|x| contents = x),
+        None => io::stdin().read_to_string(&mut contents).map(|_| ()),
+    };
+    if let Err(error) = read {
+        let standard_input = locale::text("cmd.standard_input", &[]);
+        eprintln!(
+            "{}",
+            locale::text(
+                "cmd.read_failed",
+                &[
+                    ("path", &path.unwrap_or(&standard_input)),
+                    ("error", &error)
+                ]
+            )
+        );
+        return ExitCode::from(EXIT_INVALID_LINE);
+    }
+
+    let (mut illegal, mut invalid) = (false, false);
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        match run_line(line) {
+            Ok(result) => {
+                if let Some(illegal_move) = &result.illegal_move {
+                    illegal = true;
+                    eprintln!(
+                        "{}",
+                        locale::text(
+                            "cmd.line_illegal",
+                            &[
+                                ("line", &(index + 1)),
+                                ("number", &illegal_move.number),
+                                ("column", &illegal_move.column),
+                                ("reason", &illegal_move.reason)
+                            ]
+                        )
+                    );
+                }
+                println!("{}", result.to_line(index + 1));
+            }
+            Err(message) => {
+                invalid = true;
+                eprintln!(
+                    "{}",
+                    locale::text(
+                        "cmd.line_invalid",
+                        &[("line", &(index + 1)), ("error", &message)]
+                    )
+                );
+                println!("line={} status=invalid", index + 1);
+            }
+        }
+    }
+    match (invalid, illegal) {
+        (true, _) => ExitCode::from(EXIT_INVALID_LINE),
+        (false, true) => ExitCode::from(EXIT_ILLEGAL_MOVE),
+        (false, false) => ExitCode::SUCCESS,
+    }
+}
+
+// Plays the two player types against each other, swapping who goes first after every game
+pub fn tournament(options: Options) -> ExitCode {
+    let ruleset = &options.ruleset;
+    let win_conditions = ruleset.win_conditions();
+    let players = options.players;
+    let mut bot = players.bot();
+    let (mut a_wins, mut b_wins, mut draws) = (0, 0, 0);
+    println!(
+        "{}",
+        locale::text(
+            "cmd.tournament",
+            &[
+                ("games", &options.games),
+                ("one", &describe(players.one, "A")),
+                ("two", &describe(players.two, "B")),
+                ("ruleset", ruleset)
+            ]
+        )
+    );
+    if let Some(time_control) = options.clock {
+        println!(
+            "{}",
+            locale::text("cmd.tournament_clock", &[("clock", &time_control)])
+        );
+    }
+    println!("{}", locale::text("cmd.tournament_seats", &[]));
+
+    for number in 1..=options.games {
+        let swapped = number % 2 == 0;
+        let seat = |player: Player| match (player, swapped) {
+            (Player::One, false) | (Player::Two, true) => players.one,
+            _ => players.two,
+        };
+        let mut game = Game::initialise(ruleset.columns, ruleset.rows, &win_conditions)
+            .with_variants(&ruleset.variants);
+        let mut clock = options.clock.map(Clock::new);
+        let mut flagged = false;
+        loop {
+            if let Some(clock) = &mut clock {
+                clock.start(game.current, Instant::now());
+                bot.limit_time(Some(clock.remaining(game.current, Instant::now())));
+            }
+            let Some(column) = bot.choose(seat(game.current), &game) else {
+                break;
+            };
+            if let Some(error) = bot.take_engine_error() {
+                eprintln!(
+                    "{}",
+                    locale::text("cmd.engine_failed", &[("error", &error)])
+                );
+            }
+            if let Some(clock) = &mut clock {
+                if clock::end_on_flag(&mut game, clock, Instant::now()).is_some() {
+                    flagged = true;
+                    break;
+                }
+                let _ = clock.stop(Instant::now());
+            }
+            game = game
+                .play_on_column(column)
+                .expect("Players only choose open columns");
+        }
+        let winner = match game.winner {
+            Some(winner) if (winner == Player::One) != swapped => {
+                a_wins += 1;
+                Some(describe(players.one, "A"))
+            }
+            Some(_) => {
+                b_wins += 1;
+                Some(describe(players.two, "B"))
+            }
+            None => {
+                draws += 1;
+                None
+            }
+        };
+        let key = match (&winner, flagged) {
+            (Some(_), false) => "cmd.tournament_won",
+            (Some(_), true) => "cmd.tournament_won_on_time",
+            (None, false) => "cmd.tournament_drawn",
+            (None, true) => "cmd.tournament_drawn_on_time",
+        };
+        println!(
+            "{}",
+            locale::text(
+                key,
+                &[
+                    ("number", &number),
+                    ("name", &winner.unwrap_or_default()),
+                    ("moves", &game.moves().len())
+                ]
+            )
+        );
+    }
+    println!(
+        "{}",
+        locale::text(
+            "cmd.tournament_score",
+            &[
+                ("one", &describe(players.one, "A")),
+                ("one_wins", &a_wins),
+                ("two", &describe(players.two, "B")),
+                ("two_wins", &b_wins),
+                ("draws", &draws)
+            ]
+        )
+    );
+    ExitCode::SUCCESS
+}
+
+pub fn serve(options: Options, address: &str) -> ExitCode {
+    let web = options.web.as_deref();
+    match server::serve(
+        Some(address),
+        web,
+        &options.ruleset,
+        options.spectating,
+        options.grace,
+        options.clock,
+    ) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!(
+                "{}",
+                locale::text(
+                    "cmd.serve_failed",
+                    &[("address", &address), ("error", &error)]
+                )
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
+
+pub fn connect(options: Options, address: &str, game: Option<usize>) -> ExitCode {
+    let name = options.names[0].clone();
+    let role = match game {
+        Some(game) => Role::Accept(name, Some(game)),
+        None => Role::Play(name),
+    };
+    play_online(options, address, role)
+}
+
+pub fn lobby(options: Options, address: &str) -> ExitCode {
+    let role = Role::Accept(options.names[0].clone(), None);
+    play_online(options, address, role)
+}
+
+pub fn create(options: Options, address: &str) -> ExitCode {
+    let role = Role::Create(options.names[0].clone(), options.ruleset.clone());
+    play_online(options, address, role)
+}
+
+fn play_online(options: Options, address: &str, role: Role) -> ExitCode {
+    let theme = Theme::detect(options.theme);
+    match client::connect(address, role, &theme, options.labels) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!(
+                "{}",
+                locale::text(
+                    "cmd.play_failed",
+                    &[("address", &address), ("error", &error)]
+                )
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
+
+pub fn watch(options: Options, address: &str, game: Option<usize>) -> ExitCode {
+    let theme = Theme::detect(options.theme);
+    match client::connect(address, Role::Watch(game), &theme, options.labels) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!(
+                "{}",
+                locale::text(
+                    "cmd.watch_failed",
+                    &[("address", &address), ("error", &error)]
+                )
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/* Starts a game in a file, plays a move in it, or shows it. Every run reads the game afresh,
+so the players can take their turns days apart, wherever the file is shared.
+*/
+pub fn correspond(options: Options, path: &str, action: Option<&str>) -> ExitCode {
+    let theme = Theme::detect(options.theme);
+    let started = match action {
+        Some("new") => {
+            let game = Correspondence::new(
+                options.ruleset.clone(),
+                options.names.clone(),
+                correspondence::now(),
+            );
+            correspondence::create(path, &game).map(|()| {
+                println!(
+                    "{}",
+                    locale::text(
+                        "corr.started",
+                        &[
+                            ("one", &game.names[0]),
+                            ("two", &game.names[1]),
+                            ("path", &path)
+                        ]
+                    )
+                );
+                game
+            })
+        }
+        _ => correspondence::load(path),
+    };
+    let mut game = match started {
+        Ok(game) => game,
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Some(column) = action.filter(|&x| x != "new") {
+        let played = options
+            .labels
+            .parse(column, game.ruleset.columns)
+            .and_then(|column| game.play(&options.names[0], column, correspondence::now()))
+            .and_then(|line| correspondence::append(path, &line));
+        if let Err(message) = played {
+            eprintln!("{}", message);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let win_conditions = game.ruleset.win_conditions();
+    let board = match game.replay(&win_conditions) {
+        Ok(board) => board,
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::FAILURE;
+        }
+    };
+    println!("{}", render_board(board.board(), &theme, options.labels));
+    let name = |side: Player| match side {
+        Player::Two => &game.names[1],
+        _ => &game.names[0],
+    };
+    if let Some(last) = game.moves.last() {
+        println!(
+            "{}",
+            locale::text(
+                "corr.last_move",
+                &[
+                    ("number", &game.moves.len()),
+                    ("name", name(last.side)),
+                    ("column", &options.labels.label(last.column)),
+                    ("time", &correspondence::format_time(last.played_at))
+                ]
+            )
+        );
+    }
+    let outcome = match board.status {
+        GameStatus::Completed => locale::text(
+            "corr.won",
+            &[(
+                "name",
+                name(board.winner.expect("A completed game has a winner")),
+            )],
+        ),
+        GameStatus::Draw => locale::text("corr.draw", &[]),
+        GameStatus::Started => locale::text("corr.turn", &[("name", name(board.current))]),
+    };
+    println!("{}", outcome);
+    ExitCode::SUCCESS
+}
+
+pub fn api(address: &str) -> ExitCode {
+    match api::serve(address) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!(
+                "{}",
+                locale::text(
+                    "cmd.api_failed",
+                    &[("address", &address), ("error", &error)]
+                )
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
+
+pub fn web(options: Options, address: &str) -> ExitCode {
+    match server::serve(
+        None,
+        Some(address),
+        &options.ruleset,
+        options.spectating,
+        options.grace,
+        options.clock,
+    ) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!(
+                "{}",
+                locale::text(
+                    "cmd.web_failed",
+                    &[("address", &address), ("error", &error)]
+                )
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// Answers the engine protocol on standard input and output with the computer player, until told to quit
+pub fn engine(options: Options) -> ExitCode {
+    let mut engine = ComputerEngine::new(options.players.depth);
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lock().lines().map_while(Result::ok) {
+        let Some(replies) = engine.respond(&line) else {
+            break;
+        };
+        for reply in replies {
+            if writeln!(stdout, "{}", reply.to_line())
+                .and_then(|()| stdout.flush())
+                .is_err()
+            {
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+// Prints the leaderboard from the stats file
+pub fn stats(sort: SortBy) -> ExitCode {
+    let stats = match Stats::load(DEFAULT_STATS_PATH) {
+        Ok(stats) => stats,
+        Err(error) => {
+            eprintln!(
+                "{}",
+                locale::text(
+                    "cmd.stats_failed",
+                    &[("path", &DEFAULT_STATS_PATH), ("error", &error)]
+                )
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+    print!("{}", render_leaderboard(&stats, sort));
+    if stats.skipped > 0 {
+        eprintln!(
+            "{}",
+            locale::plural(
+                "cmd.skipped_lines",
+                stats.skipped,
+                &[("path", &DEFAULT_STATS_PATH)]
+            )
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+fn name(side: Player) -> &'static str {
+    match side {
+        Player::Two => DEFAULT_NAMES[1],
+        _ => DEFAULT_NAMES[0],
+    }
+}
+
+fn after_finish(index: usize) -> String {
+    locale::text("cmd.after_finish", &[("number", &(index + 1))])
+}
+
+fn illegal_move(index: usize, column: usize, error: &str) -> String {
+    locale::text(
+        "cmd.illegal_move",
+        &[
+            ("number", &(index + 1)),
+            ("column", &column),
+            ("error", &error),
+        ],
+    )
+}
+
+fn describe(player_type: PlayerType, label: &str) -> String {
+    format!("{} ({})", label, player_type)
+}
+
+fn replay<'a>(
+    ruleset: &Ruleset,
+    win_conditions: &'a Vec<Box<dyn WinCondition>>,
+    moves: &[usize],
+) -> Result<Game<'a>, String> {
+    let mut game = Game::initialise(ruleset.columns, ruleset.rows, win_conditions)
+        .with_variants(&ruleset.variants);
+    for (index, &column) in moves.iter().enumerate() {
+        if game.status != GameStatus::Started {
+            return Err(after_finish(index));
+        }
+        game = game
+            .play_on_column(column)
+            .map_err(|error| illegal_move(index, column, &error.message))?;
+    }
+    Ok(game)
+}
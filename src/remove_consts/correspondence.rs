@@ -0,0 +1,391 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::locale;
+
+use super::{
+    game::{Game, GameStatus, Player},
+    protocol::side_name,
+    ruleset::Ruleset,
+    win_conditions::WinCondition,
+};
+
+const HEADER: &str = "# A game of Connect 4 by correspondence. Play it with connect-4 correspond.
+# Every line ends in a checksum of the game so far, so editing it by hand breaks the game.";
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Move {
+    // Seconds since the Unix epoch
+    pub played_at: u64,
+    pub side: Player,
+    pub column: usize,
+}
+
+/* A game played a move at a time through a file, for players who take turns days apart.
+The file is plain text and only ever appended to, a line at a time:
+
+rules columns=7 rows=6 connect=4 win_conditions=vertical,horizontal 529173fca01a3c30
+one Ada 8b8f9619162a6bc6
+two Grace 01dee27274117cb2
+started 1792359555 dadb8c2002227a00
+move 1792359555 one 3 5563fbb684d127f9
+
+Each line ends in a running FNV-1a checksum of every line up to and including it, which catches
+hand edits and moves slipped in out of turn. It isn't a signature, so it won't stop someone set on
+cheating who recomputes it.
+*/
+#[derive(PartialEq, Debug, Clone)]
+pub struct Correspondence {
+    pub ruleset: Ruleset,
+    // Player one's name, then player two's
+    pub names: [String; 2],
+    pub started_at: u64,
+    pub moves: Vec<Move>,
+    // The checksum of the last line
+    checksum: u64,
+}
+
+impl Correspondence {
+    pub fn new(ruleset: Ruleset, names: [String; 2], started_at: u64) -> Self {
+        let mut game = Correspondence {
+            ruleset,
+            names,
+            started_at,
+            moves: vec![],
+            checksum: FNV_OFFSET,
+        };
+        for line in game.header_lines() {
+            game.checksum = fnv(game.checksum, &line);
+        }
+        game
+    }
+
+    /* Reads a game file, checking every line's checksum and that each move was played in turn.
+    The moves are replayed with the game's rules, so an illegal one is an error as well.
+    */
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut checksum = FNV_OFFSET;
+        let mut lines = vec![];
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: &str| format!("Line {}: {}", index + 1, message);
+            let (content, written) = line
+                .rsplit_once(' ')
+                .ok_or_else(|| error("Expected a checksum at the end of the line."))?;
+            checksum = fnv(checksum, content);
+            if written != format!("{:016x}", checksum) {
+                return Err(error(
+                    "The checksum doesn't match, so the file has been edited by hand.",
+                ));
+            }
+            lines.push((index + 1, content));
+        }
+
+        let mut fields = lines.iter();
+        let mut header = |key: &str| match fields.next() {
+            Some((number, content)) => match content.split_once(' ') {
+                Some((found, value)) if found == key => Ok(value),
+                _ => Err(format!("Line {}: Expected <{}>.", number, key)),
+            },
+            None => Err(format!("The file ends before <{}>.", key)),
+        };
+        let ruleset = Ruleset::from_settings(header("rules")?, "Correspondence")?;
+        let names = [header("one")?.to_owned(), header("two")?.to_owned()];
+        let started_at = parse_time(header("started")?)?;
+
+        let mut game = Correspondence::new(ruleset, names, started_at);
+        for &(number, content) in fields {
+            let error = |message: String| format!("Line {}: {}", number, message);
+            let played = parse_move(content).map_err(error)?;
+            let win_conditions = game.ruleset.win_conditions();
+            let current = game.replay(&win_conditions)?;
+            if current.status != GameStatus::Started {
+                return Err(error(
+                    "A move was played after the game had finished.".to_owned(),
+                ));
+            }
+            if played.side != current.current {
+                return Err(error(format!(
+                    "Player {} moved out of turn.",
+                    side_name(played.side)
+                )));
+            }
+            current
+                .play_on_column(played.column)
+                .map_err(|rejected| error(rejected.message))?;
+            game.moves.push(played);
+        }
+        game.checksum = checksum;
+        Ok(game)
+    }
+
+    // The whole file, for starting a game
+    pub fn to_text(&self) -> String {
+        let mut checksum = FNV_OFFSET;
+        let mut text = HEADER.to_owned() + "\n";
+        let moves = self.moves.iter().map(move_line);
+        for line in self.header_lines().into_iter().chain(moves) {
+            checksum = fnv(checksum, &line);
+            text += &format!("{} {:016x}\n", line, checksum);
+        }
+        text
+    }
+
+    // The moves were checked as they were read or played, so this only fails on a broken ruleset
+    pub fn replay<'a>(
+        &self,
+        win_conditions: &'a Vec<Box<dyn WinCondition>>,
+    ) -> Result<Game<'a>, String> {
+        let start = Game::initialise(self.ruleset.columns, self.ruleset.rows, win_conditions)
+            .with_variants(&self.ruleset.variants);
+        self.moves.iter().try_fold(start, |game, played| {
+            game.play_on_column(played.column)
+                .map_err(|rejected| rejected.message)
+        })
+    }
+
+    /* Plays the named player's move, if it's their turn, and gives the line to add to the file.
+    Names are matched without regard to case.
+    */
+    pub fn play(&mut self, name: &str, column: usize, played_at: u64) -> Result<String, String> {
+        let win_conditions = self.ruleset.win_conditions();
+        let game = self.replay(&win_conditions)?;
+        if game.status != GameStatus::Started {
+            return Err(locale::text("corr.over", &[]));
+        }
+        let turn = match game.current {
+            Player::Two => &self.names[1],
+            _ => &self.names[0],
+        };
+        if !turn.eq_ignore_ascii_case(name.trim()) {
+            return Err(locale::text(
+                "corr.not_your_turn",
+                &[("turn", turn), ("name", &name.trim())],
+            ));
+        }
+        let side = game.current;
+        game.play_on_column(column)
+            .map_err(|rejected| rejected.message)?;
+        let played = Move {
+            played_at,
+            side,
+            column,
+        };
+        self.moves.push(played);
+        let line = move_line(&played);
+        self.checksum = fnv(self.checksum, &line);
+        Ok(format!("{} {:016x}\n", line, self.checksum))
+    }
+
+    fn header_lines(&self) -> Vec<String> {
+        vec![
+            format!("rules {}", self.ruleset.settings()),
+            format!("one {}", self.names[0]),
+            format!("two {}", self.names[1]),
+            format!("started {}", self.started_at),
+        ]
+    }
+}
+
+// Starts a game in a new file, refusing to overwrite one that's already there
+pub fn create(path: impl AsRef<Path>, game: &Correspondence) -> Result<(), String> {
+    let path = path.as_ref();
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(game.to_text().as_bytes()))
+        .map_err(|error| format!("Could not start a game in <{}>: {}", path.display(), error))
+}
+
+pub fn load(path: impl AsRef<Path>) -> Result<Correspondence, String> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path)
+        .map_err(|error| format!("Could not read <{}>: {}", path.display(), error))?;
+    Correspondence::parse(&contents).map_err(|message| format!("{}: {}", path.display(), message))
+}
+
+// Adds a move to the end of the file, leaving everything before it untouched
+pub fn append(path: impl AsRef<Path>, line: &str) -> Result<(), String> {
+    let path = path.as_ref();
+    OpenOptions::new()
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|error| format!("Could not add the move to <{}>: {}", path.display(), error))
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |x| x.as_secs())
+}
+
+// A time in UTC, such as 2026-10-18 14:03, since the players may be in any time zone
+pub fn format_time(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let minutes = seconds % 86_400 / 60;
+    // Howard Hinnant's days to civil date, counting eras of 400 years from 0000-03-01
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+fn move_line(played: &Move) -> String {
+    format!(
+        "move {} {} {}",
+        played.played_at,
+        side_name(played.side),
+        played.column
+    )
+}
+
+fn parse_move(content: &str) -> Result<Move, String> {
+    let fields: Vec<&str> = content.split(' ').collect();
+    let ["move", played_at, side, column] = fields[..] else {
+        return Err(format!(
+            "Expected <move time side column> but found <{}>.",
+            content
+        ));
+    };
+    Ok(Move {
+        played_at: parse_time(played_at)?,
+        side: match side {
+            "one" => Player::One,
+            "two" => Player::Two,
+            other => return Err(format!("<{}> is not a side. Expected one or two.", other)),
+        },
+        column: column
+            .parse()
+            .map_err(|_| format!("The column <{}> could not be parsed as a number.", column))?,
+    })
+}
+
+fn parse_time(value: &str) -> Result<u64, String> {
+    value
+        .parse()
+        .map_err(|_| format!("The time <{}> could not be parsed as seconds.", value))
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// Continues an FNV-1a hash over the line and the newline after it
+fn fnv(hash: u64, line: &str) -> u64 {
+    line.bytes().chain([b'\n']).fold(hash, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remove_consts::{game::Player, ruleset::Ruleset};
+
+    use super::{format_time, Correspondence};
+
+    fn started() -> Correspondence {
+        Correspondence::new(
+            Ruleset::default(),
+            ["Ada".to_owned(), "Grace Hopper".to_owned()],
+            1_792_000_000,
+        )
+    }
+
+    #[test]
+    fn moves_are_added_to_the_file_in_turn() {
+        let mut game = started();
+        let mut text = game.to_text();
+        text += &game.play("ada", 3, 1_792_000_100).unwrap();
+        text += &game.play("Grace Hopper", 4, 1_792_086_500).unwrap();
+        assert_eq!(
+            game.play("Grace Hopper", 4, 1_792_086_600),
+            Err("It's Ada's turn, not Grace Hopper's.".to_owned())
+        );
+        assert!(game.play("Ada", 9, 1_792_086_600).is_err());
+
+        let read = Correspondence::parse(&text).unwrap();
+        assert_eq!(read.names, game.names);
+        assert_eq!(read.moves, game.moves);
+        assert_eq!(read.moves[1].side, Player::Two);
+        assert_eq!(read.moves[1].played_at, 1_792_086_500);
+        // Appending a move gives the same file as writing the game out whole
+        assert_eq!(read.to_text(), text);
+    }
+
+    #[test]
+    fn no_moves_can_be_played_once_the_game_is_over() {
+        let mut game = started();
+        for (name, column) in [("Ada", 3), ("Grace Hopper", 4)].repeat(3) {
+            game.play(name, column, 1_792_000_100).unwrap();
+        }
+        game.play("Ada", 3, 1_792_000_100).unwrap();
+        assert_eq!(
+            game.play("Grace Hopper", 4, 1_792_000_200),
+            Err("The game is over.".to_owned())
+        );
+    }
+
+    #[test]
+    fn catches_hand_edits_and_moves_out_of_turn() {
+        let mut game = started();
+        let mut text = game.to_text();
+        text += &game.play("Ada", 3, 1_792_000_100).unwrap();
+        assert!(Correspondence::parse(&text).is_ok());
+
+        let edited = text.replace("move 1792000100 one 3", "move 1792000100 one 2");
+        assert!(Correspondence::parse(&edited)
+            .unwrap_err()
+            .contains("edited by hand"));
+        assert!(
+            Correspondence::parse(&text.replace("Grace Hopper", "Grace"))
+                .unwrap_err()
+                .contains("edited by hand")
+        );
+
+        // Ada playing again, with a checksum worked out for the move, is still caught
+        let mut cheat = game.clone();
+        cheat.moves.push(super::Move {
+            played_at: 1_792_000_200,
+            side: Player::One,
+            column: 3,
+        });
+        assert_eq!(
+            Correspondence::parse(&cheat.to_text()),
+            Err("Line 8: Player one moved out of turn.".to_owned())
+        );
+        assert!(Correspondence::parse("rules columns=7").is_err());
+    }
+
+    #[test]
+    fn shows_times_in_utc() {
+        assert_eq!(format_time(0), "1970-01-01 00:00 UTC");
+        assert_eq!(format_time(951_782_400), "2000-02-29 00:00 UTC");
+        assert_eq!(format_time(1_792_359_144), "2026-10-18 21:32 UTC");
+    }
+}
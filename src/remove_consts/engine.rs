@@ -0,0 +1,517 @@
+use std::time::{Duration, Instant};
+
+use super::{
+    ai::best_move,
+    archive::{parse_moves, parse_rules, parse_size},
+    game::{Game, GameStatus, Variant, DEFAULT_COLUMNS, DEFAULT_ROWS},
+    win_conditions::{default_win_conditions, win_condition_from_name, WinCondition},
+};
+
+/* The engine protocol, for programs that choose moves. It's modelled on UCI from chess:
+an engine reads commands on standard input and answers on standard output, a line at a time.
+Commands are lower case, columns are zero indexed, and either side ignores lines it doesn't recognise.
+
+To the engine:
+  c4i                           Start. The engine names itself and its options, then sends c4iok.
+  setoption name <name> value <value>
+                                Change one of the options the engine offered.
+  isready                       Wait until the engine has caught up. It answers readyok.
+  newgame                       The next position is from a different game.
+  position [size <c>x<r>] [rules <win conditions>] [variants <variants>] [moves <moves>]
+                                The position to search. The board is 7x6 with the standard rules unless
+                                they're given. Win conditions are separated by commas, with '-' for spaces
+                                and the connect length in brackets unless it's 4, such as
+                                vertical,forward-diagonal(5). Moves are separated by commas too.
+  go [movetime <ms>] [depth <n>]
+                                Search the position for at most that long, or that many moves ahead,
+                                then answer bestmove.
+  stop                          Answer bestmove as soon as possible.
+  quit                          Exit.
+
+From the engine:
+  id name <name>                What the engine is called. It can send id author <author> too.
+  option name <name> type spin default <n> min <n> max <n>
+                                An option that setoption can change.
+  c4iok                         Started, and ready for commands.
+  readyok                       Caught up.
+  info <anything>               Progress while searching, such as info depth 3. Just for people to read.
+  bestmove <column|none>        The column to play, or none when the game is over.
+
+A move looks like:
+  > c4i                         < id name Connect 4 0.1.0, option name Depth ..., c4iok
+  > position moves 3,3
+  > go movetime 500             < info depth 1 time 0, ..., bestmove 3
+*/
+
+// The deepest the computer player searches as an engine, however long it's given
+pub const MAX_DEPTH: usize = 12;
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum EngineCommand {
+    // c4i, which starts the handshake
+    Start,
+    SetOption { name: String, value: String },
+    IsReady,
+    NewGame,
+    Position(Position),
+    Go(Limits),
+    Stop,
+    Quit,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum EngineReply {
+    Name(String),
+    Author(String),
+    // Everything after `option`
+    Option(String),
+    // c4iok, which ends the handshake
+    Started,
+    Ready,
+    Info(String),
+    // Nothing when the game is over
+    BestMove(Option<usize>),
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Position {
+    pub columns: usize,
+    pub rows: usize,
+    // The display names of the win conditions, with their connect lengths
+    pub rules: Vec<String>,
+    pub variants: Vec<Variant>,
+    pub moves: Vec<usize>,
+}
+
+// No limit means the engine decides
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub struct Limits {
+    pub movetime: Option<Duration>,
+    pub depth: Option<usize>,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position {
+            columns: DEFAULT_COLUMNS,
+            rows: DEFAULT_ROWS,
+            rules: default_win_conditions()
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            variants: vec![],
+            moves: vec![],
+        }
+    }
+}
+
+impl Position {
+    pub fn from_game(game: &Game) -> Self {
+        Position {
+            columns: game.board().len(),
+            rows: game.board().first().map_or(0, |column| column.len()),
+            rules: game
+                .win_conditions()
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            variants: game.variants().to_vec(),
+            moves: game.moves().to_vec(),
+        }
+    }
+
+    pub fn win_conditions(&self) -> Vec<Box<dyn WinCondition>> {
+        self.rules
+            .iter()
+            .filter_map(|name| win_condition_from_name(name))
+            .collect()
+    }
+
+    // The game after the moves, which must all be legal
+    pub fn replay<'a>(
+        &self,
+        win_conditions: &'a Vec<Box<dyn WinCondition>>,
+    ) -> Result<Game<'a>, String> {
+        if self.columns == 0 || self.rows == 0 {
+            return Err("The board needs at least one column and one row.".to_owned());
+        }
+        let mut game =
+            Game::initialise(self.columns, self.rows, win_conditions).with_variants(&self.variants);
+        for (index, &column) in self.moves.iter().enumerate() {
+            if game.status != GameStatus::Started {
+                return Err(format!(
+                    "Move {} was played after the game had finished.",
+                    index + 1
+                ));
+            }
+            game = game.play_on_column(column).map_err(|error| {
+                format!("Move {} in column {}: {}", index + 1, column, error.message)
+            })?;
+        }
+        Ok(game)
+    }
+
+    fn to_line(&self) -> String {
+        // Names are written without spaces, so each setting is a single word
+        let rules: Vec<String> = self
+            .rules
+            .iter()
+            .map(|name| name.to_lowercase().replace(" (", "(").replace(' ', "-"))
+            .collect();
+        let mut line = format!(
+            "position size {}x{} rules {}",
+            self.columns,
+            self.rows,
+            rules.join(",")
+        );
+        if !self.variants.is_empty() {
+            let variants: Vec<String> = self
+                .variants
+                .iter()
+                .map(|x| x.to_string().to_lowercase())
+                .collect();
+            line += &format!(" variants {}", variants.join(","));
+        }
+        if !self.moves.is_empty() {
+            let moves: Vec<String> = self.moves.iter().map(|x| x.to_string()).collect();
+            line += &format!(" moves {}", moves.join(","));
+        }
+        line
+    }
+
+    fn parse(fields: &[&str]) -> Result<Self, String> {
+        let mut position = Position::default();
+        for pair in fields.chunks(2) {
+            let [setting, value] = pair else {
+                return Err(format!("<{}> needs a value.", pair[0]));
+            };
+            match *setting {
+                "size" => (position.columns, position.rows) = parse_size(value)?,
+                "rules" => position.rules = parse_rules(value)?,
+                "variants" => {
+                    position.variants = value
+                        .split(',')
+                        .map(|name| {
+                            Variant::from_name(name)
+                                .ok_or(format!("Did not recognise <{}> as a variant.", name))
+                        })
+                        .collect::<Result<_, _>>()?
+                }
+                "moves" => position.moves = parse_moves(value)?,
+                other => return Err(format!("Did not recognise <{}> in a position.", other)),
+            }
+        }
+        Ok(position)
+    }
+}
+
+impl EngineCommand {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let command = match fields[..] {
+            ["c4i"] => EngineCommand::Start,
+            ["setoption", "name", name, "value", ref value @ ..] if !value.is_empty() => {
+                EngineCommand::SetOption {
+                    name: name.to_owned(),
+                    value: value.join(" "),
+                }
+            }
+            ["isready"] => EngineCommand::IsReady,
+            ["newgame"] => EngineCommand::NewGame,
+            ["position", ref settings @ ..] => EngineCommand::Position(Position::parse(settings)?),
+            ["go", ref limits @ ..] => EngineCommand::Go(parse_limits(limits)?),
+            ["stop"] => EngineCommand::Stop,
+            ["quit"] => EngineCommand::Quit,
+            _ => return Err(format!("Did not recognise <{}>.", line.trim())),
+        };
+        Ok(command)
+    }
+
+    pub fn to_line(&self) -> String {
+        match self {
+            EngineCommand::Start => "c4i".to_owned(),
+            EngineCommand::SetOption { name, value } => {
+                format!("setoption name {} value {}", name, value)
+            }
+            EngineCommand::IsReady => "isready".to_owned(),
+            EngineCommand::NewGame => "newgame".to_owned(),
+            EngineCommand::Position(position) => position.to_line(),
+            EngineCommand::Go(limits) => {
+                let mut line = "go".to_owned();
+                if let Some(movetime) = limits.movetime {
+                    line += &format!(" movetime {}", movetime.as_millis());
+                }
+                if let Some(depth) = limits.depth {
+                    line += &format!(" depth {}", depth);
+                }
+                line
+            }
+            EngineCommand::Stop => "stop".to_owned(),
+            EngineCommand::Quit => "quit".to_owned(),
+        }
+    }
+}
+
+impl EngineReply {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+        let reply = match (keyword, rest.split_once(' ')) {
+            ("id", Some(("name", name))) => EngineReply::Name(name.trim().to_owned()),
+            ("id", Some(("author", author))) => EngineReply::Author(author.trim().to_owned()),
+            ("option", _) => EngineReply::Option(rest.to_owned()),
+            ("c4iok", _) => EngineReply::Started,
+            ("readyok", _) => EngineReply::Ready,
+            ("info", _) => EngineReply::Info(rest.to_owned()),
+            // Anything after the move, such as a move to ponder on, is left for later versions
+            ("bestmove", _) => match rest.split_whitespace().next() {
+                Some("none") => EngineReply::BestMove(None),
+                Some(column) => EngineReply::BestMove(Some(
+                    column
+                        .parse()
+                        .map_err(|_| format!("<{}> could not be parsed as a column.", column))?,
+                )),
+                None => return Err("bestmove needs a column.".to_owned()),
+            },
+            _ => return Err(format!("Did not recognise <{}>.", line)),
+        };
+        Ok(reply)
+    }
+
+    pub fn to_line(&self) -> String {
+        match self {
+            EngineReply::Name(name) => format!("id name {}", name),
+            EngineReply::Author(author) => format!("id author {}", author),
+            EngineReply::Option(option) => format!("option {}", option),
+            EngineReply::Started => "c4iok".to_owned(),
+            EngineReply::Ready => "readyok".to_owned(),
+            EngineReply::Info(info) => format!("info {}", info),
+            EngineReply::BestMove(Some(column)) => format!("bestmove {}", column),
+            EngineReply::BestMove(None) => "bestmove none".to_owned(),
+        }
+    }
+}
+
+fn parse_limits(fields: &[&str]) -> Result<Limits, String> {
+    let mut limits = Limits::default();
+    for pair in fields.chunks(2) {
+        let [limit, value] = pair else {
+            return Err(format!("<{}> needs a value.", pair[0]));
+        };
+        let number = value
+            .parse::<u64>()
+            .map_err(|_| format!("<{}> could not be parsed as a number.", value))?;
+        match *limit {
+            "movetime" => limits.movetime = Some(Duration::from_millis(number)),
+            "depth" => limits.depth = Some(number as usize),
+            other => return Err(format!("Did not recognise <{}> as a limit.", other)),
+        }
+    }
+    Ok(limits)
+}
+
+/* The computer player as an engine. Searches are quick enough to finish before any stop arrives,
+so stop is accepted but has nothing to interrupt.
+*/
+pub struct ComputerEngine {
+    // How far ahead to search when go doesn't say
+    depth: usize,
+    position: Position,
+}
+
+impl ComputerEngine {
+    pub fn new(depth: usize) -> Self {
+        ComputerEngine {
+            depth: depth.min(MAX_DEPTH),
+            position: Position::default(),
+        }
+    }
+
+    // The replies to a line, or nothing once told to quit. Mistakes are reported as info.
+    pub fn respond(&mut self, line: &str) -> Option<Vec<EngineReply>> {
+        if line.trim().is_empty() {
+            return Some(vec![]);
+        }
+        let command = match EngineCommand::parse(line) {
+            Ok(command) => command,
+            Err(message) => return Some(vec![EngineReply::Info(format!("string {}", message))]),
+        };
+        let replies = match command {
+            EngineCommand::Start => vec![
+                EngineReply::Name(format!("Connect 4 {}", env!("CARGO_PKG_VERSION"))),
+                EngineReply::Option(format!(
+                    "name Depth type spin default {} min 1 max {}",
+                    self.depth, MAX_DEPTH
+                )),
+                EngineReply::Started,
+            ],
+            EngineCommand::SetOption { name, value } => {
+                match (name.to_lowercase().as_str(), value.parse::<usize>()) {
+                    ("depth", Ok(depth)) if (1..=MAX_DEPTH).contains(&depth) => {
+                        self.depth = depth;
+                        vec![]
+                    }
+                    ("depth", _) => vec![EngineReply::Info(format!(
+                        "string The depth must be from 1 to {}.",
+                        MAX_DEPTH
+                    ))],
+                    _ => vec![EngineReply::Info(format!(
+                        "string There's no option called <{}>.",
+                        name
+                    ))],
+                }
+            }
+            EngineCommand::IsReady => vec![EngineReply::Ready],
+            EngineCommand::Position(position) => {
+                match position.replay(&position.win_conditions()) {
+                    Ok(_) => {
+                        self.position = position;
+                        vec![]
+                    }
+                    Err(message) => vec![EngineReply::Info(format!("string {}", message))],
+                }
+            }
+            EngineCommand::Go(limits) => self.search(limits),
+            EngineCommand::NewGame | EngineCommand::Stop => vec![],
+            EngineCommand::Quit => return None,
+        };
+        Some(replies)
+    }
+
+    /* Searches a move deeper each time until it reaches the depth, or it's used up a quarter of the time.
+    Each search takes several times longer than the one before, so there'd be no time to finish another.
+    */
+    fn search(&self, limits: Limits) -> Vec<EngineReply> {
+        let win_conditions = self.position.win_conditions();
+        let Ok(game) = self.position.replay(&win_conditions) else {
+            return vec![EngineReply::BestMove(None)];
+        };
+        let started = Instant::now();
+        let deepest = limits.depth.unwrap_or(self.depth).clamp(1, MAX_DEPTH);
+        let mut replies = vec![];
+        let mut best = None;
+        for depth in 1..=deepest {
+            best = best_move(&game, depth);
+            replies.push(EngineReply::Info(format!(
+                "depth {} time {}",
+                depth,
+                started.elapsed().as_millis()
+            )));
+            if best.is_none() || limits.movetime.is_some_and(|x| started.elapsed() * 4 > x) {
+                break;
+            }
+        }
+        replies.push(EngineReply::BestMove(best));
+        replies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::remove_consts::game::Variant;
+
+    use super::{ComputerEngine, EngineCommand, EngineReply, Limits, Position};
+
+    #[test]
+    fn commands_survive_a_round_trip() {
+        let commands = [
+            EngineCommand::Start,
+            EngineCommand::SetOption {
+                name: "Depth".to_owned(),
+                value: "6".to_owned(),
+            },
+            EngineCommand::IsReady,
+            EngineCommand::NewGame,
+            EngineCommand::Position(Position::default()),
+            EngineCommand::Position(Position {
+                columns: 9,
+                rows: 7,
+                rules: vec!["Vertical (5)".to_owned(), "Forward Diagonal".to_owned()],
+                variants: vec![Variant::Misere],
+                moves: vec![3, 3, 4],
+            }),
+            EngineCommand::Go(Limits::default()),
+            EngineCommand::Go(Limits {
+                movetime: Some(Duration::from_millis(250)),
+                depth: Some(3),
+            }),
+            EngineCommand::Stop,
+            EngineCommand::Quit,
+        ];
+        for command in commands {
+            assert_eq!(EngineCommand::parse(&command.to_line()), Ok(command));
+        }
+        assert_eq!(
+            EngineCommand::parse("position size 9x7 rules vertical(5),forward-diagonal moves 3")
+                .unwrap()
+                .to_line(),
+            "position size 9x7 rules vertical(5),forward-diagonal moves 3"
+        );
+        assert!(EngineCommand::parse("position size").is_err());
+        assert!(EngineCommand::parse("position rules sideways").is_err());
+        assert!(EngineCommand::parse("go movetime soon").is_err());
+        assert!(EngineCommand::parse("uci").is_err());
+    }
+
+    #[test]
+    fn replies_survive_a_round_trip() {
+        let replies = [
+            EngineReply::Name("Deep Disc 2".to_owned()),
+            EngineReply::Author("Ada".to_owned()),
+            EngineReply::Option("name Depth type spin default 4 min 1 max 12".to_owned()),
+            EngineReply::Started,
+            EngineReply::Ready,
+            EngineReply::Info("depth 3 time 12".to_owned()),
+            EngineReply::BestMove(Some(3)),
+            EngineReply::BestMove(None),
+        ];
+        for reply in replies {
+            assert_eq!(EngineReply::parse(&reply.to_line()), Ok(reply));
+        }
+        assert_eq!(
+            EngineReply::parse("bestmove 5 ponder 2\r\n"),
+            Ok(EngineReply::BestMove(Some(5)))
+        );
+        assert!(EngineReply::parse("bestmove").is_err());
+        assert!(EngineReply::parse("hello").is_err());
+    }
+
+    #[test]
+    fn the_computer_engine_answers_the_protocol() {
+        let mut engine = ComputerEngine::new(4);
+        let handshake = engine.respond("c4i").unwrap();
+        assert!(matches!(handshake[0], EngineReply::Name(_)));
+        assert_eq!(handshake.last(), Some(&EngineReply::Started));
+        assert_eq!(engine.respond("isready"), Some(vec![EngineReply::Ready]));
+        assert_eq!(engine.respond("setoption name Depth value 3"), Some(vec![]));
+        // Player One has three stacked in column 0
+        assert_eq!(engine.respond("position moves 0,1,0,1,0,6"), Some(vec![]));
+        assert_eq!(
+            engine.respond("go movetime 1000").unwrap().last(),
+            Some(&EngineReply::BestMove(Some(0)))
+        );
+        assert_eq!(engine.respond("position moves 0,1,0,1,0,1,0"), Some(vec![]));
+        assert_eq!(
+            engine.respond("go depth 2").unwrap().last(),
+            Some(&EngineReply::BestMove(None))
+        );
+        assert_eq!(engine.respond("quit"), None);
+    }
+
+    #[test]
+    fn the_computer_engine_reports_mistakes() {
+        let mut engine = ComputerEngine::new(4);
+        let info = |replies: Option<Vec<EngineReply>>| match replies.as_deref() {
+            Some([EngineReply::Info(info)]) => info.starts_with("string "),
+            _ => false,
+        };
+        assert!(info(engine.respond("setoption name Depth value 99")));
+        assert!(info(engine.respond("setoption name Colour value red")));
+        assert!(info(engine.respond("position size 4x4 moves 0,0,0,0,0")));
+        assert!(info(engine.respond("fly")));
+        assert_eq!(engine.respond(""), Some(vec![]));
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{
+    engine::{EngineCommand, EngineReply, Limits, Position},
+    game::{Game, Player},
+};
+
+// How long an engine has to start and finish the handshake
+const START_TIMEOUT: Duration = Duration::from_secs(10);
+
+// How long an engine has past its time to answer, and again after being told to stop
+const GRACE: Duration = Duration::from_secs(1);
+
+// How long an engine has to exit after quit before it's killed
+const QUIT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/* A program that speaks the engine protocol, run as a player. Its output is read on another thread,
+so a slow or silent engine can't hang the game. Anything it writes to standard error is thrown away,
+since the game is using the terminal.
+*/
+pub struct ExternalEngine {
+    // What the engine calls itself, or the program until it says
+    pub name: String,
+    child: Child,
+    input: ChildStdin,
+    output: Receiver<String>,
+    // The moves of the last position sent, to tell when a new game has started
+    moves: Option<Vec<usize>>,
+}
+
+impl ExternalEngine {
+    // Runs the command, split on whitespace, and waits for the handshake
+    pub fn launch(command: &str) -> Result<Self, String> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or("The engine command is empty.")?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|error| format!("Could not start <{}>: {}", command, error))?;
+        let input = child.stdin.take().expect("The engine's input is piped");
+        let stdout = child.stdout.take().expect("The engine's output is piped");
+        let (lines, output) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if lines.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = ExternalEngine {
+            name: program.to_owned(),
+            child,
+            input,
+            output,
+            moves: None,
+        };
+        engine.send(&EngineCommand::Start)?;
+        let deadline = Instant::now() + START_TIMEOUT;
+        loop {
+            match engine.next_reply(deadline)? {
+                Some(EngineReply::Name(name)) => engine.name = name,
+                Some(EngineReply::Started) => break,
+                Some(_) => (),
+                None => return Err(format!("{} didn't finish starting.", engine.name)),
+            }
+        }
+        engine.send(&EngineCommand::IsReady)?;
+        loop {
+            match engine.next_reply(deadline)? {
+                Some(EngineReply::Ready) => return Ok(engine),
+                Some(_) => (),
+                None => return Err(format!("{} didn't say it was ready.", engine.name)),
+            }
+        }
+    }
+
+    /* Asks for a move with the time given. An engine that's still thinking after its grace is told to stop,
+    and one that still doesn't answer, or chooses a column that isn't open, gets an error.
+    */
+    pub fn best_move(&mut self, game: &Game, movetime: Duration) -> Result<usize, String> {
+        let position = Position::from_game(game);
+        let same_game = self
+            .moves
+            .as_ref()
+            .is_some_and(|moves| position.moves.starts_with(moves));
+        if !same_game {
+            self.send(&EngineCommand::NewGame)?;
+        }
+        self.moves = Some(position.moves.clone());
+        self.send(&EngineCommand::Position(position))?;
+        self.send(&EngineCommand::Go(Limits {
+            movetime: Some(movetime),
+            depth: None,
+        }))?;
+
+        let mut deadline = Instant::now() + movetime + GRACE;
+        let mut stopped = false;
+        let choice = loop {
+            match self.next_reply(deadline)? {
+                Some(EngineReply::BestMove(choice)) => break choice,
+                Some(_) => (),
+                None if !stopped => {
+                    self.send(&EngineCommand::Stop)?;
+                    deadline = Instant::now() + GRACE;
+                    stopped = true;
+                }
+                None => return Err(format!("{} ran out of time.", self.name)),
+            }
+        };
+        match choice {
+            Some(column)
+                if game
+                    .board()
+                    .get(column)
+                    .is_some_and(|x| x.contains(&Player::None)) =>
+            {
+                Ok(column)
+            }
+            Some(column) => Err(format!(
+                "{} chose column {}, which isn't open.",
+                self.name, column
+            )),
+            None => Err(format!("{} didn't choose a move.", self.name)),
+        }
+    }
+
+    fn send(&mut self, command: &EngineCommand) -> Result<(), String> {
+        writeln!(self.input, "{}", command.to_line())
+            .and_then(|()| self.input.flush())
+            .map_err(|_| format!("{} has stopped.", self.name))
+    }
+
+    // The next line the engine sends that's part of the protocol, or nothing once the deadline passes
+    fn next_reply(&mut self, deadline: Instant) -> Result<Option<EngineReply>, String> {
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.output.recv_timeout(remaining) {
+                Ok(line) => {
+                    if let Ok(reply) = EngineReply::parse(&line) {
+                        return Ok(Some(reply));
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => return Ok(None),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(format!("{} has stopped.", self.name))
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        let _ = self.send(&EngineCommand::Quit);
+        let deadline = Instant::now() + QUIT_TIMEOUT;
+        while Instant::now() < deadline {
+            if !matches!(self.child.try_wait(), Ok(None)) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{env, fs, process, time::Duration};
+
+    use crate::remove_consts::{game::Game, win_conditions::default_win_conditions};
+
+    use super::ExternalEngine;
+
+    // A shell script that answers the handshake and then plays the same column every time
+    fn engine_playing(column: &str) -> String {
+        let path =
+            env::temp_dir().join(format!("connect-4-engine-{}-{}.sh", column, process::id()));
+        fs::write(
+            &path,
+            format!(
+                "while read command rest; do
+  case $command in
+    c4i) echo 'id name Column Bot'; echo 'c4iok';;
+    isready) echo 'readyok';;
+    go) echo 'info thinking'; echo 'bestmove {}';;
+    quit) exit;;
+  esac
+done
+",
+                column
+            ),
+        )
+        .unwrap();
+        format!("sh {}", path.display())
+    }
+
+    #[test]
+    fn plays_the_moves_an_engine_chooses() {
+        let win_conditions = default_win_conditions();
+        let mut game = Game::initialise(7, 6, &win_conditions);
+        let mut engine = ExternalEngine::launch(&engine_playing("3")).unwrap();
+        assert_eq!(engine.name, "Column Bot");
+        assert_eq!(engine.best_move(&game, Duration::from_millis(100)), Ok(3));
+        for _ in 0..6 {
+            game = game.play_on_column(3).unwrap();
+        }
+        // Column 3 is full now
+        assert!(engine.best_move(&game, Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn reports_engines_that_cant_play() {
+        assert!(ExternalEngine::launch("").is_err());
+        assert!(ExternalEngine::launch("connect-4-no-such-engine").is_err());
+        let win_conditions = default_win_conditions();
+        let game = Game::initialise(7, 6, &win_conditions);
+        let mut engine = ExternalEngine::launch(&engine_playing("none")).unwrap();
+        assert!(engine.best_move(&game, Duration::from_millis(100)).is_err());
+    }
+}
@@ -1,17 +1,32 @@
 use std::fmt::Display;
 
+use crate::{
+    labels::ColumnLabels,
+    theme::{render_board, PLAIN},
+};
+
 use super::win_conditions::WinCondition;
 
 pub const DEFAULT_COLUMNS: usize = 7;
 pub const DEFAULT_ROWS: usize = 6;
 
-#[derive(PartialEq, Debug, Clone, Copy)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
 pub enum Player {
     None,
     One,
     Two,
 }
 
+impl Player {
+    pub fn opponent(&self) -> Self {
+        match self {
+            Player::One => Player::Two,
+            Player::Two => Player::One,
+            Player::None => panic!("Invalid game state"),
+        }
+    }
+}
+
 impl Display for Player {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -22,6 +37,29 @@ impl Display for Player {
     }
 }
 
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum Variant {
+    // Connecting loses the game rather than winning it
+    Misere,
+}
+
+impl Variant {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "misere" => Some(Variant::Misere),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Variant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Variant::Misere => f.write_str("Misere"),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum GameStatus {
     Started,
@@ -48,6 +86,8 @@ impl<'a> GameError<'a> {
 pub struct Game<'a> {
     game_board: Vec<Vec<Player>>,
     win_conditions: &'a Vec<Box<dyn WinCondition>>,
+    moves: Vec<usize>,
+    variants: Vec<Variant>,
     pub winner: Option<Player>,
     pub status: GameStatus,
     pub current: Player,
@@ -58,6 +98,8 @@ impl<'a> std::fmt::Debug for Game<'a> {
         f.debug_struct("Game")
             .field("current", &self.current)
             .field("game_board", &self.game_board)
+            .field("moves", &self.moves)
+            .field("variants", &self.variants)
             .field("winner", &self.winner)
             .field("status", &self.status)
             .finish()
@@ -66,22 +108,11 @@ impl<'a> std::fmt::Debug for Game<'a> {
 
 impl<'a> std::fmt::Display for Game<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut output: String = "\n".to_owned();
-        for j in (0..self.game_board[0].len()).rev() {
-            for i in 0..self.game_board.len() {
-                output += match self.game_board[i][j] {
-                    Player::None => ".",
-                    Player::One => "x",
-                    Player::Two => "o",
-                }
-            }
-            output += "\n"
-        }
-        for i in 0..self.game_board.len() {
-            output += &i.to_string();
-        }
-        output += "\n";
-        f.write_str(&output)
+        f.write_str(&render_board(
+            &self.game_board,
+            &PLAIN,
+            ColumnLabels::default(),
+        ))
     }
 }
 
@@ -97,9 +128,83 @@ impl<'a> Game<'a> {
             winner: None,
             status: GameStatus::Started,
             win_conditions,
+            moves: vec![],
+            variants: vec![],
         }
     }
 
+    pub fn with_variants(mut self, variants: &[Variant]) -> Self {
+        self.variants = variants.to_vec();
+        self
+    }
+
+    pub fn variants(&self) -> &[Variant] {
+        &self.variants
+    }
+
+    pub fn board(&self) -> &Vec<Vec<Player>> {
+        &self.game_board
+    }
+
+    pub fn win_conditions(&self) -> &[Box<dyn WinCondition>] {
+        self.win_conditions
+    }
+
+    // The columns played so far, in order - zero indexed
+    pub fn moves(&self) -> &[usize] {
+        &self.moves
+    }
+
+    // The cells making up the connect 4 that won the game, if there is one
+    pub fn winning_line(&self) -> Option<Vec<(usize, usize)>> {
+        if self.status != GameStatus::Completed {
+            return None;
+        }
+        for column in 0..self.game_board.len() {
+            for row in 0..self.game_board[column].len() {
+                for win_condition in self.win_conditions {
+                    if win_condition.is_met(&self.game_board, column, row) {
+                        return Some(win_condition.line(column, row));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /* Whether the player could still win, however the rest of the game went. Every empty cell is
+    filled with the discs that would win it for them, which are the opponent's in misere, and any
+    line is enough. Whose turn each disc would be is ignored, so this can only err towards a win.
+    */
+    pub fn could_win(&self, player: Player) -> bool {
+        let connecting = match self.variants.contains(&Variant::Misere) {
+            true => player.opponent(),
+            false => player,
+        };
+        let filled: Vec<Vec<Player>> = self
+            .game_board
+            .iter()
+            .map(|column| {
+                column
+                    .iter()
+                    .map(|&cell| match cell {
+                        Player::None => connecting,
+                        disc => disc,
+                    })
+                    .collect()
+            })
+            .collect();
+        filled.iter().enumerate().any(|(column, cells)| {
+            cells.iter().enumerate().any(|(row, &cell)| {
+                cell == connecting
+                    && self
+                        .win_conditions
+                        .iter()
+                        .any(|x| x.is_met(&filled, column, row))
+            })
+        })
+    }
+
     // Plays on the column - zero indexed
     pub fn play_on_column(self, column: usize) -> Result<Self, GameError<'a>> {
         let mut active_state = self.clone();
@@ -114,6 +219,7 @@ impl<'a> Game<'a> {
         if let Some(error) = active_state.place_piece(column).err() {
             return Err(GameError::with_message(error, old_state));
         }
+        active_state.moves.push(column);
 
         let mut available_move_count = 0;
         for column in 0..active_state.game_board.len() {
@@ -123,7 +229,11 @@ impl<'a> Game<'a> {
                     _ => {
                         if active_state.has_four_connected(column, row) {
                             active_state.status = GameStatus::Completed;
-                            active_state.winner = Some(active_state.current);
+                            active_state.winner =
+                                match active_state.variants.contains(&Variant::Misere) {
+                                    true => Some(active_state.current.opponent()),
+                                    false => Some(active_state.current),
+                                };
                             return Ok(active_state);
                         }
                     }
@@ -136,20 +246,14 @@ impl<'a> Game<'a> {
             return Ok(active_state);
         }
 
-        active_state.current = match active_state.current {
-            Player::One => Player::Two,
-            Player::Two => Player::One,
-            Player::None => panic!("Invalid game state"),
-        };
+        active_state.current = active_state.current.opponent();
         Ok(active_state)
     }
 
     fn place_piece(&mut self, column: usize) -> Result<(), &str> {
-        dbg!(&self);
         for row in 0..self.game_board[column].len() {
             if self.game_board[column][row] == Player::None {
                 self.game_board[column][row] = self.current;
-                dbg!(&self);
                 return Ok(());
             }
         }
@@ -169,8 +273,8 @@ impl<'a> Game<'a> {
 #[cfg(test)]
 mod tests {
     use crate::remove_consts::{
-        game::{GameStatus, Player, DEFAULT_COLUMNS, DEFAULT_ROWS},
-        win_conditions::default_win_conditions,
+        game::{GameStatus, Player, Variant, DEFAULT_COLUMNS, DEFAULT_ROWS},
+        win_conditions::{default_win_conditions, win_conditions_with_length},
     };
 
     use super::Game;
@@ -329,4 +433,85 @@ mod tests {
         let result = game.play_on_column(0);
         assert!(result.is_ok())
     }
+
+    #[test]
+    fn records_the_moves_that_were_played() {
+        let win_conditions = default_win_conditions();
+        let mut game = Game::initialise(DEFAULT_COLUMNS, DEFAULT_ROWS, &win_conditions);
+        game = game.play_on_column(3).unwrap();
+        game = game.play_on_column(5).unwrap();
+        game = game.play_on_column(7).unwrap_err().previous_state;
+        game = game.play_on_column(0).unwrap();
+        assert_eq!(game.moves(), &[3, 5, 0]);
+    }
+
+    /*
+    o . . .
+    o . . .
+    o . . .
+    x x x x
+    */
+    #[test]
+    fn finds_the_winning_line_once_the_game_is_won() {
+        let win_conditions = default_win_conditions();
+        let mut game = Game::initialise(4, 4, &win_conditions);
+        for column in [0, 0, 1, 0, 2, 0] {
+            game = game.play_on_column(column).unwrap();
+        }
+        assert_eq!(game.winning_line(), None);
+        game = game.play_on_column(3).unwrap();
+        assert_eq!(
+            game.winning_line(),
+            Some(vec![(0, 0), (1, 0), (2, 0), (3, 0)])
+        );
+    }
+
+    /*
+    o o o o .
+    x x x x x
+    */
+    #[test]
+    fn recognises_a_win_with_a_longer_connect_length() {
+        let win_conditions = win_conditions_with_length(5);
+        let mut game = Game::initialise(5, 2, &win_conditions);
+        for column in [0, 0, 1, 1, 2, 2, 3, 3] {
+            game = game.play_on_column(column).unwrap();
+        }
+        assert_eq!(game.status, GameStatus::Started);
+        game = game.play_on_column(4).unwrap();
+        assert_eq!(game.status, GameStatus::Completed);
+        assert_eq!(game.winner, Some(Player::One));
+        assert_eq!(game.winning_line().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn connecting_loses_in_a_misere_game() {
+        let win_conditions = default_win_conditions();
+        let mut game = Game::initialise(4, 4, &win_conditions).with_variants(&[Variant::Misere]);
+        for column in [0, 1, 0, 1, 0, 1, 0] {
+            game = game.play_on_column(column).unwrap();
+        }
+        assert_eq!(game.status, GameStatus::Completed);
+        assert_eq!(game.winner, Some(Player::Two));
+    }
+
+    /*
+    o . . .
+    x o x .
+    */
+    #[test]
+    fn only_a_player_with_a_line_left_open_could_win() {
+        let win_conditions = default_win_conditions();
+        let mut game = Game::initialise(4, 2, &win_conditions);
+        assert!(game.could_win(Player::One) && game.could_win(Player::Two));
+        for column in [0, 1, 2, 0] {
+            game = game.play_on_column(column).unwrap();
+        }
+        assert!(!game.could_win(Player::One));
+        assert!(game.could_win(Player::Two));
+
+        let game = game.with_variants(&[Variant::Misere]);
+        assert!(game.could_win(Player::One));
+        assert!(!game.could_win(Player::Two));
+    }
 }
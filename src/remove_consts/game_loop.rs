@@ -1,157 +1,545 @@
-use std::io;
+use std::{fs, io, time::Instant};
 
-use crate::remove_consts::{
-    game::{Game, GameStatus, DEFAULT_COLUMNS, DEFAULT_ROWS},
-    win_conditions::{
-        default_win_conditions, DiagonalWinCondition, HorizontalWinCondition,
-        ReverseDiagonalWinCondition, VerticalWinCondition, WinCondition,
+use crate::{
+    accessible,
+    labels::ColumnLabels,
+    locale,
+    remove_consts::{
+        ai::{best_move, PlayerType, Players},
+        archive::{Archive, GameRecord, DEFAULT_ARCHIVE_PATH},
+        clock::{self, Clock, TimeControl},
+        game::{Game, GameStatus, Player, DEFAULT_COLUMNS, DEFAULT_ROWS},
+        gif::{self, GifOptions},
+        ruleset::Ruleset,
+        svg::{self, SvgOptions},
+        win_conditions::{
+            default_win_conditions, DiagonalWinCondition, HorizontalWinCondition,
+            ReverseDiagonalWinCondition, VerticalWinCondition, WinCondition,
+        },
     },
+    scoreboard::Scoreboard,
+    stats::{self, PlayedGame, DEFAULT_STATS_PATH},
+    theme::Theme,
+    turn::{self, TurnCommand},
 };
 
-pub fn play() {
+/* Plays with the preset ruleset if there is one, otherwise asks for the rules before every game,
+or just the first game of a match.
+Games without a human player are played without any questions afterwards, and only once unless they're a match.
+The player types follow the named players, so they swap sides with them between the games of a match.
+With a time control, a player's clock runs from their turn starting until they move, and running out
+loses the game. Nothing interrupts the prompt, so a human player's flag falls when they answer it.
+*/
+pub fn play(
+    mut preset: Option<Ruleset>,
+    players: Players,
+    time_control: Option<TimeControl>,
+    theme: &Theme,
+    labels: ColumnLabels,
+    mut scoreboard: Scoreboard,
+) {
     let stdin = io::stdin();
     let input = &mut String::new();
+    let mut bot = players.bot();
 
     loop {
-        println!("<<Fully Customisable Mode>>");
-        println!("Would you like to play with a default gameboard? Y/n");
-        input.clear();
-        stdin.read_line(input).expect("Error reading from stdio");
-        let mut columns: usize = DEFAULT_COLUMNS;
-        let mut rows: usize = DEFAULT_ROWS;
-        if input.trim() == "n" {
-            loop {
-                println!("How many columns?");
-                input.clear();
-                stdin.read_line(input).expect("Error reading from stdio");
-                match input.trim().parse::<usize>() {
-                    Ok(parsed) => {
-                        columns = parsed;
-                        break;
-                    }
-                    Err(_) => {
-                        eprintln!(
-                            "The input <{}> could not be parsed as a usize. Please try again.",
-                            input
-                        );
-                        continue;
-                    }
-                };
-            }
-            loop {
-                println!("How many rows?");
-                input.clear();
-                stdin.read_line(input).expect("Error reading from stdio");
-                match input.trim().parse::<usize>() {
-                    Ok(parsed) => {
-                        rows = parsed;
-                        break;
-                    }
-                    Err(_) => {
-                        eprintln!(
-                            "The input <{}> could not be parsed as a usize. Please try again.",
-                            input
-                        );
-                        continue;
-                    }
-                };
-            }
+        if !accessible::enabled() {
+            println!("{}", locale::text("mode.custom", &[]));
         }
-        println!("Would you play to play with the standard ruleset? Y/n");
-        input.clear();
-        stdin.read_line(input).expect("Error reading from stdio");
-        let mut win_conditions: Vec<Box<dyn WinCondition>> = vec![];
-        if input.trim() == "n" {
-            println!("Do you want to allow for vertical connect 4s? Y/n");
-            input.clear();
-            stdin.read_line(input).expect("Error reading from stdio");
-            if input.trim() != "n" {
-                win_conditions.push(VerticalWinCondition::boxed())
-            }
-
-            println!("Do you want to allow for horizontal connect 4s? Y/n");
-            input.clear();
-            stdin.read_line(input).expect("Error reading from stdio");
-            if input.trim() != "n" {
-                win_conditions.push(HorizontalWinCondition::boxed())
-            }
-
-            println!("Do you want to allow for forward diagonal connect 4s? Y/n");
-            input.clear();
-            stdin.read_line(input).expect("Error reading from stdio");
-            if input.trim() != "n" {
-                win_conditions.push(DiagonalWinCondition::boxed())
-            }
-            println!("Do you want to allow for backwards diagonal connect 4s? Y/n");
-            input.clear();
-            stdin.read_line(input).expect("Error reading from stdio");
-            if input.trim() != "n" {
-                win_conditions.push(ReverseDiagonalWinCondition::boxed())
-            }
-        } else {
-            win_conditions = default_win_conditions()
+        if let Some(heading) = scoreboard.next_game() {
+            println!("{}", heading);
         }
-
-        let printable_win_conditions: Vec<String> =
-            win_conditions.iter().map(|x| format!("{}", x)).collect();
-
+        let ruleset = match &preset {
+            Some(ruleset) => ruleset.clone(),
+            None => match ask_for_ruleset(input) {
+                Some(ruleset) => ruleset,
+                None => continue,
+            },
+        };
+        if scoreboard.best_of.is_some() {
+            preset = Some(ruleset.clone());
+        }
+        let win_conditions = ruleset.win_conditions();
         println!(
-            "Beginning a game of board size: [{},{}], with the following win conditions: {}",
-            columns,
-            rows,
-            printable_win_conditions.join(", ")
+            "{}",
+            locale::text("game.beginning_with_rules", &[("ruleset", &ruleset)])
         );
 
-        let mut game = Game::initialise(columns, rows, &win_conditions);
+        let new_game = || {
+            Game::initialise(ruleset.columns, ruleset.rows, &win_conditions)
+                .with_variants(&ruleset.variants)
+        };
+        let mut game = new_game();
+        let mut clock = time_control.map(Clock::new);
+        let names = [scoreboard.name(Player::One), scoreboard.name(Player::Two)];
+        // The positions before each move, for undoing them
+        let mut earlier = vec![];
+        let mut show_board = true;
         loop {
-            println!("{}", game);
+            if show_board {
+                println!(
+                    "{}",
+                    accessible::present_board(game.board(), theme, labels, names)
+                );
+                if let Some(clock) = &clock {
+                    println!("{}", clock.describe(names, Instant::now()));
+                }
+                show_board = false;
+            }
             match game.status {
                 GameStatus::Started => (),
                 GameStatus::Completed => {
+                    let winner = game
+                        .winner
+                        .expect("Game has been win with no winner. Invalid state.");
                     println!(
-                        "Player {} wins!",
-                        game.winner
-                            .expect("Game has been win with no winner. Invalid state.")
+                        "{}",
+                        accessible::result(Some(scoreboard.name(winner)), game.moves().len())
                     );
                     break;
                 }
                 GameStatus::Draw => {
-                    println!("It's a draw!");
+                    println!("{}", accessible::result(None, game.moves().len()));
                     break;
                 }
             }
+            if let Some(clock) = &mut clock {
+                clock.start(game.current, Instant::now());
+                bot.limit_time(Some(clock.remaining(game.current, Instant::now())));
+            }
+            let player_type = type_of_player(&players, &scoreboard, game.current);
+            let choice = bot.choose(player_type, &game);
+            if let Some(error) = bot.take_engine_error() {
+                eprintln!(
+                    "{}",
+                    locale::text(
+                        "game.engine_failed",
+                        &[("error", &error), ("name", &scoreboard.name(game.current))]
+                    )
+                );
+            }
+            if choice.is_some() && flag_fell(&mut game, &clock, names) {
+                continue;
+            }
+            if let Some(column) = choice {
+                if !accessible::enabled() {
+                    println!(
+                        "{}",
+                        locale::text(
+                            "game.computer_plays",
+                            &[
+                                ("name", &scoreboard.name(game.current)),
+                                ("player_type", &player_type),
+                                ("column", &labels.label(column)),
+                            ]
+                        )
+                    );
+                }
+                earlier.push(game.clone());
+                stop_clock(&mut clock);
+                game = game
+                    .play_on_column(column)
+                    .expect("Players only choose open columns");
+                show_board = announce_move(&game, column, labels, names);
+                continue;
+            }
             println!(
-                "Player {}'s turn. Which column would you like to play in? 0-{}",
-                game.current,
-                DEFAULT_COLUMNS - 1
+                "{}",
+                accessible::turn_prompt(
+                    scoreboard.name(game.current),
+                    theme,
+                    game.current,
+                    &labels.range(ruleset.columns)
+                )
+            );
+            input.clear();
+            stdin.read_line(input).expect("Error reading from stdio");
+            if flag_fell(&mut game, &clock, names) {
+                continue;
+            }
+            let command = match turn::parse(input, labels, ruleset.columns) {
+                Ok(command) => command,
+                Err(message) => {
+                    eprintln!("{}", message);
+                    continue;
+                }
+            };
+            let opponent = game.current.opponent();
+            match command {
+                TurnCommand::Play(column) => {
+                    let previous = game.clone();
+                    game = match game.play_on_column(column) {
+                        Ok(game) => {
+                            earlier.push(previous);
+                            stop_clock(&mut clock);
+                            show_board = announce_move(&game, column, labels, names);
+                            game
+                        }
+                        Err(error) => {
+                            eprintln!("{}", error.message);
+                            error.previous_state
+                        }
+                    }
+                }
+                // Computer moves are taken back too, back to the last position a human had to play
+                TurnCommand::Undo => match earlier.pop() {
+                    Some(previous) => {
+                        game = previous;
+                        while type_of_player(&players, &scoreboard, game.current)
+                            != PlayerType::Human
+                        {
+                            match earlier.pop() {
+                                Some(previous) => game = previous,
+                                None => break,
+                            }
+                        }
+                        show_board = true;
+                    }
+                    None => eprintln!("{}", locale::text("game.nothing_to_undo", &[])),
+                },
+                TurnCommand::Hint => match best_move(&game, players.depth) {
+                    Some(column) => println!(
+                        "{}",
+                        locale::text("game.hint", &[("column", &labels.label(column))])
+                    ),
+                    None => eprintln!("{}", locale::text("game.no_hint", &[])),
+                },
+                TurnCommand::Save(path) => match turn::save_moves(&path, game.moves()) {
+                    Ok(()) => println!("{}", locale::text("game.saved_moves", &[("path", &path)])),
+                    Err(message) => eprintln!("{}", message),
+                },
+                TurnCommand::Load(path) => {
+                    let loaded = turn::load_moves(&path).and_then(|loaded| {
+                        turn::replay(new_game(), &loaded, |game, column| {
+                            game.play_on_column(column).map_err(|error| error.message)
+                        })
+                    });
+                    match loaded {
+                        Ok((replayed, replayed_earlier)) => {
+                            game = replayed;
+                            earlier = replayed_earlier;
+                            show_board = true;
+                            println!("{}", locale::text("game.loaded_moves", &[("path", &path)]));
+                        }
+                        Err(message) => eprintln!("{}", message),
+                    }
+                }
+                TurnCommand::Resign => {
+                    println!(
+                        "{}",
+                        locale::text("game.resigns", &[("name", &scoreboard.name(game.current))])
+                    );
+                    game.winner = Some(opponent);
+                    game.status = GameStatus::Completed;
+                }
+                TurnCommand::OfferDraw => {
+                    let opponent_type = type_of_player(&players, &scoreboard, opponent);
+                    if opponent_type != PlayerType::Human {
+                        println!(
+                            "{}",
+                            locale::text(
+                                "game.computer_declines_draw",
+                                &[
+                                    ("name", &scoreboard.name(opponent)),
+                                    ("player_type", &opponent_type),
+                                ]
+                            )
+                        );
+                        continue;
+                    }
+                    println!(
+                        "{}",
+                        locale::text("game.offer_draw", &[("name", &scoreboard.name(opponent))])
+                    );
+                    input.clear();
+                    stdin.read_line(input).expect("Error reading from stdio");
+                    match locale::is_yes(input) {
+                        true => game.status = GameStatus::Draw,
+                        false => println!("{}", locale::text("game.draw_declined", &[])),
+                    }
+                }
+                TurnCommand::Board => show_board = true,
+                TurnCommand::Column(column) => println!(
+                    "{}",
+                    accessible::describe_column(game.board(), column, labels, names)
+                ),
+                TurnCommand::Row(row) => match accessible::describe_row(game.board(), row, names) {
+                    Ok(description) => println!("{}", description),
+                    Err(message) => eprintln!("{}", message),
+                },
+                TurnCommand::History => println!("{}", turn::history(game.moves(), names, labels)),
+                TurnCommand::Rules => println!("{}", ruleset),
+                TurnCommand::Help => println!("{}", turn::help()),
+                TurnCommand::Quit => {
+                    println!("{}\n", locale::text("game.quit", &[]));
+                    return;
+                }
+            }
+        }
+
+        let played = PlayedGame::new(&scoreboard, game.winner, game.moves());
+        if let Err(error) = stats::record(DEFAULT_STATS_PATH, &played) {
+            eprintln!(
+                "{}",
+                locale::text(
+                    "game.stats_failed",
+                    &[("path", &DEFAULT_STATS_PATH), ("error", &error)]
+                )
             );
+        }
+        scoreboard.record(game.winner);
+        println!("{}", scoreboard.standings());
+        if players.has_human() {
+            offer_to_save(&game, input);
+        }
+
+        if scoreboard.is_over() {
+            println!("{}", scoreboard.summary());
+            println!("{}\n", locale::text("game.return_to_menu", &[]));
+            break;
+        }
+        if scoreboard.best_of.is_some() {
+            continue;
+        }
+        if !players.has_human() {
+            break;
+        }
+
+        println!("{}", locale::text("game.play_again", &[]));
+        input.clear();
+        stdin.read_line(input).expect("Error reading from stdio");
+        if locale::is_no(input) {
+            println!("{}", scoreboard.summary());
+            println!("{}\n", locale::text("game.return_to_menu", &[]));
+            break;
+        }
+    }
+}
+
+// Ends the game if the side to move has run out of time. Gives whether they had.
+fn flag_fell(game: &mut Game, clock: &Option<Clock>, names: [&str; 2]) -> bool {
+    let Some(clock) = clock else {
+        return false;
+    };
+    match clock::end_on_flag(game, clock, Instant::now()) {
+        Some((flagged, winner)) => {
+            println!("{}", clock::describe_flag(names, flagged, winner));
+            true
+        }
+        None => false,
+    }
+}
+
+// Adds the increment for the move just made. The flag has been checked since the move was chosen.
+fn stop_clock(clock: &mut Option<Clock>) {
+    if let Some(clock) = clock {
+        let _ = clock.stop(Instant::now());
+    }
+}
+
+// The named players keep their player types when they swap sides in a match
+fn type_of_player(players: &Players, scoreboard: &Scoreboard, player: Player) -> PlayerType {
+    match scoreboard.swapped() {
+        true => players.for_player(player.opponent()),
+        false => players.for_player(player),
+    }
+}
+
+// Offers to save the finished game as pictures or to the archive
+fn offer_to_save(game: &Game, input: &mut String) {
+    let stdin = io::stdin();
+    println!("{}", locale::text("save.ask_svg", &[]));
+    input.clear();
+    stdin.read_line(input).expect("Error reading from stdio");
+    if locale::is_yes(input) {
+        println!("{}", locale::text("save.where_svg", &[]));
+        input.clear();
+        stdin.read_line(input).expect("Error reading from stdio");
+        let path = input.trim();
+        match fs::write(path, svg::render(game, &SvgOptions::default())) {
+            Ok(()) => println!("{}", locale::text("save.saved_svg", &[("path", &path)])),
+            Err(error) => eprintln!(
+                "{}",
+                locale::text("save.svg_failed", &[("path", &path), ("error", &error)])
+            ),
+        }
+    }
+
+    println!("{}", locale::text("save.ask_gif", &[]));
+    input.clear();
+    stdin.read_line(input).expect("Error reading from stdio");
+    if locale::is_yes(input) {
+        println!("{}", locale::text("save.where_gif", &[]));
+        input.clear();
+        stdin.read_line(input).expect("Error reading from stdio");
+        let path = input.trim();
+        match fs::write(path, gif::export(game, &GifOptions::default())) {
+            Ok(()) => println!("{}", locale::text("save.saved_gif", &[("path", &path)])),
+            Err(error) => eprintln!(
+                "{}",
+                locale::text("save.gif_failed", &[("path", &path), ("error", &error)])
+            ),
+        }
+    }
+
+    println!("{}", locale::text("save.ask_archive", &[]));
+    input.clear();
+    stdin.read_line(input).expect("Error reading from stdio");
+    if locale::is_yes(input) {
+        match Archive::open(DEFAULT_ARCHIVE_PATH)
+            .and_then(|mut archive| archive.add(GameRecord::from_game(game)))
+        {
+            Ok(()) => println!(
+                "{}",
+                locale::text("save.archived", &[("path", &DEFAULT_ARCHIVE_PATH)])
+            ),
+            Err(error) => eprintln!(
+                "{}",
+                locale::text(
+                    "save.archive_failed",
+                    &[("path", &DEFAULT_ARCHIVE_PATH), ("error", &error)]
+                )
+            ),
+        }
+    }
+}
+
+/* Asks the player to build a ruleset, or load one from a file. Gives nothing if the file couldn't be loaded,
+or if nobody could win with the rules built.
+*/
+fn ask_for_ruleset(input: &mut String) -> Option<Ruleset> {
+    let stdin = io::stdin();
+    println!("{}", locale::text("setup.default_board", &[]));
+    println!("{}", locale::text("setup.rules_file", &[]));
+    input.clear();
+    stdin.read_line(input).expect("Error reading from stdio");
+    if let Some(path) = input.trim().strip_prefix("--rules") {
+        return match Ruleset::load(path.trim()) {
+            Ok(ruleset) => Some(ruleset),
+            Err(message) => {
+                eprintln!("{}", message);
+                None
+            }
+        };
+    }
+    let mut columns: usize = DEFAULT_COLUMNS;
+    let mut rows: usize = DEFAULT_ROWS;
+    if locale::is_no(input) {
+        loop {
+            println!("{}", locale::text("setup.columns", &[]));
             input.clear();
             stdin.read_line(input).expect("Error reading from stdio");
-            let column: usize = match input.trim().parse() {
-                Ok(column) => column,
+            match input.trim().parse::<usize>() {
+                Ok(parsed) => {
+                    columns = parsed;
+                    break;
+                }
                 Err(_) => {
                     eprintln!(
-                        "The input <{}> could not be parsed as a usize. Please try again.",
-                        input
+                        "{}",
+                        locale::text("setup.invalid_number", &[("input", &input.trim())])
                     );
                     continue;
                 }
             };
-            game = match game.play_on_column(column) {
-                Ok(game) => game,
-                Err(error) => {
-                    eprintln!("{}", error.message);
-                    error.previous_state
+        }
+        loop {
+            println!("{}", locale::text("setup.rows", &[]));
+            input.clear();
+            stdin.read_line(input).expect("Error reading from stdio");
+            match input.trim().parse::<usize>() {
+                Ok(parsed) => {
+                    rows = parsed;
+                    break;
                 }
-            }
+                Err(_) => {
+                    eprintln!(
+                        "{}",
+                        locale::text("setup.invalid_number", &[("input", &input.trim())])
+                    );
+                    continue;
+                }
+            };
+        }
+    }
+    println!("{}", locale::text("setup.standard_rules", &[]));
+    input.clear();
+    stdin.read_line(input).expect("Error reading from stdio");
+    let mut win_conditions: Vec<Box<dyn WinCondition>> = vec![];
+    if locale::is_no(input) {
+        println!("{}", locale::text("setup.vertical", &[]));
+        input.clear();
+        stdin.read_line(input).expect("Error reading from stdio");
+        if !locale::is_no(input) {
+            win_conditions.push(VerticalWinCondition::boxed())
         }
 
-        println!("Would you like to play again? Y/n");
+        println!("{}", locale::text("setup.horizontal", &[]));
         input.clear();
         stdin.read_line(input).expect("Error reading from stdio");
-        if input.trim() == "n" {
-            println!("Returning to the main menu.\n");
-            break;
+        if !locale::is_no(input) {
+            win_conditions.push(HorizontalWinCondition::boxed())
+        }
+
+        println!("{}", locale::text("setup.diagonal", &[]));
+        input.clear();
+        stdin.read_line(input).expect("Error reading from stdio");
+        if !locale::is_no(input) {
+            win_conditions.push(DiagonalWinCondition::boxed())
+        }
+        println!("{}", locale::text("setup.reverse_diagonal", &[]));
+        input.clear();
+        stdin.read_line(input).expect("Error reading from stdio");
+        if !locale::is_no(input) {
+            win_conditions.push(ReverseDiagonalWinCondition::boxed())
         }
+    } else {
+        win_conditions = default_win_conditions()
     }
+
+    let mut ruleset = Ruleset {
+        columns,
+        rows,
+        win_conditions: win_conditions.iter().map(|x| x.to_string()).collect(),
+        ..Default::default()
+    };
+    if ruleset != Ruleset::default() {
+        ruleset.name = "Custom".to_owned();
+    }
+    // Built rules are checked the same way as those loaded from a file
+    match ruleset.validate() {
+        Ok(()) => Some(ruleset),
+        Err(message) => {
+            eprintln!("{}", message);
+            None
+        }
+    }
+}
+
+// Announces a move in the accessible mode. Gives whether the board should be drawn instead.
+fn announce_move(game: &Game, column: usize, labels: ColumnLabels, names: [&str; 2]) -> bool {
+    if !accessible::enabled() {
+        return true;
+    }
+    let threats = (game.status == GameStatus::Started).then(|| winning_columns(game));
+    println!(
+        "{}",
+        accessible::announce_move(game.board(), column, labels, names, game.current, threats)
+    );
+    false
+}
+
+// The columns each player could win in with their next disc
+fn winning_columns(game: &Game) -> [Vec<usize>; 2] {
+    [Player::One, Player::Two].map(|player| {
+        (0..game.board().len())
+            .filter(|&column| {
+                let mut trial = game.clone();
+                trial.current = player;
+                trial
+                    .play_on_column(column)
+                    .is_ok_and(|x| x.winner == Some(player))
+            })
+            .collect()
+    })
 }
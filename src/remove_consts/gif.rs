@@ -0,0 +1,527 @@
+use super::game::{Game, Player};
+
+pub struct GifPalette {
+    pub background: [u8; 3],
+    pub board: [u8; 3],
+    pub empty: [u8; 3],
+    pub player_one: [u8; 3],
+    pub player_two: [u8; 3],
+    pub highlight: [u8; 3],
+}
+
+impl Default for GifPalette {
+    fn default() -> Self {
+        Self {
+            background: [0xff, 0xff, 0xff],
+            board: [0x1f, 0x4f, 0xbf],
+            empty: [0xf4, 0xf4, 0xf4],
+            player_one: [0xd6, 0x28, 0x28],
+            player_two: [0xf7, 0xc6, 0x00],
+            highlight: [0x2b, 0xd9, 0x6b],
+        }
+    }
+}
+
+pub struct GifOptions {
+    // The width and height of a single cell, in pixels
+    pub cell_size: usize,
+    // How long each move is shown for, in hundredths of a second
+    pub frame_delay: u16,
+    // How long the final position is held before the animation loops, in hundredths of a second
+    pub final_delay: u16,
+    // Shows each disc falling down its column rather than appearing in place
+    pub drop_animation: bool,
+    pub palette: GifPalette,
+}
+
+impl Default for GifOptions {
+    fn default() -> Self {
+        Self {
+            cell_size: 40,
+            frame_delay: 80,
+            final_delay: 400,
+            drop_animation: true,
+            palette: GifPalette::default(),
+        }
+    }
+}
+
+// Indexes into the colour table written by `export`
+const BACKGROUND: u8 = 0;
+const BOARD: u8 = 1;
+const EMPTY: u8 = 2;
+const PLAYER_ONE: u8 = 3;
+const PLAYER_TWO: u8 = 4;
+const HIGHLIGHT: u8 = 5;
+
+// The colour table always has 8 entries, so every pixel fits in 3 bits
+const COLOUR_BITS: u8 = 3;
+const DROP_FRAME_DELAY: u16 = 3;
+const MARGIN: usize = 8;
+
+// Exports the game as an animated GIF, replaying its moves from an empty board.
+// There is one frame for the empty board and one per move, and the winning line is highlighted on the last.
+pub fn export(game: &Game, options: &GifOptions) -> Vec<u8> {
+    let columns = game.board().len();
+    let rows = game.board().first().map_or(0, |column| column.len());
+    let canvas = Canvas::new(columns, rows, options.cell_size);
+    let mut encoder = Encoder::new(canvas.width, canvas.height, &options.palette);
+
+    let mut board = vec![vec![Player::None; rows]; columns];
+    let mut player = Player::One;
+    let moves = game.moves();
+    encoder.frame(&canvas.draw(&board, None, &[]), first_delay(moves, options));
+
+    for (index, &column) in moves.iter().enumerate() {
+        let row = board[column]
+            .iter()
+            .position(|cell| *cell == Player::None)
+            .expect("Game history contains a move into a full column. Invalid state.");
+        if options.drop_animation {
+            for falling_row in ((row + 1)..rows).rev() {
+                let frame = canvas.draw(&board, Some((column, falling_row, player)), &[]);
+                encoder.frame(&frame, DROP_FRAME_DELAY);
+            }
+        }
+        board[column][row] = player;
+        player = match player {
+            Player::One => Player::Two,
+            _ => Player::One,
+        };
+
+        let is_last = index + 1 == moves.len();
+        let highlight = match is_last {
+            true => game.winning_line().unwrap_or_default(),
+            false => vec![],
+        };
+        let delay = match is_last {
+            true => options.final_delay,
+            false => options.frame_delay,
+        };
+        encoder.frame(&canvas.draw(&board, None, &highlight), delay);
+    }
+
+    encoder.finish()
+}
+
+fn first_delay(moves: &[usize], options: &GifOptions) -> u16 {
+    match moves.is_empty() {
+        true => options.final_delay,
+        false => options.frame_delay,
+    }
+}
+
+struct Canvas {
+    cell_size: usize,
+    rows: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Canvas {
+    fn new(columns: usize, rows: usize, cell_size: usize) -> Self {
+        Self {
+            cell_size,
+            rows,
+            width: columns * cell_size + 2 * MARGIN,
+            height: rows * cell_size + 2 * MARGIN,
+        }
+    }
+
+    // Rasterises the board into colour table indexes, optionally with a disc part way down a column
+    fn draw(
+        &self,
+        board: &[Vec<Player>],
+        falling: Option<(usize, usize, Player)>,
+        highlight: &[(usize, usize)],
+    ) -> Vec<u8> {
+        let mut pixels = vec![BACKGROUND; self.width * self.height];
+        for y in MARGIN..self.height - MARGIN {
+            for x in MARGIN..self.width - MARGIN {
+                pixels[y * self.width + x] = BOARD;
+            }
+        }
+
+        for (column, cells) in board.iter().enumerate() {
+            for (row, player) in cells.iter().enumerate() {
+                let mut player = *player;
+                if let Some((falling_column, falling_row, falling_player)) = falling {
+                    if falling_column == column && falling_row == row {
+                        player = falling_player;
+                    }
+                }
+                let colour = match player {
+                    Player::None => EMPTY,
+                    Player::One => PLAYER_ONE,
+                    Player::Two => PLAYER_TWO,
+                };
+                let ring = match highlight.contains(&(column, row)) {
+                    true => Some(HIGHLIGHT),
+                    false => None,
+                };
+                self.disc(&mut pixels, column, row, colour, ring);
+            }
+        }
+        pixels
+    }
+
+    fn disc(&self, pixels: &mut [u8], column: usize, row: usize, colour: u8, ring: Option<u8>) {
+        let left = MARGIN + column * self.cell_size;
+        let top = MARGIN + (self.rows - 1 - row) * self.cell_size;
+        let centre = self.cell_size as f64 / 2.0;
+        let radius = self.cell_size as f64 * 0.4;
+        let ring_width = (self.cell_size as f64 / 10.0).max(1.0);
+        for dy in 0..self.cell_size {
+            for dx in 0..self.cell_size {
+                let x = dx as f64 + 0.5 - centre;
+                let y = dy as f64 + 0.5 - centre;
+                let distance = (x * x + y * y).sqrt();
+                if distance > radius {
+                    continue;
+                }
+                pixels[(top + dy) * self.width + left + dx] = match ring {
+                    Some(ring) if distance > radius - ring_width => ring,
+                    _ => colour,
+                };
+            }
+        }
+    }
+}
+
+struct Encoder {
+    width: u16,
+    height: u16,
+    output: Vec<u8>,
+}
+
+impl Encoder {
+    fn new(width: usize, height: usize, palette: &GifPalette) -> Self {
+        let width = u16::try_from(width).expect("Board is too large to export as a GIF.");
+        let height = u16::try_from(height).expect("Board is too large to export as a GIF.");
+        let mut output = b"GIF89a".to_vec();
+        output.extend_from_slice(&width.to_le_bytes());
+        output.extend_from_slice(&height.to_le_bytes());
+        // Global colour table present, 8 bits of colour resolution, 2^3 entries
+        output.push(0b1111_0000 | (COLOUR_BITS - 1));
+        output.push(BACKGROUND);
+        output.push(0);
+
+        let colours = [
+            palette.background,
+            palette.board,
+            palette.empty,
+            palette.player_one,
+            palette.player_two,
+            palette.highlight,
+            [0, 0, 0],
+            [0, 0, 0],
+        ];
+        for colour in colours {
+            output.extend_from_slice(&colour);
+        }
+
+        // Loop forever
+        output.extend_from_slice(&[0x21, 0xff, 0x0b]);
+        output.extend_from_slice(b"NETSCAPE2.0");
+        output.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+
+        Self {
+            width,
+            height,
+            output,
+        }
+    }
+
+    fn frame(&mut self, pixels: &[u8], delay: u16) {
+        self.output.extend_from_slice(&[0x21, 0xf9, 0x04, 0x00]);
+        self.output.extend_from_slice(&delay.to_le_bytes());
+        self.output.extend_from_slice(&[0x00, 0x00]);
+
+        self.output.push(0x2c);
+        self.output.extend_from_slice(&[0, 0, 0, 0]);
+        self.output.extend_from_slice(&self.width.to_le_bytes());
+        self.output.extend_from_slice(&self.height.to_le_bytes());
+        self.output.push(0);
+
+        self.output.push(COLOUR_BITS);
+        for block in lzw_compress(pixels).chunks(255) {
+            self.output.push(block.len() as u8);
+            self.output.extend_from_slice(block);
+        }
+        self.output.push(0);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.output.push(0x3b);
+        self.output
+    }
+}
+
+const MAX_CODE: u16 = 4096;
+
+fn lzw_compress(pixels: &[u8]) -> Vec<u8> {
+    let alphabet = 1usize << COLOUR_BITS;
+    let clear_code = alphabet as u16;
+    let end_code = clear_code + 1;
+    // Maps a code and the pixel that follows it to the code for the combined sequence, 0 meaning unassigned
+    let mut table = vec![0u16; MAX_CODE as usize * alphabet];
+    let mut next_code = end_code + 1;
+    let mut code_size = COLOUR_BITS + 1;
+    let mut writer = BitWriter::default();
+
+    writer.write(clear_code, code_size);
+    let mut pixels = pixels.iter();
+    let mut current = match pixels.next() {
+        Some(&pixel) => pixel as u16,
+        None => {
+            writer.write(end_code, code_size);
+            return writer.finish();
+        }
+    };
+
+    for &pixel in pixels {
+        let key = current as usize * alphabet + pixel as usize;
+        if table[key] != 0 {
+            current = table[key];
+            continue;
+        }
+        writer.write(current, code_size);
+        if next_code == MAX_CODE {
+            writer.write(clear_code, code_size);
+            table.iter_mut().for_each(|entry| *entry = 0);
+            next_code = end_code + 1;
+            code_size = COLOUR_BITS + 1;
+        } else {
+            if next_code >= 1 << code_size {
+                code_size += 1;
+            }
+            table[key] = next_code;
+            next_code += 1;
+        }
+        current = pixel as u16;
+    }
+
+    writer.write(current, code_size);
+    writer.write(end_code, code_size);
+    writer.finish()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remove_consts::{
+        game::{Game, DEFAULT_COLUMNS, DEFAULT_ROWS},
+        win_conditions::default_win_conditions,
+    };
+
+    use super::{export, lzw_compress, GifOptions, COLOUR_BITS, HIGHLIGHT, PLAYER_ONE};
+
+    struct Frame {
+        delay: u16,
+        pixels: Vec<u8>,
+    }
+
+    // A minimal decoder for the subset of GIF written by `export`
+    fn decode(bytes: &[u8]) -> (u16, u16, Vec<[u8; 3]>, Vec<Frame>) {
+        assert_eq!(&bytes[0..6], b"GIF89a");
+        let width = u16::from_le_bytes([bytes[6], bytes[7]]);
+        let height = u16::from_le_bytes([bytes[8], bytes[9]]);
+        let table_size = 2usize << (bytes[10] & 0b111);
+        let mut palette = vec![];
+        let mut position = 13;
+        for _ in 0..table_size {
+            palette.push([bytes[position], bytes[position + 1], bytes[position + 2]]);
+            position += 3;
+        }
+
+        let mut frames = vec![];
+        let mut delay = 0;
+        loop {
+            match bytes[position] {
+                0x21 => {
+                    if bytes[position + 1] == 0xf9 {
+                        delay = u16::from_le_bytes([bytes[position + 4], bytes[position + 5]]);
+                    }
+                    position += 2;
+                    while bytes[position] != 0 {
+                        position += bytes[position] as usize + 1;
+                    }
+                    position += 1;
+                }
+                0x2c => {
+                    position += 10;
+                    let minimum_code_size = bytes[position];
+                    position += 1;
+                    let mut data = vec![];
+                    while bytes[position] != 0 {
+                        let length = bytes[position] as usize;
+                        data.extend_from_slice(&bytes[position + 1..position + 1 + length]);
+                        position += length + 1;
+                    }
+                    position += 1;
+                    frames.push(Frame {
+                        delay,
+                        pixels: lzw_decompress(&data, minimum_code_size),
+                    });
+                }
+                0x3b => break,
+                other => panic!("Unexpected block {:#x}", other),
+            }
+        }
+        (width, height, palette, frames)
+    }
+
+    fn lzw_decompress(data: &[u8], minimum_code_size: u8) -> Vec<u8> {
+        let clear_code = 1u16 << minimum_code_size;
+        let end_code = clear_code + 1;
+        let mut code_size = minimum_code_size + 1;
+        let mut table: Vec<Vec<u8>> = vec![];
+        let mut previous: Option<Vec<u8>> = None;
+        let mut output = vec![];
+        let mut buffer = 0u32;
+        let mut bits = 0u8;
+        let mut bytes = data.iter();
+        loop {
+            while bits < code_size {
+                buffer |= (*bytes.next().expect("Ran out of data") as u32) << bits;
+                bits += 8;
+            }
+            let code = (buffer & ((1 << code_size) - 1)) as u16;
+            buffer >>= code_size;
+            bits -= code_size;
+
+            if code == clear_code {
+                table = (0..clear_code).map(|pixel| vec![pixel as u8]).collect();
+                table.push(vec![]);
+                table.push(vec![]);
+                code_size = minimum_code_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == end_code {
+                return output;
+            }
+            let entry = match table.get(code as usize) {
+                Some(entry) => entry.clone(),
+                None => {
+                    let mut entry = previous.clone().expect("Invalid code");
+                    entry.push(entry[0]);
+                    entry
+                }
+            };
+            output.extend_from_slice(&entry);
+            if let Some(mut previous) = previous {
+                if table.len() < 4096 {
+                    previous.push(entry[0]);
+                    table.push(previous);
+                    if table.len() == 1 << code_size && code_size < 12 {
+                        code_size += 1;
+                    }
+                }
+            }
+            previous = Some(entry);
+        }
+    }
+
+    #[test]
+    fn compression_round_trips_through_table_resets() {
+        let pixels: Vec<u8> = (0..100_000u32)
+            .map(|i| ((i * 7919) ^ (i >> 3)) as u8 % 8)
+            .collect();
+        let compressed = lzw_compress(&pixels);
+        assert_eq!(lzw_decompress(&compressed, COLOUR_BITS), pixels);
+    }
+
+    #[test]
+    fn writes_one_frame_per_move_plus_the_empty_board() {
+        let win_conditions = default_win_conditions();
+        let mut game = Game::initialise(DEFAULT_COLUMNS, DEFAULT_ROWS, &win_conditions);
+        for column in [3, 3, 4] {
+            game = game.play_on_column(column).unwrap();
+        }
+        let options = GifOptions {
+            cell_size: 10,
+            drop_animation: false,
+            frame_delay: 50,
+            final_delay: 300,
+            ..Default::default()
+        };
+        let (width, height, palette, frames) = decode(&export(&game, &options));
+        assert_eq!((width, height), (7 * 10 + 16, 6 * 10 + 16));
+        assert_eq!(palette[PLAYER_ONE as usize], options.palette.player_one);
+        assert_eq!(frames.len(), 4);
+        assert_eq!(
+            frames.iter().map(|frame| frame.delay).collect::<Vec<_>>(),
+            vec![50, 50, 50, 300]
+        );
+        for frame in &frames {
+            assert_eq!(frame.pixels.len(), width as usize * height as usize);
+        }
+        assert!(!frames[0].pixels.contains(&PLAYER_ONE));
+        assert!(frames[1].pixels.contains(&PLAYER_ONE));
+    }
+
+    #[test]
+    fn adds_a_frame_for_each_row_a_disc_falls_past() {
+        let win_conditions = default_win_conditions();
+        let mut game = Game::initialise(DEFAULT_COLUMNS, DEFAULT_ROWS, &win_conditions);
+        game = game.play_on_column(0).unwrap();
+        game = game.play_on_column(0).unwrap();
+        let options = GifOptions {
+            cell_size: 6,
+            ..Default::default()
+        };
+        let (_, _, _, frames) = decode(&export(&game, &options));
+        // The empty board, 5 rows of falling and the landing, then 4 rows of falling and the landing
+        assert_eq!(frames.len(), 1 + 6 + 5);
+    }
+
+    /*
+    o . . .
+    o . . .
+    o . . .
+    x x x x
+    */
+    #[test]
+    fn highlights_the_winning_line_on_the_final_frame() {
+        let win_conditions = default_win_conditions();
+        let mut game = Game::initialise(4, 4, &win_conditions);
+        for column in [0, 0, 1, 0, 2, 0, 3] {
+            game = game.play_on_column(column).unwrap();
+        }
+        let options = GifOptions {
+            cell_size: 10,
+            drop_animation: false,
+            ..Default::default()
+        };
+        let (_, _, _, frames) = decode(&export(&game, &options));
+        assert_eq!(frames.len(), 8);
+        assert!(!frames[6].pixels.contains(&HIGHLIGHT));
+        assert!(frames[7].pixels.contains(&HIGHLIGHT));
+    }
+}
@@ -0,0 +1,278 @@
+use std::{fmt::Display, iter::Peekable, str::Chars};
+
+// Arrays and objects nest no deeper than this, so a body of brackets can't overflow the stack
+const MAX_DEPTH: usize = 32;
+
+// The largest whole number that a number in JSON, being an f64, holds exactly
+const MAX_EXACT_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+// Just enough JSON for the HTTP API. Objects keep their keys in order, so responses read predictably.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut chars = text.chars().peekable();
+        let value = parse_value(&mut chars, 0)?;
+        skip_whitespace(&mut chars);
+        match chars.next() {
+            None => Ok(value),
+            Some(other) => Err(format!(
+                "Expected the end of the JSON but found <{}>.",
+                other
+            )),
+        }
+    }
+
+    pub fn object<const N: usize>(fields: [(&str, Json); N]) -> Self {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+        )
+    }
+
+    pub fn string(value: &str) -> Self {
+        Json::String(value.to_owned())
+    }
+
+    pub fn number(value: usize) -> Self {
+        Json::Number(value as f64)
+    }
+
+    pub fn usize(&self) -> Option<usize> {
+        match self {
+            // Larger numbers can't be told apart from their neighbours, so they're refused rather than rounded
+            Json::Number(number)
+                if number.fract() == 0.0 && (0.0..=MAX_EXACT_INTEGER).contains(number) =>
+            {
+                Some(*number as usize)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Number(value) => write!(f, "{}", value),
+            Json::String(value) => write_string(f, value),
+            Json::Array(values) => {
+                f.write_str("[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                f.write_str("]")
+            }
+            Json::Object(fields) => {
+                f.write_str("{")?;
+                for (index, (key, value)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut std::fmt::Formatter<'_>, value: &str) -> std::fmt::Result {
+    f.write_str("\"")?;
+    for character in value.chars() {
+        match character {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            control if control.is_control() => write!(f, "\\u{:04x}", control as u32)?,
+            other => write!(f, "{}", other)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+fn parse_value(chars: &mut Peekable<Chars>, depth: usize) -> Result<Json, String> {
+    skip_whitespace(chars);
+    match chars.peek() {
+        Some('{' | '[') if depth >= MAX_DEPTH => {
+            Err(format!("The JSON nests deeper than {} levels.", MAX_DEPTH))
+        }
+        Some('{') => parse_object(chars, depth + 1),
+        Some('[') => parse_array(chars, depth + 1),
+        Some('"') => Ok(Json::String(parse_string(chars)?)),
+        Some('t') => parse_word(chars, "true", Json::Bool(true)),
+        Some('f') => parse_word(chars, "false", Json::Bool(false)),
+        Some('n') => parse_word(chars, "null", Json::Null),
+        Some(x) if *x == '-' || x.is_ascii_digit() => parse_number(chars),
+        Some(other) => Err(format!("Did not expect <{}> in the JSON.", other)),
+        None => Err("The JSON ended early.".to_owned()),
+    }
+}
+
+fn parse_object(chars: &mut Peekable<Chars>, depth: usize) -> Result<Json, String> {
+    chars.next();
+    let mut fields = vec![];
+    skip_whitespace(chars);
+    if chars.peek() == Some(&'}') {
+        chars.next();
+        return Ok(Json::Object(fields));
+    }
+    loop {
+        skip_whitespace(chars);
+        if chars.peek() != Some(&'"') {
+            return Err("Expected a key in quotes.".to_owned());
+        }
+        let key = parse_string(chars)?;
+        skip_whitespace(chars);
+        expect(chars, ':')?;
+        fields.push((key, parse_value(chars, depth)?));
+        skip_whitespace(chars);
+        match chars.next() {
+            Some(',') => continue,
+            Some('}') => return Ok(Json::Object(fields)),
+            _ => return Err("Expected <,> or <}> after a value in an object.".to_owned()),
+        }
+    }
+}
+
+fn parse_array(chars: &mut Peekable<Chars>, depth: usize) -> Result<Json, String> {
+    chars.next();
+    let mut values = vec![];
+    skip_whitespace(chars);
+    if chars.peek() == Some(&']') {
+        chars.next();
+        return Ok(Json::Array(values));
+    }
+    loop {
+        values.push(parse_value(chars, depth)?);
+        skip_whitespace(chars);
+        match chars.next() {
+            Some(',') => continue,
+            Some(']') => return Ok(Json::Array(values)),
+            _ => return Err("Expected <,> or <]> after a value in an array.".to_owned()),
+        }
+    }
+}
+
+fn parse_string(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    chars.next();
+    let mut value = String::new();
+    loop {
+        match chars.next().ok_or("A string in the JSON wasn't closed.")? {
+            '"' => return Ok(value),
+            '\\' => value.push(match chars.next().ok_or("The JSON ended early.")? {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'b' => '\u{8}',
+                'f' => '\u{c}',
+                'u' => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    u32::from_str_radix(&code, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or(format!("<\\u{}> is not a character.", code))?
+                }
+                other => other,
+            }),
+            other => value.push(other),
+        }
+    }
+}
+
+fn parse_number(chars: &mut Peekable<Chars>) -> Result<Json, String> {
+    let mut text = String::new();
+    while let Some(&x) = chars.peek() {
+        if !(x.is_ascii_digit() || "+-.eE".contains(x)) {
+            break;
+        }
+        text.push(x);
+        chars.next();
+    }
+    text.parse()
+        .map(Json::Number)
+        .map_err(|_| format!("<{}> is not a number.", text))
+}
+
+fn parse_word(chars: &mut Peekable<Chars>, word: &str, value: Json) -> Result<Json, String> {
+    for expected in word.chars() {
+        if chars.next() != Some(expected) {
+            return Err(format!("Expected <{}> in the JSON.", word));
+        }
+    }
+    Ok(value)
+}
+
+fn expect(chars: &mut Peekable<Chars>, expected: char) -> Result<(), String> {
+    match chars.next() {
+        Some(x) if x == expected => Ok(()),
+        _ => Err(format!("Expected <{}> in the JSON.", expected)),
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.peek().is_some_and(|x| x.is_whitespace()) {
+        chars.next();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Json;
+
+    #[test]
+    fn reads_and_writes_json() {
+        let text = r#" {"columns": 9, "win_conditions": ["vertical", "horizontal"],
+            "name": "House \"rules\"\n", "misere": false, "seed": null, "a": {}} "#;
+        let json = Json::parse(text).unwrap();
+        assert_eq!(
+            json.to_string(),
+            r#"{"columns":9,"win_conditions":["vertical","horizontal"],"name":"House \"rules\"\n","misere":false,"seed":null,"a":{}}"#
+        );
+        assert_eq!(Json::parse(&json.to_string()), Ok(json));
+        assert_eq!(Json::parse("\"\\u00e9\""), Ok(Json::string("é")));
+        assert_eq!(Json::parse("3").unwrap().usize(), Some(3));
+        assert_eq!(Json::parse("-3").unwrap().usize(), None);
+        assert_eq!(Json::parse("3.5").unwrap().usize(), None);
+        assert_eq!(Json::parse("1e30").unwrap().usize(), None);
+        for invalid in [
+            "",
+            "{",
+            "[1,]",
+            "{\"a\" 1}",
+            "tru",
+            "\"open",
+            "1 2",
+            "{a: 1}",
+        ] {
+            assert!(Json::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn refuses_json_nested_too_deeply() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(Json::parse(&nested(32)).is_ok());
+        assert!(Json::parse(&nested(33)).is_err());
+        assert!(Json::parse(&"[".repeat(60_000)).is_err());
+        assert!(Json::parse(&"{\"a\":".repeat(60_000)).is_err());
+    }
+}
@@ -1,3 +1,29 @@
+pub mod ai;
+pub mod api;
+pub mod archive;
+pub mod archive_loop;
+pub mod batch;
+pub mod cli;
+pub mod client;
+pub mod clock;
+pub mod commands;
+pub mod correspondence;
+pub mod engine;
+pub mod external_engine;
 pub mod game;
 pub mod game_loop;
+pub mod gif;
+pub mod json;
+pub mod protocol;
+pub mod random;
+pub mod rules_command;
+pub mod ruleset;
+pub mod server;
+pub mod svg;
+pub mod terminal;
+pub mod training;
+pub mod training_loop;
+pub mod tui;
+pub mod web;
+pub mod websocket;
 pub mod win_conditions;
@@ -0,0 +1,662 @@
+use std::time::Duration;
+
+use super::{
+    archive::parse_moves,
+    game::Player,
+    ruleset::Ruleset,
+    training::{decode, encode},
+};
+
+/* The line protocol for network games. Every message is one line of UTF-8 ending in \n,
+starting with an upper case keyword. Names run to the end of the line, so they can contain spaces,
+and where a message has both players' names they're separated by a tab.
+Columns are zero indexed, and sides are `one` or `two`, with player one moving first.
+
+From the client:
+  JOIN <name>                   Wait for an opponent with a similar rating, and start a game with the
+                                server's rules when one joins. Ratings come from the server's stats.
+  CREATE <name>[<tab><settings>]
+                                Open a game for anyone to accept. The settings are the rules, in the same
+                                form as a ruleset file, such as columns=9 connect=5 win_conditions=vertical.
+                                Anything not set is standard, and without settings the server's rules are used.
+  LOBBY                         List the open games.
+  ACCEPT <game> <name>          Accept an open game, which starts it. Whoever opened it moves first.
+  CANCEL                        Stop waiting for an opponent, closing any game this connection opened.
+  LOCAL <names>                 Start a game right away, playing both sides from this connection.
+  RESUME <token>                Take back a seat after losing the connection, with the token from SESSION.
+                                The game carries on where it was.
+  MOVE <column>                 Drop a disc in the column.
+  BOARD                         Ask for the board again.
+  RESIGN                        Give the game to the opponent.
+  GAMES                         List the games being played.
+  WATCH <game>                  Watch a game being played, without joining it.
+  QUIT                          Leave. The server closes the connection.
+
+From the server:
+  HELLO <version>               Sent as soon as a client connects.
+  WAITING                       Joined, and waiting for an opponent.
+  OPENED <game>                 The game is open, and waiting for someone to accept it.
+  CANCELLED                     No longer waiting. Sent for CANCEL, and when nobody accepts an open game
+                                for 10 minutes.
+  LOBBY <count>                 How many games are open. One OPENGAME follows for each.
+  OPENGAME <game> <rating> <name><tab><settings>
+                                An open game, who opened it and their rating, and its rules.
+  START <side> <columns> <rows> <opponent>
+                                A game has started, and the client plays the side. A local game
+                                starts as side one, against the second name.
+  SESSION <token> <seconds>     Sent to each player after START. A player whose connection drops has the
+                                seconds to come back with RESUME before they lose the game.
+  RESUMED <game> <side> <columns> <rows> <names>
+                                Back in the game, playing the side. The history, the board and whose turn
+                                it is follow, as when watching.
+  AWAY <side> <seconds>         The side's connection dropped, and they have the seconds to come back.
+  BACK <side>                   The side has come back.
+  BOARD <board>                 The board, encoded a column at a time from the left, each column
+                                from the bottom up, with x for one, o for two, b for blank and /
+                                between columns. Sent at the start and after every move.
+  TURN <side>                   The side to move next. Moves in a local game are for this side.
+  CLOCK <one> <two>             Each side's time left in milliseconds, in games with a clock. Sent just
+                                before TURN, and the time of the side to move runs down from then.
+  MOVED <side> <column>         A side has played in the column.
+  ERROR <message>               The last message was rejected. Nothing else changed.
+  LINE <cells>                  The cells that won the game, as column,row pairs separated by spaces,
+                                with rows counted from 0 at the bottom. Sent just before GAMEOVER.
+  GAMEOVER <side|draw> <reason> The game has finished. The reason is connected, full, resigned,
+                                disconnected, when a player quits or doesn't come back in time,
+                                abandoned, when the side to move hasn't for 15 minutes in a game
+                                without a clock, or timeout, when the side to move ran out of time.
+                                A timeout is a draw if their opponent had no way left to win.
+                                Send JOIN to play again, or WATCH another game.
+  GAMES <count>                 How many games are being played. One GAME follows for each.
+  GAME <game> <moves> <names>   A game, how many moves have been played and who's playing it.
+  WATCHING <game> <columns> <rows> <names>
+                                Now watching the game. The history, the board and whose turn it is
+                                follow, and then every move just as the players see them.
+  HISTORY <moves>               Every move so far, separated by commas.
+  SPECTATORS <count>            How many are watching. Sent to the table whenever it changes.
+
+A game looks like:
+  > JOIN Ada        < HELLO 6, WAITING
+  ... Grace joins   < START one 7 6 Grace, SESSION 5f0c... 60, BOARD bbbbbb/..., TURN one
+  > MOVE 3          < MOVED one 3, BOARD ..., TURN two
+  ... Ada's connection drops, and Grace is sent AWAY one 60
+  > RESUME 5f0c...  < HELLO 6, RESUMED 0 one 7 6 Ada<tab>Grace, HISTORY 3, BOARD ..., TURN two
+
+And with a clock, from serve --clock 5+3:
+  > MOVE 3          < MOVED one 3, BOARD ..., CLOCK 301250 300000, TURN two
+
+And watching one:
+  > GAMES           < HELLO 6, GAMES 1, GAME 0 1 Ada<tab>Grace
+  > WATCH 0         < WATCHING 0 7 6 Ada<tab>Grace, HISTORY 3, BOARD ..., TURN two, SPECTATORS 1
+
+And opening one in the lobby:
+  > CREATE Ada<tab>columns=9 rows=7   < HELLO 6, OPENED 1
+  ... Grace sends LOBBY   < LOBBY 1, OPENGAME 1 1500 Ada<tab>columns=9 rows=7 connect=4 ...
+  ... Grace sends ACCEPT 1 Grace      < START one 9 7 Grace, BOARD ..., TURN one
+*/
+pub const PROTOCOL_VERSION: usize = 6;
+
+pub const DEFAULT_PORT: u16 = 4444;
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum ClientMessage {
+    Join(String),
+    // Without a ruleset, the server's rules are used
+    Create(String, Option<Ruleset>),
+    Lobby,
+    Accept(usize, String),
+    Cancel,
+    Local([String; 2]),
+    Resume(String),
+    Move(usize),
+    Board,
+    Resign,
+    Games,
+    Watch(usize),
+    Quit,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Reason {
+    // The winner connected enough discs
+    Connected,
+    // The board filled up without a winner
+    Full,
+    Resigned,
+    Disconnected,
+    // The side to move took too long
+    Abandoned,
+    // The side to move ran out of time on their clock
+    Timeout,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum ServerMessage {
+    Hello(usize),
+    Waiting,
+    Opened(usize),
+    Cancelled,
+    Lobby(usize),
+    // The ruleset is named after whoever opened the game
+    OpenGame {
+        game: usize,
+        rating: usize,
+        ruleset: Ruleset,
+    },
+    Start {
+        side: Player,
+        columns: usize,
+        rows: usize,
+        opponent: String,
+    },
+    // The token, and how long a dropped player has to come back
+    Session(String, Duration),
+    Resumed {
+        game: usize,
+        side: Player,
+        columns: usize,
+        rows: usize,
+        names: [String; 2],
+    },
+    Away(Player, Duration),
+    Back(Player),
+    Board(Vec<Vec<Player>>),
+    Turn(Player),
+    // Player one's time left, then player two's
+    Clock([Duration; 2]),
+    Moved(Player, usize),
+    Error(String),
+    // Column and row pairs
+    Line(Vec<(usize, usize)>),
+    // No winner is a draw
+    GameOver(Option<Player>, Reason),
+    Games(usize),
+    Game {
+        game: usize,
+        moves: usize,
+        names: [String; 2],
+    },
+    Watching {
+        game: usize,
+        columns: usize,
+        rows: usize,
+        names: [String; 2],
+    },
+    History(Vec<usize>),
+    Spectators(usize),
+}
+
+impl ClientMessage {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let (keyword, rest) = split_keyword(line);
+        let message = match keyword {
+            "JOIN" if rest.contains('\t') => return Err("Names can't contain tabs.".to_owned()),
+            "JOIN" if !rest.is_empty() => ClientMessage::Join(rest.to_owned()),
+            "JOIN" => return Err("JOIN needs a name.".to_owned()),
+            "CREATE" => {
+                let (name, settings) = match rest.split_once('\t') {
+                    Some((name, settings)) => (name.trim(), Some(settings)),
+                    None => (rest, None),
+                };
+                if name.is_empty() {
+                    return Err("CREATE needs a name.".to_owned());
+                }
+                let ruleset = settings
+                    .map(|x| Ruleset::from_hosted_settings(x, "Custom"))
+                    .transpose()?;
+                ClientMessage::Create(name.to_owned(), ruleset)
+            }
+            "LOBBY" => ClientMessage::Lobby,
+            "ACCEPT" => match rest.split_once(' ') {
+                Some((_, name)) if name.contains('\t') => {
+                    return Err("Names can't contain tabs.".to_owned())
+                }
+                Some((game, name)) if !name.trim().is_empty() => {
+                    ClientMessage::Accept(parse_number(game)?, name.trim().to_owned())
+                }
+                _ => return Err("ACCEPT needs a game and a name.".to_owned()),
+            },
+            "CANCEL" => ClientMessage::Cancel,
+            "LOCAL" => ClientMessage::Local(parse_names(rest)?),
+            "RESUME" if !rest.is_empty() && !rest.contains(char::is_whitespace) => {
+                ClientMessage::Resume(rest.to_owned())
+            }
+            "RESUME" => return Err("RESUME needs a token.".to_owned()),
+            "MOVE" => ClientMessage::Move(parse_number(rest)?),
+            "BOARD" => ClientMessage::Board,
+            "RESIGN" => ClientMessage::Resign,
+            "GAMES" => ClientMessage::Games,
+            "WATCH" => ClientMessage::Watch(parse_number(rest)?),
+            "QUIT" => ClientMessage::Quit,
+            _ => return Err(format!("Did not recognise <{}>.", line.trim())),
+        };
+        match message {
+            ClientMessage::Board
+            | ClientMessage::Resign
+            | ClientMessage::Games
+            | ClientMessage::Lobby
+            | ClientMessage::Cancel
+            | ClientMessage::Quit
+                if !rest.is_empty() =>
+            {
+                Err(format!("{} doesn't take anything after it.", keyword))
+            }
+            message => Ok(message),
+        }
+    }
+
+    pub fn to_line(&self) -> String {
+        match self {
+            ClientMessage::Join(name) => format!("JOIN {}", name),
+            ClientMessage::Create(name, None) => format!("CREATE {}", name),
+            ClientMessage::Create(name, Some(ruleset)) => {
+                format!("CREATE {}\t{}", name, ruleset.settings())
+            }
+            ClientMessage::Lobby => "LOBBY".to_owned(),
+            ClientMessage::Accept(game, name) => format!("ACCEPT {} {}", game, name),
+            ClientMessage::Cancel => "CANCEL".to_owned(),
+            ClientMessage::Local(names) => format!("LOCAL {}", names.join("\t")),
+            ClientMessage::Resume(token) => format!("RESUME {}", token),
+            ClientMessage::Move(column) => format!("MOVE {}", column),
+            ClientMessage::Board => "BOARD".to_owned(),
+            ClientMessage::Resign => "RESIGN".to_owned(),
+            ClientMessage::Games => "GAMES".to_owned(),
+            ClientMessage::Watch(game) => format!("WATCH {}", game),
+            ClientMessage::Quit => "QUIT".to_owned(),
+        }
+    }
+}
+
+impl Reason {
+    fn name(&self) -> &'static str {
+        match self {
+            Reason::Connected => "connected",
+            Reason::Full => "full",
+            Reason::Resigned => "resigned",
+            Reason::Disconnected => "disconnected",
+            Reason::Abandoned => "abandoned",
+            Reason::Timeout => "timeout",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [
+            Reason::Connected,
+            Reason::Full,
+            Reason::Resigned,
+            Reason::Disconnected,
+            Reason::Abandoned,
+            Reason::Timeout,
+        ]
+        .into_iter()
+        .find(|x| x.name() == name)
+    }
+}
+
+impl ServerMessage {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let (keyword, rest) = split_keyword(line);
+        let fields: Vec<&str> = rest.split_whitespace().collect();
+        let message = match (keyword, &fields[..]) {
+            ("HELLO", [version]) => ServerMessage::Hello(parse_number(version)?),
+            ("WAITING", []) => ServerMessage::Waiting,
+            ("OPENED", [game]) => ServerMessage::Opened(parse_number(game)?),
+            ("CANCELLED", []) => ServerMessage::Cancelled,
+            ("LOBBY", [count]) => ServerMessage::Lobby(parse_number(count)?),
+            ("OPENGAME", [game, rating, ..]) => {
+                let (name, settings) = nth_onwards(rest, 2)
+                    .split_once('\t')
+                    .ok_or("OPENGAME needs a name and settings separated by a tab.")?;
+                ServerMessage::OpenGame {
+                    game: parse_number(game)?,
+                    rating: parse_number(rating)?,
+                    ruleset: Ruleset::from_settings(settings, name)?,
+                }
+            }
+            ("START", [side, columns, rows, ..]) => ServerMessage::Start {
+                side: parse_side(side)?,
+                columns: parse_number(columns)?,
+                rows: parse_number(rows)?,
+                opponent: nth_onwards(rest, 3).to_owned(),
+            },
+            ("SESSION", [token, seconds]) => ServerMessage::Session(
+                token.to_string(),
+                Duration::from_secs(parse_number(seconds)? as u64),
+            ),
+            ("RESUMED", [game, side, columns, rows, ..]) => ServerMessage::Resumed {
+                game: parse_number(game)?,
+                side: parse_side(side)?,
+                columns: parse_number(columns)?,
+                rows: parse_number(rows)?,
+                names: parse_names(nth_onwards(rest, 4))?,
+            },
+            ("AWAY", [side, seconds]) => ServerMessage::Away(
+                parse_side(side)?,
+                Duration::from_secs(parse_number(seconds)? as u64),
+            ),
+            ("BACK", [side]) => ServerMessage::Back(parse_side(side)?),
+            ("BOARD", [board]) => ServerMessage::Board(decode(board)?),
+            ("TURN", [side]) => ServerMessage::Turn(parse_side(side)?),
+            ("CLOCK", [one, two]) => ServerMessage::Clock([
+                Duration::from_millis(parse_number(one)? as u64),
+                Duration::from_millis(parse_number(two)? as u64),
+            ]),
+            ("MOVED", [side, column]) => {
+                ServerMessage::Moved(parse_side(side)?, parse_number(column)?)
+            }
+            ("ERROR", _) => ServerMessage::Error(rest.to_owned()),
+            ("LINE", cells) => ServerMessage::Line(
+                cells
+                    .iter()
+                    .map(|cell| match cell.split_once(',') {
+                        Some((column, row)) => Ok((parse_number(column)?, parse_number(row)?)),
+                        None => Err(format!("<{}> is not a column,row pair.", cell)),
+                    })
+                    .collect::<Result<_, String>>()?,
+            ),
+            ("GAMEOVER", [winner, reason]) => ServerMessage::GameOver(
+                match *winner {
+                    "draw" => None,
+                    side => Some(parse_side(side)?),
+                },
+                Reason::from_name(reason)
+                    .ok_or(format!("<{}> is not a reason for a game to end.", reason))?,
+            ),
+            ("GAMES", [count]) => ServerMessage::Games(parse_number(count)?),
+            ("GAME", [game, moves, ..]) => ServerMessage::Game {
+                game: parse_number(game)?,
+                moves: parse_number(moves)?,
+                names: parse_names(nth_onwards(rest, 2))?,
+            },
+            ("WATCHING", [game, columns, rows, ..]) => ServerMessage::Watching {
+                game: parse_number(game)?,
+                columns: parse_number(columns)?,
+                rows: parse_number(rows)?,
+                names: parse_names(nth_onwards(rest, 3))?,
+            },
+            ("HISTORY", [] | [_]) => ServerMessage::History(parse_moves(rest)?),
+            ("SPECTATORS", [count]) => ServerMessage::Spectators(parse_number(count)?),
+            _ => return Err(format!("Did not recognise <{}>.", line.trim())),
+        };
+        Ok(message)
+    }
+
+    pub fn to_line(&self) -> String {
+        match self {
+            ServerMessage::Hello(version) => format!("HELLO {}", version),
+            ServerMessage::Waiting => "WAITING".to_owned(),
+            ServerMessage::Opened(game) => format!("OPENED {}", game),
+            ServerMessage::Cancelled => "CANCELLED".to_owned(),
+            ServerMessage::Lobby(count) => format!("LOBBY {}", count),
+            ServerMessage::OpenGame {
+                game,
+                rating,
+                ruleset,
+            } => format!(
+                "OPENGAME {} {} {}\t{}",
+                game,
+                rating,
+                ruleset.name,
+                ruleset.settings()
+            ),
+            ServerMessage::Start {
+                side,
+                columns,
+                rows,
+                opponent,
+            } => format!(
+                "START {} {} {} {}",
+                side_name(*side),
+                columns,
+                rows,
+                opponent
+            ),
+            ServerMessage::Session(token, grace) => {
+                format!("SESSION {} {}", token, grace.as_secs())
+            }
+            ServerMessage::Resumed {
+                game,
+                side,
+                columns,
+                rows,
+                names,
+            } => format!(
+                "RESUMED {} {} {} {} {}",
+                game,
+                side_name(*side),
+                columns,
+                rows,
+                names.join("\t")
+            ),
+            ServerMessage::Away(side, grace) => {
+                format!("AWAY {} {}", side_name(*side), grace.as_secs())
+            }
+            ServerMessage::Back(side) => format!("BACK {}", side_name(*side)),
+            ServerMessage::Board(board) => format!("BOARD {}", encode(board)),
+            ServerMessage::Turn(side) => format!("TURN {}", side_name(*side)),
+            ServerMessage::Clock([one, two]) => {
+                format!("CLOCK {} {}", one.as_millis(), two.as_millis())
+            }
+            ServerMessage::Moved(side, column) => format!("MOVED {} {}", side_name(*side), column),
+            ServerMessage::Error(message) => format!("ERROR {}", message),
+            ServerMessage::Line(cells) => {
+                let cells: Vec<String> = cells
+                    .iter()
+                    .map(|(column, row)| format!("{},{}", column, row))
+                    .collect();
+                format!("LINE {}", cells.join(" "))
+            }
+            ServerMessage::GameOver(winner, reason) => format!(
+                "GAMEOVER {} {}",
+                winner.map_or("draw", side_name),
+                reason.name()
+            ),
+            ServerMessage::Games(count) => format!("GAMES {}", count),
+            ServerMessage::Game { game, moves, names } => {
+                format!("GAME {} {} {}", game, moves, names.join("\t"))
+            }
+            ServerMessage::Watching {
+                game,
+                columns,
+                rows,
+                names,
+            } => format!(
+                "WATCHING {} {} {} {}",
+                game,
+                columns,
+                rows,
+                names.join("\t")
+            ),
+            ServerMessage::History(moves) => {
+                let moves: Vec<String> = moves.iter().map(|x| x.to_string()).collect();
+                format!("HISTORY {}", moves.join(","))
+            }
+            ServerMessage::Spectators(count) => format!("SPECTATORS {}", count),
+        }
+    }
+}
+
+pub fn side_name(side: Player) -> &'static str {
+    match side {
+        Player::Two => "two",
+        _ => "one",
+    }
+}
+
+fn parse_side(name: &str) -> Result<Player, String> {
+    match name {
+        "one" => Ok(Player::One),
+        "two" => Ok(Player::Two),
+        other => Err(format!("<{}> is not a side. Expected one or two.", other)),
+    }
+}
+
+fn parse_number(value: &str) -> Result<usize, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("<{}> could not be parsed as a usize.", value.trim()))
+}
+
+// Both players' names, separated by a tab
+fn parse_names(names: &str) -> Result<[String; 2], String> {
+    match names.split_once('\t') {
+        Some((one, two)) => Ok([one.to_owned(), two.to_owned()]),
+        None => Err(format!("<{}> is not two names separated by a tab.", names)),
+    }
+}
+
+// Everything after the first n fields, which is where names go
+fn nth_onwards(rest: &str, n: usize) -> &str {
+    rest.splitn(n + 1, ' ').nth(n).unwrap_or_default()
+}
+
+// The keyword and whatever follows it, without the line ending
+fn split_keyword(line: &str) -> (&str, &str) {
+    let line = line.trim();
+    match line.split_once(' ') {
+        Some((keyword, rest)) => (keyword, rest.trim()),
+        None => (line, ""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::remove_consts::{game::Player, ruleset::Ruleset};
+
+    use super::{ClientMessage, Reason, ServerMessage};
+
+    #[test]
+    fn reads_client_messages() {
+        assert_eq!(
+            ClientMessage::parse("JOIN Ada Lovelace\r\n"),
+            Ok(ClientMessage::Join("Ada Lovelace".to_owned()))
+        );
+        assert_eq!(ClientMessage::parse("MOVE 3"), Ok(ClientMessage::Move(3)));
+        assert_eq!(ClientMessage::parse("RESIGN"), Ok(ClientMessage::Resign));
+        assert!(ClientMessage::parse("JOIN").is_err());
+        assert!(ClientMessage::parse("MOVE three").is_err());
+        assert!(ClientMessage::parse("BOARD please").is_err());
+        assert!(ClientMessage::parse("move 3").is_err());
+        assert_eq!(ClientMessage::parse("WATCH 2"), Ok(ClientMessage::Watch(2)));
+        assert!(ClientMessage::parse("JOIN Ada\tGrace").is_err());
+        assert_eq!(
+            ClientMessage::parse("LOCAL Ada\tGrace Hopper"),
+            Ok(ClientMessage::Local([
+                "Ada".to_owned(),
+                "Grace Hopper".to_owned()
+            ]))
+        );
+        assert!(ClientMessage::parse("LOCAL Ada").is_err());
+        assert_eq!(
+            ClientMessage::parse("RESUME 5f0c3a"),
+            Ok(ClientMessage::Resume("5f0c3a".to_owned()))
+        );
+        assert!(ClientMessage::parse("RESUME").is_err());
+        assert!(ClientMessage::parse("RESUME 5f0c 3a").is_err());
+    }
+
+    #[test]
+    fn reads_lobby_messages() {
+        assert_eq!(
+            ClientMessage::parse("CREATE Ada Lovelace"),
+            Ok(ClientMessage::Create("Ada Lovelace".to_owned(), None))
+        );
+        let Ok(ClientMessage::Create(name, Some(ruleset))) =
+            ClientMessage::parse("CREATE Ada\tcolumns=9 win_conditions=vertical,horizontal")
+        else {
+            panic!("Expected a game with its own rules");
+        };
+        assert_eq!(name, "Ada");
+        assert_eq!(ruleset.columns, 9);
+        assert_eq!(ruleset.win_conditions, vec!["Vertical", "Horizontal"]);
+        assert_eq!(
+            ClientMessage::parse(
+                &ClientMessage::Create(name.clone(), Some(ruleset.clone())).to_line()
+            ),
+            Ok(ClientMessage::Create(name, Some(ruleset)))
+        );
+        assert!(ClientMessage::parse("CREATE Ada\tcolumns=0").is_err());
+        assert!(ClientMessage::parse("CREATE").is_err());
+        assert_eq!(
+            ClientMessage::parse("ACCEPT 3 Grace Hopper"),
+            Ok(ClientMessage::Accept(3, "Grace Hopper".to_owned()))
+        );
+        assert!(ClientMessage::parse("ACCEPT 3").is_err());
+        assert!(ClientMessage::parse("ACCEPT Grace").is_err());
+        assert_eq!(ClientMessage::parse("LOBBY"), Ok(ClientMessage::Lobby));
+        assert_eq!(ClientMessage::parse("CANCEL"), Ok(ClientMessage::Cancel));
+    }
+
+    #[test]
+    fn server_messages_survive_a_round_trip() {
+        let messages = [
+            ServerMessage::Hello(1),
+            ServerMessage::Waiting,
+            ServerMessage::Start {
+                side: Player::Two,
+                columns: 7,
+                rows: 6,
+                opponent: "Grace Hopper".to_owned(),
+            },
+            ServerMessage::Board(vec![vec![Player::One, Player::None], vec![Player::Two; 2]]),
+            ServerMessage::Turn(Player::One),
+            ServerMessage::Moved(Player::Two, 6),
+            ServerMessage::Error("It's not your turn.".to_owned()),
+            ServerMessage::GameOver(None, Reason::Full),
+            ServerMessage::GameOver(Some(Player::One), Reason::Disconnected),
+            ServerMessage::Games(2),
+            ServerMessage::Game {
+                game: 4,
+                moves: 12,
+                names: ["Ada Lovelace".to_owned(), "Grace".to_owned()],
+            },
+            ServerMessage::Watching {
+                game: 4,
+                columns: 7,
+                rows: 6,
+                names: ["Ada".to_owned(), "Grace Hopper".to_owned()],
+            },
+            ServerMessage::History(vec![3, 3, 4]),
+            ServerMessage::History(vec![]),
+            ServerMessage::Spectators(3),
+            ServerMessage::Line(vec![(3, 0), (4, 1), (5, 2), (6, 3)]),
+            ServerMessage::GameOver(Some(Player::Two), Reason::Abandoned),
+            ServerMessage::Opened(5),
+            ServerMessage::Cancelled,
+            ServerMessage::Lobby(1),
+            ServerMessage::OpenGame {
+                game: 5,
+                rating: 1532,
+                ruleset: Ruleset {
+                    name: "Ada Lovelace".to_owned(),
+                    columns: 9,
+                    ..Default::default()
+                },
+            },
+            ServerMessage::Session("5f0c3a".to_owned(), Duration::from_secs(60)),
+            ServerMessage::Resumed {
+                game: 2,
+                side: Player::Two,
+                columns: 7,
+                rows: 6,
+                names: ["Ada".to_owned(), "Grace Hopper".to_owned()],
+            },
+            ServerMessage::Away(Player::One, Duration::from_secs(45)),
+            ServerMessage::Back(Player::One),
+            ServerMessage::Clock([Duration::from_millis(299_500), Duration::from_secs(300)]),
+            ServerMessage::GameOver(None, Reason::Timeout),
+        ];
+        for message in messages {
+            assert_eq!(ServerMessage::parse(&message.to_line()), Ok(message));
+        }
+        assert_eq!(
+            ServerMessage::Board(vec![vec![Player::One, Player::None], vec![Player::Two; 2]])
+                .to_line(),
+            "BOARD xb/oo"
+        );
+        assert!(ServerMessage::parse("GAMEOVER one bored").is_err());
+    }
+}
@@ -0,0 +1,16 @@
+// A xorshift generator. It's plenty for picking moves and keeps the crate free of dependencies.
+pub struct Random(u64);
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift gets stuck on zero, so mix the seed into a non-zero state
+        Random(seed ^ 0x9E37_79B9_7F4A_7C15 | 1)
+    }
+
+    pub fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+}
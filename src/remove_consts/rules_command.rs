@@ -0,0 +1,40 @@
+use std::path::Path;
+
+use crate::remove_consts::ruleset::{ruleset_files, Ruleset, RULES_EXTENSION};
+
+// Lists every ruleset file in the directory and whether it's valid. Returns false if any of them aren't.
+pub fn check_directory(directory: &str) -> bool {
+    let files = match ruleset_files(directory) {
+        Ok(files) => files,
+        Err(error) => {
+            eprintln!(
+                "Could not read the rules directory <{}>: {}",
+                directory, error
+            );
+            return false;
+        }
+    };
+    if files.is_empty() {
+        println!(
+            "There are no .{} files in <{}>.",
+            RULES_EXTENSION, directory
+        );
+        return true;
+    }
+
+    let mut all_valid = true;
+    for path in files {
+        let file_name = Path::new(&path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        match Ruleset::load(&path) {
+            Ok(ruleset) => println!("{}: OK. {}", file_name, ruleset),
+            Err(message) => {
+                all_valid = false;
+                println!("{}: INVALID. {}", file_name, message);
+            }
+        }
+    }
+    all_valid
+}
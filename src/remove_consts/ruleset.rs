@@ -0,0 +1,388 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    game::{Player, Variant, DEFAULT_COLUMNS, DEFAULT_ROWS},
+    win_conditions::{
+        default_win_conditions, win_condition_with_length, WinCondition, DEFAULT_CONNECT_LENGTH,
+    },
+};
+
+pub const DEFAULT_RULES_DIRECTORY: &str = "rules";
+pub const RULES_EXTENSION: &str = "rules";
+
+// The most columns or rows a board hosted for someone else, in the lobby or over the API, can have
+pub const MAX_HOSTED_BOARD_SIZE: usize = 20;
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Ruleset {
+    pub name: String,
+    pub columns: usize,
+    pub rows: usize,
+    pub connect_length: usize,
+    // The display names of the enabled win conditions, without a connect length
+    pub win_conditions: Vec<String>,
+    pub variants: Vec<Variant>,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Self {
+            name: "Standard".to_owned(),
+            columns: DEFAULT_COLUMNS,
+            rows: DEFAULT_ROWS,
+            connect_length: DEFAULT_CONNECT_LENGTH,
+            win_conditions: default_win_conditions()
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            variants: vec![],
+        }
+    }
+}
+
+impl Ruleset {
+    /* Parses a ruleset file, using the default name if the file doesn't name itself.
+    Each line is a `key = value` pair, and anything after a '#' is a comment.
+    Every key is optional and falls back to the standard rules. For example:
+
+    name = House rules
+    columns = 9
+    rows = 7
+    connect = 5
+    win_conditions = vertical, horizontal, forward diagonal, reverse diagonal
+    variants = misere
+    */
+    pub fn parse(contents: &str, default_name: &str) -> Result<Self, String> {
+        let ruleset = Ruleset::read(contents, default_name)?;
+        ruleset.validate()?;
+        Ok(ruleset)
+    }
+
+    /* Parses a ruleset someone else asked for, refusing boards too large to host before
+    validating it, as that builds a full board.
+    */
+    pub fn parse_hosted(contents: &str, default_name: &str) -> Result<Self, String> {
+        let ruleset = Ruleset::read(contents, default_name)?;
+        ruleset.check_hosted_size()?;
+        ruleset.validate()?;
+        Ok(ruleset)
+    }
+
+    fn read(contents: &str, default_name: &str) -> Result<Self, String> {
+        let mut ruleset = Ruleset {
+            name: default_name.to_owned(),
+            ..Default::default()
+        };
+        let mut seen: Vec<&str> = vec![];
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| format!("Line {}: {}", index + 1, message);
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error(format!("Expected <key = value> but found <{}>.", line)))?;
+            let (key, value) = (key.trim(), value.trim());
+            if seen.contains(&key) {
+                return Err(error(format!("<{}> is set more than once.", key)));
+            }
+            seen.push(key);
+
+            match key {
+                "name" => ruleset.name = value.to_owned(),
+                "columns" => ruleset.columns = parse_count(value).map_err(error)?,
+                "rows" => ruleset.rows = parse_count(value).map_err(error)?,
+                "connect" => ruleset.connect_length = parse_count(value).map_err(error)?,
+                "win_conditions" => {
+                    ruleset.win_conditions = split_list(value)
+                        .map(|name| {
+                            win_condition_with_length(name, DEFAULT_CONNECT_LENGTH)
+                                .map(|x| x.to_string())
+                                .ok_or_else(|| {
+                                    error(format!(
+                                        "Did not recognise <{}> as a win condition.",
+                                        name
+                                    ))
+                                })
+                        })
+                        .collect::<Result<_, _>>()?
+                }
+                "variants" => {
+                    ruleset.variants = split_list(value)
+                        .map(|name| {
+                            Variant::from_name(name).ok_or_else(|| {
+                                error(format!("Did not recognise <{}> as a variant.", name))
+                            })
+                        })
+                        .collect::<Result<_, _>>()?
+                }
+                other => {
+                    return Err(error(format!(
+                        "Did not recognise <{}> as a setting.",
+                        other
+                    )))
+                }
+            }
+        }
+        Ok(ruleset)
+    }
+
+    /* Parses space separated settings in the same form as a ruleset file, as batch lines and
+    network games take them. For example:
+    columns=9 rows=7 connect=5 win_conditions=vertical,forward-diagonal variants=misere
+    Win conditions with spaces in their names can be written with '-' instead.
+    */
+    pub fn from_settings(settings: &str, name: &str) -> Result<Self, String> {
+        Ruleset::parse(&settings_lines(settings)?, name).map_err(without_line_number)
+    }
+
+    // As `from_settings`, for settings sent over the network to be hosted
+    pub fn from_hosted_settings(settings: &str, name: &str) -> Result<Self, String> {
+        Ruleset::parse_hosted(&settings_lines(settings)?, name).map_err(without_line_number)
+    }
+
+    // Every setting but the name, in the form `from_settings` reads
+    pub fn settings(&self) -> String {
+        let win_conditions: Vec<String> = self
+            .win_conditions
+            .iter()
+            .map(|name| name.to_lowercase().replace(' ', "-"))
+            .collect();
+        let mut settings = format!(
+            "columns={} rows={} connect={} win_conditions={}",
+            self.columns,
+            self.rows,
+            self.connect_length,
+            win_conditions.join(",")
+        );
+        if !self.variants.is_empty() {
+            let variants: Vec<String> = self
+                .variants
+                .iter()
+                .map(|x| x.to_string().to_lowercase())
+                .collect();
+            settings += &format!(" variants={}", variants.join(","));
+        }
+        settings
+    }
+
+    // Loads a ruleset file, naming it after the file if it doesn't name itself
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .map_err(|error| format!("Could not read <{}>: {}", path.display(), error))?;
+        let default_name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ruleset::parse(&contents, &default_name)
+    }
+
+    pub fn win_conditions(&self) -> Vec<Box<dyn WinCondition>> {
+        self.win_conditions
+            .iter()
+            .map(|name| {
+                win_condition_with_length(name, self.connect_length)
+                    .expect("Ruleset win conditions are validated when parsed")
+            })
+            .collect()
+    }
+
+    pub fn check_hosted_size(&self) -> Result<(), String> {
+        if self.columns > MAX_HOSTED_BOARD_SIZE || self.rows > MAX_HOSTED_BOARD_SIZE {
+            return Err(format!(
+                "Boards can have at most {} columns and {} rows.",
+                MAX_HOSTED_BOARD_SIZE, MAX_HOSTED_BOARD_SIZE
+            ));
+        }
+        Ok(())
+    }
+
+    // Rules out rulesets where nobody could ever win, by checking for a win on a board full of one player's pieces
+    pub fn validate(&self) -> Result<(), String> {
+        if self.win_conditions.is_empty() {
+            return Err("No win conditions are enabled, so every game would be a draw.".to_owned());
+        }
+        // No line can be longer than the board is wide or tall, and checking for one would take a full board
+        if self.connect_length > self.columns.max(self.rows) {
+            return Err(format!(
+                "Nobody can connect {} on a board of size [{},{}].",
+                self.connect_length, self.columns, self.rows
+            ));
+        }
+        let full_board = vec![vec![Player::One; self.rows]; self.columns];
+        let can_be_won = self.win_conditions().iter().any(|win_condition| {
+            (0..self.columns).any(|column| {
+                (0..self.rows).any(|row| win_condition.is_met(&full_board, column, row))
+            })
+        });
+        if !can_be_won {
+            return Err(format!(
+                "Nobody can connect {} on a board of size [{},{}] with these win conditions.",
+                self.connect_length, self.columns, self.rows
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Ruleset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: board size [{},{}], connect {}, win conditions: {}",
+            self.name,
+            self.columns,
+            self.rows,
+            self.connect_length,
+            self.win_conditions.join(", ")
+        )?;
+        if !self.variants.is_empty() {
+            let printable_variants: Vec<String> =
+                self.variants.iter().map(|x| format!("{}", x)).collect();
+            write!(f, ", variants: {}", printable_variants.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+// Settings in the form `from_settings` reads, as the lines of a ruleset file
+fn settings_lines(settings: &str) -> Result<String, String> {
+    let settings: Vec<&str> = settings.split_whitespace().collect();
+    for setting in &settings {
+        if !setting.contains('=') {
+            return Err(format!("Expected <setting=value> but found <{}>.", setting));
+        }
+    }
+    Ok(settings.join("\n"))
+}
+
+// Errors from parsing name a line of the file, which means nothing when the settings weren't in one
+pub fn without_line_number(message: String) -> String {
+    match message
+        .strip_prefix("Line ")
+        .and_then(|x| x.split_once(": "))
+    {
+        Some((_, message)) => message.to_owned(),
+        None => message,
+    }
+}
+
+fn parse_count(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!(
+            "The value <{}> could not be parsed as a number above 0.",
+            value
+        )),
+    }
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|x| !x.is_empty())
+}
+
+// Every ruleset file in the directory, in name order
+pub fn ruleset_files(directory: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|x| x == RULES_EXTENSION))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remove_consts::game::Variant;
+
+    use super::Ruleset;
+
+    #[test]
+    fn missing_settings_fall_back_to_the_standard_rules() {
+        let ruleset = Ruleset::parse("# nothing to see here\n\n", "Standard").unwrap();
+        assert_eq!(ruleset, Ruleset::default());
+        assert_eq!(ruleset.win_conditions().len(), 4);
+    }
+
+    #[test]
+    fn parses_every_setting() {
+        let ruleset = Ruleset::parse(
+            "name = House rules
+columns = 9 # wider than usual
+rows=7
+connect = 5
+win_conditions = vertical, Horizontal
+variants = misere",
+            "Test",
+        )
+        .unwrap();
+        assert_eq!(ruleset.name, "House rules");
+        assert_eq!((ruleset.columns, ruleset.rows), (9, 7));
+        assert_eq!(ruleset.connect_length, 5);
+        assert_eq!(ruleset.win_conditions, vec!["Vertical", "Horizontal"]);
+        assert_eq!(ruleset.variants, vec![Variant::Misere]);
+        let names: Vec<String> = ruleset
+            .win_conditions()
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        assert_eq!(names, vec!["Vertical (5)", "Horizontal (5)"]);
+    }
+
+    #[test]
+    fn settings_survive_a_round_trip() {
+        let ruleset = Ruleset::from_settings(
+            "columns=9 rows=7 connect=5 win_conditions=vertical,forward-diagonal variants=misere",
+            "Custom",
+        )
+        .unwrap();
+        assert_eq!(ruleset.win_conditions, vec!["Vertical", "Forward Diagonal"]);
+        assert_eq!(
+            Ruleset::from_settings(&ruleset.settings(), "Custom"),
+            Ok(ruleset)
+        );
+        assert_eq!(
+            Ruleset::from_settings(&Ruleset::default().settings(), "Standard"),
+            Ok(Ruleset::default())
+        );
+        assert!(Ruleset::from_settings("columns 9", "Custom").is_err());
+    }
+
+    #[test]
+    fn reports_mistakes_with_their_line_number() {
+        let error = Ruleset::parse("columns = 8\nrows = many", "Test").unwrap_err();
+        assert!(error.starts_with("Line 2:"), "{}", error);
+        assert!(Ruleset::parse("columns = 0", "Test").is_err());
+        assert!(Ruleset::parse("colour = red", "Test").is_err());
+        assert!(Ruleset::parse("rows = 5\nrows = 6", "Test").is_err());
+        assert!(Ruleset::parse("just some words", "Test").is_err());
+        assert!(Ruleset::parse("win_conditions = sideways", "Test").is_err());
+        assert!(Ruleset::parse("variants = pop out", "Test").is_err());
+    }
+
+    #[test]
+    fn rejects_rulesets_that_nobody_can_win() {
+        assert!(Ruleset::parse("win_conditions =", "Test").is_err());
+        assert!(Ruleset::parse("columns = 4\nrows = 4\nconnect = 5", "Test").is_err());
+        assert!(Ruleset::parse("rows = 3\nwin_conditions = vertical", "Test").is_err());
+        assert!(Ruleset::parse("rows = 3\nwin_conditions = vertical, horizontal", "Test").is_ok());
+        assert!(Ruleset::parse("connect = 18446744073709551615", "Test").is_err());
+        assert!(Ruleset::from_hosted_settings("connect=18446744073709551615", "Custom").is_err());
+    }
+
+    #[test]
+    fn refuses_to_host_boards_that_are_too_large() {
+        assert!(Ruleset::from_settings("columns=21", "Custom").is_ok());
+        assert_eq!(
+            Ruleset::from_hosted_settings("columns=21", "Custom"),
+            Err("Boards can have at most 20 columns and 20 rows.".to_owned())
+        );
+        assert!(Ruleset::parse_hosted("columns = 100000\nrows = 100000", "Custom").is_err());
+        assert!(Ruleset::from_hosted_settings("columns=20 rows=20", "Custom").is_ok());
+    }
+}
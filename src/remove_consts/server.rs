@@ -0,0 +1,1606 @@
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::BuildHasher,
+    io::{self, BufRead, BufReader, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    scoreboard::Scoreboard,
+    stats::{self, PlayedGame, Stats, DEFAULT_STATS_PATH},
+};
+
+use super::{
+    clock::{self, Clock, TimeControl},
+    game::{Game, GameStatus, Player},
+    protocol::{ClientMessage, Reason, ServerMessage, PROTOCOL_VERSION},
+    ruleset::Ruleset,
+    web, websocket,
+    win_conditions::WinCondition,
+};
+
+pub type ConnectionId = usize;
+
+// How long a player whose connection drops has to come back, unless the host sets otherwise
+pub const DEFAULT_GRACE: Duration = Duration::from_secs(60);
+
+// How far apart two waiting players' ratings can be to be matched straight away
+const MATCH_GAP: f64 = 100.0;
+
+// How much further apart they can be for every interval the longer waiting player has waited
+const MATCH_GAP_GROWTH: f64 = 50.0;
+const MATCH_GAP_INTERVAL: Duration = Duration::from_secs(10);
+
+// How long an open game waits for someone to accept it
+const OPEN_GAME_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+// How long a game without a clock can go without a move before the side to move loses it
+const IDLE_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+// How often the server checks the queue and clears away old games
+const TICK: Duration = Duration::from_secs(1);
+
+/* How long a reply can wait on a connection that isn't reading before it's dropped, as one thread
+writes to every connection, and a stuck write would hold up every game
+*/
+pub const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Messages are a few words or a ruleset's settings, so a terminal sending a longer line is dropped
+const MAX_LINE_LENGTH: usize = 64 * 1024;
+
+// What the server needs done after handling a message
+#[derive(PartialEq, Debug)]
+pub enum Action {
+    Send(ConnectionId, ServerMessage),
+    // Close the connection, after sending everything before this
+    Close(ConnectionId),
+    // A game has finished, and should be added to the stats
+    Finished(PlayedGame),
+}
+
+struct Table {
+    ruleset: Ruleset,
+    moves: Vec<usize>,
+    // The connections playing as player one and player two, which are the same in a local game
+    seats: [ConnectionId; 2],
+    names: [String; 2],
+    spectators: Vec<ConnectionId>,
+    // When the game started, or the last move was played
+    last_move_at: Instant,
+    // What each player resumes with, which local games don't have
+    tokens: Option<[String; 2]>,
+    // When each player's connection dropped, while they have the grace period to come back
+    away: [Option<Instant>; 2],
+    // Runs for the side to move, including while they're away
+    clock: Option<Clock>,
+}
+
+impl Table {
+    // The moves were checked as they were played, so replaying them can't fail
+    fn replay<'a>(&self, win_conditions: &'a Vec<Box<dyn WinCondition>>) -> Game<'a> {
+        self.moves.iter().fold(
+            Game::initialise(self.ruleset.columns, self.ruleset.rows, win_conditions)
+                .with_variants(&self.ruleset.variants),
+            |game, &column| {
+                game.play_on_column(column)
+                    .expect("Moves are checked when played")
+            },
+        )
+    }
+
+    fn current(&self) -> Player {
+        match self.moves.len() % 2 {
+            0 => Player::One,
+            _ => Player::Two,
+        }
+    }
+
+    // Both sides' time left, in games with a clock
+    fn times(&self, now: Instant) -> Option<ServerMessage> {
+        let clock = self.clock.as_ref()?;
+        let times = [Player::One, Player::Two].map(|side| clock.remaining(side, now));
+        Some(ServerMessage::Clock(times))
+    }
+
+    // The seats whose players are still connected
+    fn present(&self) -> impl Iterator<Item = ConnectionId> + '_ {
+        let seats = match self.seats[0] == self.seats[1] {
+            true => &self.seats[..1],
+            false => &self.seats[..],
+        };
+        seats
+            .iter()
+            .zip(self.away)
+            .filter(|(_, away)| away.is_none())
+            .map(|(&seat, _)| seat)
+    }
+}
+
+// A game waiting for someone to accept it, with the rules whoever opened it chose
+struct OpenGame {
+    host: ConnectionId,
+    ruleset: Ruleset,
+    opened_at: Instant,
+}
+
+/* Pairs players into games and referees them. Everything arrives here one event at a time,
+and whatever should be sent back is given as actions, so this never touches a socket.
+Players joining are matched with whoever's waiting with the closest rating, within a gap that
+widens the longer they wait, and whoever waited longer plays first. Players can also open a game
+with their own rules in the lobby, for someone else to accept.
+Games run side by side, each with its own rules, and anyone else can watch them unless the host
+has turned spectating off. Time only passes in `tick`, which matches players as their gaps widen
+and clears away games that have been left.
+A player whose connection drops keeps their seat for the grace period, and can take it back from a
+new connection with the token they were given at the start. Their opponent is told they've gone,
+and when they're back.
+With a time control every game has a clock, and a player whose time runs out loses, unless their
+opponent has no way left to win, which makes it a draw.
+*/
+pub struct Server {
+    ruleset: Ruleset,
+    // For the players' ratings, kept up to date as games finish
+    stats: Stats,
+    names: HashMap<ConnectionId, String>,
+    // Players waiting to be matched, and since when, in the order they joined
+    queue: Vec<(ConnectionId, Instant)>,
+    open_games: HashMap<usize, OpenGame>,
+    // The table each playing connection sits at
+    seated: HashMap<ConnectionId, usize>,
+    tables: HashMap<usize, Table>,
+    // Open games and tables are numbered from the same count, and keep their number when accepted
+    next_table: usize,
+    // The table each spectator is watching
+    watching: HashMap<ConnectionId, usize>,
+    spectating: bool,
+    // How long a dropped player has to come back, where nothing forfeits the game straight away
+    grace: Duration,
+    // Games are untimed without one
+    time_control: Option<TimeControl>,
+    // Randomly keyed for each server, so tokens can't be guessed from the ones before
+    token_keys: RandomState,
+    tokens_issued: u64,
+}
+
+impl Server {
+    pub fn new(
+        ruleset: Ruleset,
+        stats: Stats,
+        spectating: bool,
+        grace: Duration,
+        time_control: Option<TimeControl>,
+    ) -> Self {
+        Server {
+            ruleset,
+            stats,
+            names: HashMap::new(),
+            queue: vec![],
+            open_games: HashMap::new(),
+            seated: HashMap::new(),
+            tables: HashMap::new(),
+            next_table: 0,
+            watching: HashMap::new(),
+            spectating,
+            grace,
+            time_control,
+            token_keys: RandomState::new(),
+            tokens_issued: 0,
+        }
+    }
+
+    pub fn connect(&mut self, id: ConnectionId) -> Vec<Action> {
+        vec![Action::Send(id, ServerMessage::Hello(PROTOCOL_VERSION))]
+    }
+
+    pub fn receive(&mut self, id: ConnectionId, line: &str) -> Vec<Action> {
+        let message = match ClientMessage::parse(line) {
+            Ok(message) => message,
+            Err(message) => return error(id, &message),
+        };
+        let now = Instant::now();
+        match message {
+            ClientMessage::Join(name) => self.join(id, name, now),
+            ClientMessage::Create(name, ruleset) => self.create(id, name, ruleset, now),
+            ClientMessage::Lobby => self.lobby(id),
+            ClientMessage::Accept(game, name) => self.accept(id, game, name, now),
+            ClientMessage::Cancel => match self.stop_waiting(id) {
+                true => vec![Action::Send(id, ServerMessage::Cancelled)],
+                false => error(id, "You're not waiting for an opponent."),
+            },
+            ClientMessage::Local(names) => match self.busy(id) {
+                Err(message) => error(id, message),
+                Ok(()) => {
+                    let mut actions = self.stop_watching(id);
+                    actions.extend(self.start([id, id], names, self.ruleset.clone(), now));
+                    actions
+                }
+            },
+            ClientMessage::Resume(token) => self.resume(id, &token, now),
+            ClientMessage::Move(column) => self.play(id, column, now),
+            ClientMessage::Board => match self.table_of(id).or(self.watched_by(id)) {
+                Some(table) => {
+                    let table = &self.tables[&table];
+                    let win_conditions = table.ruleset.win_conditions();
+                    let board = table.replay(&win_conditions).board().clone();
+                    vec![Action::Send(id, ServerMessage::Board(board))]
+                }
+                None => error(id, "Join a game first."),
+            },
+            ClientMessage::Resign => match self.table_of(id) {
+                Some(table) => {
+                    let winner = self.side_of(table, id).opponent();
+                    self.finish(table, Some(winner), Reason::Resigned)
+                }
+                None => error(id, "Join a game first."),
+            },
+            ClientMessage::Games => self.list(id),
+            ClientMessage::Watch(table) => self.watch(id, table, now),
+            ClientMessage::Quit => {
+                let mut actions = self.leave(id);
+                actions.push(Action::Close(id));
+                actions
+            }
+        }
+    }
+
+    // A player whose connection drops mid-game has the grace period to come back before they lose it
+    pub fn disconnect(&mut self, id: ConnectionId) -> Vec<Action> {
+        match self.table_of(id) {
+            Some(table) if !self.is_local(table) && !self.grace.is_zero() => {
+                self.names.remove(&id);
+                let side = self.side_of(table, id);
+                self.seated.remove(&id);
+                let seated = self.tables.get_mut(&table).expect("Seated at a table");
+                seated.away[side_index(side)] = Some(Instant::now());
+                self.broadcast(table, ServerMessage::Away(side, self.grace))
+            }
+            _ => self.leave(id),
+        }
+    }
+
+    // A player leaving mid-game loses it. A local game is only abandoned, as nobody else was playing.
+    fn leave(&mut self, id: ConnectionId) -> Vec<Action> {
+        self.names.remove(&id);
+        self.stop_waiting(id);
+        match self.table_of(id) {
+            Some(table) if self.is_local(table) => self.close(table, None, Reason::Disconnected).1,
+            Some(table) => {
+                let winner = self.side_of(table, id).opponent();
+                self.finish(table, Some(winner), Reason::Disconnected)
+            }
+            None => self.stop_watching(id),
+        }
+    }
+
+    /* Seats the connection in place of the player the token was given to, and catches it up on the game.
+    The old connection is closed if it's still open, as a dropped connection isn't always noticed.
+    */
+    fn resume(&mut self, id: ConnectionId, token: &str, now: Instant) -> Vec<Action> {
+        if let Err(message) = self.busy(id) {
+            return error(id, message);
+        }
+        let found = self.tables.iter().find_map(|(&table, seated)| {
+            let tokens = seated.tokens.as_ref()?;
+            let index = tokens.iter().position(|x| x == token)?;
+            Some((table, index))
+        });
+        let Some((table, index)) = found else {
+            return error(
+                id,
+                "There's no game to resume with that token. It may have finished.",
+            );
+        };
+        let side = [Player::One, Player::Two][index];
+
+        let mut actions = self.stop_watching(id);
+        let seated = self.tables.get_mut(&table).expect("Found above");
+        let old = seated.seats[index];
+        if seated.away[index].is_none() {
+            self.names.remove(&old);
+            self.seated.remove(&old);
+            actions.push(Action::Close(old));
+        }
+        seated.away[index] = None;
+        seated.seats[index] = id;
+        self.seated.insert(id, table);
+        self.names.insert(id, seated.names[index].clone());
+
+        let win_conditions = seated.ruleset.win_conditions();
+        let game = seated.replay(&win_conditions);
+        let mut messages = vec![
+            ServerMessage::Resumed {
+                game: table,
+                side,
+                columns: seated.ruleset.columns,
+                rows: seated.ruleset.rows,
+                names: seated.names.clone(),
+            },
+            ServerMessage::History(seated.moves.clone()),
+            ServerMessage::Board(game.board().clone()),
+        ];
+        messages.extend(seated.times(now));
+        messages.push(ServerMessage::Turn(game.current));
+        for message in messages {
+            actions.push(Action::Send(id, message));
+        }
+        let others: Vec<ConnectionId> = seated
+            .present()
+            .filter(|&seat| seat != id)
+            .chain(seated.spectators.iter().copied())
+            .collect();
+        for other in others {
+            actions.push(Action::Send(other, ServerMessage::Back(side)));
+        }
+        actions
+    }
+
+    /* Matches anyone whose rating gap has widened enough, closes open games nobody accepted,
+    and ends games where the side to move has run out of time or gone quiet, or a player hasn't
+    come back in time, which loses them the game.
+    */
+    pub fn tick(&mut self, now: Instant) -> Vec<Action> {
+        let mut actions = self.matchmake(now);
+
+        let mut expired: Vec<usize> = self
+            .open_games
+            .iter()
+            .filter(|(_, open)| now.saturating_duration_since(open.opened_at) >= OPEN_GAME_TIMEOUT)
+            .map(|(&game, _)| game)
+            .collect();
+        expired.sort();
+        for game in expired {
+            let open = self.open_games.remove(&game).expect("Listed above");
+            actions.push(Action::Send(open.host, ServerMessage::Cancelled));
+        }
+
+        let mut flagged: Vec<(usize, Player)> = self
+            .tables
+            .iter()
+            .filter_map(|(&table, seated)| Some((table, seated.clock.as_ref()?.flagged(now)?)))
+            .collect();
+        flagged.sort_by_key(|&(table, _)| table);
+        for (table, side) in flagged {
+            actions.extend(self.time_out(table, side));
+        }
+
+        // The clock decides how long a move can take in the games that have one
+        let mut abandoned: Vec<usize> = self
+            .tables
+            .iter()
+            .filter(|(_, table)| table.clock.is_none())
+            .filter(|(_, table)| now.saturating_duration_since(table.last_move_at) >= IDLE_TIMEOUT)
+            .map(|(&table, _)| table)
+            .collect();
+        abandoned.sort();
+
+        let mut gone: Vec<(usize, Player)> = self
+            .tables
+            .iter()
+            .filter(|(table, _)| !abandoned.contains(table))
+            .filter_map(|(&table, seated)| {
+                let index = seated.away.iter().position(|away| {
+                    away.is_some_and(|x| now.saturating_duration_since(x) >= self.grace)
+                })?;
+                Some((table, [Player::One, Player::Two][index]))
+            })
+            .collect();
+        gone.sort_by_key(|&(table, _)| table);
+        for (table, side) in gone {
+            actions.extend(self.finish(table, Some(side.opponent()), Reason::Disconnected));
+        }
+
+        for table in abandoned {
+            actions.extend(match self.is_local(table) {
+                true => self.close(table, None, Reason::Abandoned).1,
+                false => {
+                    let winner = self.tables[&table].current().opponent();
+                    self.finish(table, Some(winner), Reason::Abandoned)
+                }
+            });
+        }
+        actions
+    }
+
+    fn join(&mut self, id: ConnectionId, name: String, now: Instant) -> Vec<Action> {
+        if let Err(message) = self.busy(id) {
+            return error(id, message);
+        }
+        let mut actions = self.stop_watching(id);
+        self.names.insert(id, name);
+        self.queue.push((id, now));
+        actions.extend(self.matchmake(now));
+        if self.queue.iter().any(|&(waiting, _)| waiting == id) {
+            actions.push(Action::Send(id, ServerMessage::Waiting));
+        }
+        actions
+    }
+
+    // Pairs each waiting player, longest waiting first, with the closest rated player within their gap
+    fn matchmake(&mut self, now: Instant) -> Vec<Action> {
+        let mut actions = vec![];
+        let mut index = 0;
+        while index < self.queue.len() {
+            let (first, since) = self.queue[index];
+            let waited =
+                now.saturating_duration_since(since).as_secs() / MATCH_GAP_INTERVAL.as_secs();
+            let gap = MATCH_GAP + MATCH_GAP_GROWTH * waited as f64;
+            let rating = self.rating(first);
+            let distance = |id: &ConnectionId| (self.rating(*id) - rating).abs();
+            let second = self.queue[index + 1..]
+                .iter()
+                .map(|&(id, _)| id)
+                .filter(|id| distance(id) <= gap)
+                .min_by(|a, b| distance(a).total_cmp(&distance(b)));
+            let Some(second) = second else {
+                index += 1;
+                continue;
+            };
+            self.queue.retain(|&(id, _)| id != first && id != second);
+            let seats = [first, second];
+            let names = seats.map(|x| self.names[&x].clone());
+            actions.extend(self.start(seats, names, self.ruleset.clone(), now));
+        }
+        actions
+    }
+
+    fn create(
+        &mut self,
+        id: ConnectionId,
+        name: String,
+        ruleset: Option<Ruleset>,
+        now: Instant,
+    ) -> Vec<Action> {
+        if let Err(message) = self.busy(id) {
+            return error(id, message);
+        }
+        let ruleset = ruleset.unwrap_or_else(|| self.ruleset.clone());
+        if let Err(message) = ruleset.check_hosted_size() {
+            return error(id, &message);
+        }
+        let mut actions = self.stop_watching(id);
+        self.names.insert(id, name);
+        let game = self.next_table;
+        self.next_table += 1;
+        self.open_games.insert(
+            game,
+            OpenGame {
+                host: id,
+                ruleset,
+                opened_at: now,
+            },
+        );
+        actions.push(Action::Send(id, ServerMessage::Opened(game)));
+        actions
+    }
+
+    // Every open game, oldest first, named after whoever opened it
+    fn lobby(&self, id: ConnectionId) -> Vec<Action> {
+        let mut games: Vec<&usize> = self.open_games.keys().collect();
+        games.sort();
+        let mut actions = vec![Action::Send(id, ServerMessage::Lobby(games.len()))];
+        for &game in games {
+            let open = &self.open_games[&game];
+            let name = self.names[&open.host].clone();
+            actions.push(Action::Send(
+                id,
+                ServerMessage::OpenGame {
+                    game,
+                    rating: self.rating(open.host).round().max(0.0) as usize,
+                    ruleset: Ruleset {
+                        name,
+                        ..open.ruleset.clone()
+                    },
+                },
+            ));
+        }
+        actions
+    }
+
+    fn accept(&mut self, id: ConnectionId, game: usize, name: String, now: Instant) -> Vec<Action> {
+        if let Err(message) = self.busy(id) {
+            return error(id, message);
+        }
+        let Some(open) = self.open_games.remove(&game) else {
+            return error(id, &format!("There is no open game {}.", game));
+        };
+        let mut actions = self.stop_watching(id);
+        self.names.insert(id, name);
+        let seats = [open.host, id];
+        let names = seats.map(|x| self.names[&x].clone());
+        // The open game's number carries over to its table
+        let next_table = std::mem::replace(&mut self.next_table, game);
+        actions.extend(self.start(seats, names, open.ruleset, now));
+        self.next_table = next_table;
+        actions
+    }
+
+    fn start(
+        &mut self,
+        seats: [ConnectionId; 2],
+        names: [String; 2],
+        ruleset: Ruleset,
+        now: Instant,
+    ) -> Vec<Action> {
+        let (columns, rows) = (ruleset.columns, ruleset.rows);
+        let table = self.next_table;
+        self.next_table += 1;
+        for seat in seats {
+            self.seated.insert(seat, table);
+        }
+        self.tables.insert(
+            table,
+            Table {
+                ruleset,
+                moves: vec![],
+                seats,
+                names,
+                spectators: vec![],
+                last_move_at: now,
+                tokens: None,
+                away: [None; 2],
+                clock: self.time_control.map(|control| {
+                    let mut clock = Clock::new(control);
+                    clock.start(Player::One, now);
+                    clock
+                }),
+            },
+        );
+        let local = seats[0] == seats[1];
+        if !local {
+            let tokens = [self.token(), self.token()];
+            self.tables.get_mut(&table).expect("Just added").tokens = Some(tokens);
+        }
+
+        let mut actions = vec![];
+        for (index, side) in [Player::One, Player::Two].into_iter().enumerate() {
+            // Whoever plays both sides starts as player one
+            if index == 1 && seats[0] == seats[1] {
+                break;
+            }
+            actions.push(Action::Send(
+                seats[index],
+                ServerMessage::Start {
+                    side,
+                    columns,
+                    rows,
+                    opponent: self.tables[&table].names[1 - index].clone(),
+                },
+            ));
+            if let Some(tokens) = &self.tables[&table].tokens {
+                actions.push(Action::Send(
+                    seats[index],
+                    ServerMessage::Session(tokens[index].clone(), self.grace),
+                ));
+            }
+        }
+        let board = vec![vec![Player::None; rows]; columns];
+        actions.extend(self.broadcast(table, ServerMessage::Board(board)));
+        if let Some(times) = self.tables[&table].times(now) {
+            actions.extend(self.broadcast(table, times));
+        }
+        actions.extend(self.broadcast(table, ServerMessage::Turn(Player::One)));
+        actions
+    }
+
+    fn play(&mut self, id: ConnectionId, column: usize, now: Instant) -> Vec<Action> {
+        if self.watched_by(id).is_some() {
+            return error(id, "Spectators can't make moves.");
+        }
+        let Some(table) = self.table_of(id) else {
+            return error(id, "Join a game first.");
+        };
+        let side = self.side_of(table, id);
+        let seated = self.tables.get_mut(&table).expect("Seated at a table");
+        if seated.current() != side {
+            return error(id, "It's not your turn.");
+        }
+        let win_conditions = seated.ruleset.win_conditions();
+        let game = match seated.replay(&win_conditions).play_on_column(column) {
+            Ok(game) => game,
+            Err(rejected) => return error(id, &rejected.message),
+        };
+        // A move that arrives after the time ran out, before the next tick noticed, is too late
+        if let Some(clock) = &mut seated.clock {
+            if clock.stop(now).is_err() {
+                return self.time_out(table, side);
+            }
+            clock.start(game.current, now);
+        }
+        seated.moves.push(column);
+        seated.last_move_at = now;
+
+        let mut actions = self.broadcast(table, ServerMessage::Moved(side, column));
+        actions.extend(self.broadcast(table, ServerMessage::Board(game.board().clone())));
+        if let Some(line) = game.winning_line() {
+            actions.extend(self.broadcast(table, ServerMessage::Line(line)));
+        }
+        if let Some(times) = self.tables[&table].times(now) {
+            actions.extend(self.broadcast(table, times));
+        }
+        actions.extend(match game.status {
+            GameStatus::Started => self.broadcast(table, ServerMessage::Turn(game.current)),
+            GameStatus::Completed => self.finish(table, game.winner, Reason::Connected),
+            GameStatus::Draw => self.finish(table, None, Reason::Full),
+        });
+        actions
+    }
+
+    // Every game being played, oldest first
+    fn list(&self, id: ConnectionId) -> Vec<Action> {
+        if !self.spectating {
+            return error(id, "The host has turned spectating off.");
+        }
+        let mut tables: Vec<&usize> = self.tables.keys().collect();
+        tables.sort();
+        let mut actions = vec![Action::Send(id, ServerMessage::Games(tables.len()))];
+        for &table in tables {
+            actions.push(Action::Send(
+                id,
+                ServerMessage::Game {
+                    game: table,
+                    moves: self.tables[&table].moves.len(),
+                    names: self.tables[&table].names.clone(),
+                },
+            ));
+        }
+        actions
+    }
+
+    // Catches the spectator up on the game, and lets everyone at the table know they're watching
+    fn watch(&mut self, id: ConnectionId, table: usize, now: Instant) -> Vec<Action> {
+        if !self.spectating {
+            return error(id, "The host has turned spectating off.");
+        }
+        if self.busy(id).is_err() {
+            return error(id, "Players can't watch other games.");
+        }
+        if !self.tables.contains_key(&table) {
+            return error(id, &format!("There is no game {} being played.", table));
+        }
+        let mut actions = self.stop_watching(id);
+        self.watching.insert(id, table);
+        let watched = self.tables.get_mut(&table).expect("Checked above");
+        watched.spectators.push(id);
+        let win_conditions = watched.ruleset.win_conditions();
+        let game = watched.replay(&win_conditions);
+        let mut messages = vec![
+            ServerMessage::Watching {
+                game: table,
+                columns: watched.ruleset.columns,
+                rows: watched.ruleset.rows,
+                names: watched.names.clone(),
+            },
+            ServerMessage::History(watched.moves.clone()),
+            ServerMessage::Board(game.board().clone()),
+        ];
+        messages.extend(watched.times(now));
+        messages.push(ServerMessage::Turn(game.current));
+        for message in messages {
+            actions.push(Action::Send(id, message));
+        }
+        let count = watched.spectators.len();
+        actions.extend(self.broadcast(table, ServerMessage::Spectators(count)));
+        actions
+    }
+
+    fn stop_watching(&mut self, id: ConnectionId) -> Vec<Action> {
+        let Some(table) = self.watching.remove(&id) else {
+            return vec![];
+        };
+        let watched = self
+            .tables
+            .get_mut(&table)
+            .expect("Watching a table in play");
+        watched.spectators.retain(|&x| x != id);
+        let count = watched.spectators.len();
+        self.broadcast(table, ServerMessage::Spectators(count))
+    }
+
+    // Takes the player out of the queue and closes any game they opened. Gives whether they were waiting.
+    fn stop_waiting(&mut self, id: ConnectionId) -> bool {
+        let waiting = self.queue.len() + self.open_games.len();
+        self.queue.retain(|&(waiting, _)| waiting != id);
+        self.open_games.retain(|_, open| open.host != id);
+        self.queue.len() + self.open_games.len() < waiting
+    }
+
+    // The side ran out of time, which loses unless their opponent has no way left to win
+    fn time_out(&mut self, table: usize, side: Player) -> Vec<Action> {
+        let seated = &self.tables[&table];
+        let win_conditions = seated.ruleset.win_conditions();
+        let winner = clock::flag_winner(&seated.replay(&win_conditions), side);
+        self.finish(table, winner, Reason::Timeout)
+    }
+
+    // Ends the game at the table and records it. Both players stay connected, and can join again.
+    fn finish(&mut self, table: usize, winner: Option<Player>, reason: Reason) -> Vec<Action> {
+        let (table, mut actions) = self.close(table, winner, reason);
+        let scoreboard = Scoreboard::new(table.names, None);
+        let played = PlayedGame::new(&scoreboard, winner, &table.moves);
+        self.stats.add(&played);
+        actions.push(Action::Finished(played));
+        actions
+    }
+
+    // Tells everyone at the table the game is over, and clears the table away
+    fn close(
+        &mut self,
+        table: usize,
+        winner: Option<Player>,
+        reason: Reason,
+    ) -> (Table, Vec<Action>) {
+        let actions = self.broadcast(table, ServerMessage::GameOver(winner, reason));
+        let table = self.tables.remove(&table).expect("Closing a table in play");
+        for seat in table.seats {
+            self.seated.remove(&seat);
+        }
+        for spectator in &table.spectators {
+            self.watching.remove(spectator);
+        }
+        (table, actions)
+    }
+
+    // Sends the message to both players, unless they're away, and everyone watching
+    fn broadcast(&self, table: usize, message: ServerMessage) -> Vec<Action> {
+        let table = &self.tables[&table];
+        table
+            .present()
+            .chain(table.spectators.iter().copied())
+            .map(|seat| Action::Send(seat, message.clone()))
+            .collect()
+    }
+
+    // Two hashes of a count, with keys that are random for each server
+    fn token(&mut self) -> String {
+        self.tokens_issued += 1;
+        let [first, second] =
+            [0, 1].map(|half| self.token_keys.hash_one((self.tokens_issued, half)));
+        format!("{:016x}{:016x}", first, second)
+    }
+
+    // Players can only wait for one game, or play one, at a time
+    fn busy(&self, id: ConnectionId) -> Result<(), &'static str> {
+        if self.table_of(id).is_some() {
+            return Err("You're already in a game.");
+        }
+        let queued = self.queue.iter().any(|&(waiting, _)| waiting == id);
+        if queued || self.open_games.values().any(|open| open.host == id) {
+            return Err("You're already waiting for an opponent.");
+        }
+        Ok(())
+    }
+
+    fn rating(&self, id: ConnectionId) -> f64 {
+        self.stats.rating(&self.names[&id])
+    }
+
+    fn table_of(&self, id: ConnectionId) -> Option<usize> {
+        self.seated.get(&id).copied()
+    }
+
+    fn watched_by(&self, id: ConnectionId) -> Option<usize> {
+        self.watching.get(&id).copied()
+    }
+
+    // In a local game it's whoever's turn it is
+    fn side_of(&self, table: usize, id: ConnectionId) -> Player {
+        let table = &self.tables[&table];
+        match (table.seats, table.seats[0] == id) {
+            ([one, two], _) if one == two => table.current(),
+            (_, true) => Player::One,
+            (_, false) => Player::Two,
+        }
+    }
+
+    fn is_local(&self, table: usize) -> bool {
+        let seats = self.tables[&table].seats;
+        seats[0] == seats[1]
+    }
+}
+
+fn side_index(side: Player) -> usize {
+    match side {
+        Player::Two => 1,
+        _ => 0,
+    }
+}
+
+fn error(id: ConnectionId, message: &str) -> Vec<Action> {
+    vec![Action::Send(id, ServerMessage::Error(message.to_owned()))]
+}
+
+pub enum Event {
+    Connected(ConnectionId, Writer),
+    Line(ConnectionId, String),
+    Disconnected(ConnectionId),
+}
+
+// How replies reach a connection: as lines for terminal clients, or as text messages for browsers
+pub enum Writer {
+    Lines(TcpStream),
+    WebSocket(TcpStream),
+}
+
+impl Writer {
+    fn send(&mut self, line: &str) -> io::Result<()> {
+        match self {
+            Writer::Lines(stream) => writeln!(stream, "{}", line),
+            Writer::WebSocket(stream) => websocket::write_text(stream, line),
+        }
+    }
+
+    fn close(self) {
+        let stream = match self {
+            Writer::Lines(stream) => stream,
+            Writer::WebSocket(mut stream) => {
+                let _ = websocket::write_close(&mut stream);
+                stream
+            }
+        };
+        let _ = stream.shutdown(Shutdown::Both);
+    }
+
+    // Drops the connection without a goodbye, for when writing to it has already failed
+    fn abandon(self) {
+        let (Writer::Lines(stream) | Writer::WebSocket(stream)) = self;
+        let _ = stream.shutdown(Shutdown::Both);
+    }
+}
+
+/* Hosts games until the process is stopped, for terminal clients on the address and browsers on the
+web address. Every connection has a thread reading its lines, and they all feed one channel,
+so a single thread owns the games and writes the replies.
+*/
+pub fn serve(
+    address: Option<&str>,
+    web_address: Option<&str>,
+    ruleset: &Ruleset,
+    spectating: bool,
+    grace: Duration,
+    time_control: Option<TimeControl>,
+) -> io::Result<()> {
+    let (events, received) = mpsc::channel();
+    // Both listeners number their connections from the same count
+    let ids = Arc::new(AtomicUsize::new(0));
+    println!("Serving games with the rules {}.", ruleset);
+    if let Some(address) = address {
+        let listener = TcpListener::bind(address)?;
+        println!("Terminals can connect on {}.", listener.local_addr()?);
+        let (events, ids) = (events.clone(), Arc::clone(&ids));
+        thread::spawn(move || accept(listener, events, &ids));
+    }
+    if let Some(address) = web_address {
+        let listener = TcpListener::bind(address)?;
+        println!("Browsers can play at http://{}.", listener.local_addr()?);
+        let (events, ids) = (events.clone(), Arc::clone(&ids));
+        thread::spawn(move || web::accept(listener, events, ids));
+    }
+    drop(events);
+    println!("Press Ctrl+C to stop.");
+    if !spectating {
+        println!("Spectating is turned off.");
+    }
+    if grace.is_zero() {
+        println!("Players who lose their connection lose the game straight away.");
+    }
+    if let Some(time_control) = time_control {
+        println!("Every game is played with a {} clock.", time_control);
+    }
+
+    // Players are matched on their ratings so far, but a server with no stats can still host games
+    let stats = Stats::load(DEFAULT_STATS_PATH).unwrap_or_else(|error| {
+        eprintln!(
+            "Could not read the stats <{}>, so everyone starts with the same rating: {}",
+            DEFAULT_STATS_PATH, error
+        );
+        Stats::default()
+    });
+    let mut server = Server::new(ruleset.clone(), stats, spectating, grace, time_control);
+    let mut writers: HashMap<ConnectionId, Writer> = HashMap::new();
+    let mut last_tick = Instant::now();
+    loop {
+        let mut actions = match received.recv_timeout(TICK.saturating_sub(last_tick.elapsed())) {
+            Ok(Event::Connected(id, writer)) => {
+                writers.insert(id, writer);
+                server.connect(id)
+            }
+            Ok(Event::Line(id, line)) => server.receive(id, &line),
+            Ok(Event::Disconnected(id)) => {
+                writers.remove(&id);
+                server.disconnect(id)
+            }
+            Err(RecvTimeoutError::Timeout) => vec![],
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        actions.extend(tick_if_due(&mut server, &mut last_tick, Instant::now()));
+        for action in actions {
+            match action {
+                /* A failed or timed out write drops the connection, and its reader sees it drop,
+                so it's handled there
+                */
+                Action::Send(id, message) => {
+                    let Some(writer) = writers.get_mut(&id) else {
+                        continue;
+                    };
+                    if writer.send(&message.to_line()).is_err() {
+                        if let Some(writer) = writers.remove(&id) {
+                            writer.abandon();
+                        }
+                    }
+                }
+                Action::Close(id) => {
+                    if let Some(writer) = writers.remove(&id) {
+                        writer.close();
+                    }
+                }
+                Action::Finished(played) => {
+                    println!("{}", played.to_line());
+                    if let Err(error) = stats::record(DEFAULT_STATS_PATH, &played) {
+                        eprintln!(
+                            "Could not add the game to the stats <{}>: {}",
+                            DEFAULT_STATS_PATH, error
+                        );
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Ticks whenever a tick has come due, so connections sending lines more often than that can't hold it off
+fn tick_if_due(server: &mut Server, last_tick: &mut Instant, now: Instant) -> Vec<Action> {
+    if now.saturating_duration_since(*last_tick) < TICK {
+        return vec![];
+    }
+    *last_tick = now;
+    server.tick(now)
+}
+
+fn accept(listener: TcpListener, events: Sender<Event>, ids: &AtomicUsize) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let Ok(writer) = stream.try_clone() else {
+            continue;
+        };
+        if writer.set_write_timeout(Some(WRITE_TIMEOUT)).is_err() {
+            continue;
+        }
+        let id = ids.fetch_add(1, Ordering::Relaxed);
+        if events
+            .send(Event::Connected(id, Writer::Lines(writer)))
+            .is_err()
+        {
+            return;
+        }
+        let events = events.clone();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stream);
+            while let Ok(Some(line)) = read_line(&mut reader) {
+                if events.send(Event::Line(id, line)).is_err() {
+                    return;
+                }
+            }
+            let _ = events.send(Event::Disconnected(id));
+        });
+    }
+}
+
+// The next line without its ending, or nothing at the end, failing on a line that's too long
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = vec![];
+    io::Read::take(reader, MAX_LINE_LENGTH as u64 + 1).read_until(b'\n', &mut line)?;
+    if line.is_empty() {
+        return Ok(None);
+    }
+    if line.pop_if(|x| *x == b'\n').is_some() {
+        line.pop_if(|x| *x == b'\r');
+    } else if line.len() > MAX_LINE_LENGTH {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "The line is too long.",
+        ));
+    }
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        remove_consts::{
+            clock::TimeControl,
+            game::Player,
+            protocol::{Reason, ServerMessage, PROTOCOL_VERSION},
+            ruleset::Ruleset,
+        },
+        stats::{PlayedGame, Stats},
+    };
+
+    use std::time::{Duration, Instant};
+
+    use super::{read_line, tick_if_due, Action, Server, DEFAULT_GRACE, MAX_LINE_LENGTH, TICK};
+
+    // Only the messages sent to the connection
+    fn sent_to(actions: &[Action], id: usize) -> Vec<ServerMessage> {
+        actions
+            .iter()
+            .filter_map(|action| match action {
+                Action::Send(to, message) if *to == id => Some(message.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pairs_players_in_the_order_they_join() {
+        let ruleset = Ruleset::default();
+        let mut server = Server::new(ruleset, Stats::default(), true, DEFAULT_GRACE, None);
+        assert_eq!(
+            sent_to(&server.connect(0), 0),
+            [ServerMessage::Hello(PROTOCOL_VERSION)]
+        );
+        assert_eq!(
+            sent_to(&server.receive(0, "JOIN Ada"), 0),
+            [ServerMessage::Waiting]
+        );
+        let actions = server.receive(1, "JOIN Grace");
+        let to_ada = sent_to(&actions, 0);
+        assert_eq!(
+            to_ada[0],
+            ServerMessage::Start {
+                side: Player::One,
+                columns: 7,
+                rows: 6,
+                opponent: "Grace".to_owned()
+            }
+        );
+        assert!(matches!(to_ada[1], ServerMessage::Session(_, _)));
+        assert_eq!(to_ada[3], ServerMessage::Turn(Player::One));
+        assert_eq!(sent_to(&actions, 1).len(), 4);
+        // A third player waits for a fourth
+        assert_eq!(
+            sent_to(&server.receive(2, "JOIN Alan"), 2),
+            [ServerMessage::Waiting]
+        );
+    }
+
+    #[test]
+    fn referees_moves_until_someone_connects_four() {
+        let ruleset = Ruleset::default();
+        let mut server = Server::new(ruleset, Stats::default(), true, DEFAULT_GRACE, None);
+        server.receive(0, "JOIN Ada");
+        server.receive(1, "JOIN Grace");
+        assert_eq!(
+            sent_to(&server.receive(1, "MOVE 3"), 1),
+            [ServerMessage::Error("It's not your turn.".to_owned())]
+        );
+        assert!(matches!(
+            sent_to(&server.receive(0, "MOVE 9"), 0)[..],
+            [ServerMessage::Error(_)]
+        ));
+        let mut actions = vec![];
+        for (id, column) in [(0, 3), (1, 4), (0, 3), (1, 4), (0, 3), (1, 4), (0, 3)] {
+            actions = server.receive(id, &format!("MOVE {}", column));
+        }
+        let to_grace = sent_to(&actions, 1);
+        assert_eq!(to_grace[0], ServerMessage::Moved(Player::One, 3));
+        assert!(matches!(to_grace[2], ServerMessage::Line(_)));
+        assert_eq!(
+            to_grace[3],
+            ServerMessage::GameOver(Some(Player::One), Reason::Connected)
+        );
+        let Some(Action::Finished(played)) = actions.last() else {
+            panic!("Expected the game to be recorded");
+        };
+        assert_eq!(played.names, ["Ada".to_owned(), "Grace".to_owned()]);
+        assert_eq!(played.winner, Some(0));
+        assert_eq!(played.moves, [3, 4, 3, 4, 3, 4, 3]);
+        // The game is over, so there's nothing to move in until they join again
+        assert_eq!(
+            sent_to(&server.receive(1, "MOVE 0"), 1),
+            [ServerMessage::Error("Join a game first.".to_owned())]
+        );
+    }
+
+    #[test]
+    fn leaving_or_resigning_gives_the_opponent_the_game() {
+        let ruleset = Ruleset::default();
+        // Without a grace period a dropped connection loses straight away
+        let mut server = Server::new(ruleset, Stats::default(), true, Duration::ZERO, None);
+        server.receive(0, "JOIN Ada");
+        server.receive(1, "JOIN Grace");
+        assert_eq!(
+            sent_to(&server.receive(1, "RESIGN"), 0),
+            [ServerMessage::GameOver(Some(Player::One), Reason::Resigned)]
+        );
+        server.receive(1, "JOIN Grace");
+        server.receive(0, "JOIN Ada");
+        // Grace waited this time, so plays first
+        let actions = server.disconnect(1);
+        assert_eq!(
+            sent_to(&actions, 0),
+            [ServerMessage::GameOver(
+                Some(Player::Two),
+                Reason::Disconnected
+            )]
+        );
+        assert!(server.disconnect(1).is_empty());
+        assert!(matches!(
+            server.receive(0, "QUIT").last(),
+            Some(Action::Close(0))
+        ));
+    }
+
+    #[test]
+    fn spectators_catch_up_and_then_follow_the_game() {
+        let ruleset = Ruleset::default();
+        let mut server = Server::new(ruleset, Stats::default(), true, DEFAULT_GRACE, None);
+        server.receive(0, "JOIN Ada");
+        server.receive(1, "JOIN Grace");
+        server.receive(0, "MOVE 3");
+        assert_eq!(
+            sent_to(&server.receive(2, "GAMES"), 2),
+            [
+                ServerMessage::Games(1),
+                ServerMessage::Game {
+                    game: 0,
+                    moves: 1,
+                    names: ["Ada".to_owned(), "Grace".to_owned()]
+                }
+            ]
+        );
+        let actions = server.receive(2, "WATCH 0");
+        let to_alan = sent_to(&actions, 2);
+        assert_eq!(to_alan[1], ServerMessage::History(vec![3]));
+        assert_eq!(to_alan[3], ServerMessage::Turn(Player::Two));
+        assert_eq!(sent_to(&actions, 0), [ServerMessage::Spectators(1)]);
+        assert_eq!(
+            sent_to(&server.receive(2, "MOVE 4"), 2),
+            [ServerMessage::Error(
+                "Spectators can't make moves.".to_owned()
+            )]
+        );
+        assert_eq!(
+            sent_to(&server.receive(1, "MOVE 4"), 2)[0],
+            ServerMessage::Moved(Player::Two, 4)
+        );
+        assert_eq!(
+            sent_to(&server.disconnect(2), 1),
+            [ServerMessage::Spectators(0)]
+        );
+        assert!(matches!(
+            sent_to(&server.receive(3, "WATCH 7"), 3)[..],
+            [ServerMessage::Error(_)]
+        ));
+    }
+
+    #[test]
+    fn the_host_can_turn_spectating_off() {
+        let ruleset = Ruleset::default();
+        let mut server = Server::new(ruleset, Stats::default(), false, DEFAULT_GRACE, None);
+        server.receive(0, "JOIN Ada");
+        server.receive(1, "JOIN Grace");
+        assert_eq!(
+            sent_to(&server.receive(2, "WATCH 0"), 2),
+            [ServerMessage::Error(
+                "The host has turned spectating off.".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn one_connection_can_play_both_sides_of_a_local_game() {
+        let ruleset = Ruleset::default();
+        let mut server = Server::new(ruleset, Stats::default(), true, DEFAULT_GRACE, None);
+        let started = sent_to(&server.receive(0, "LOCAL Ada\tGrace"), 0);
+        assert_eq!(started.len(), 3);
+        assert_eq!(started[2], ServerMessage::Turn(Player::One));
+        let mut actions = vec![];
+        for column in [3, 4, 3, 4, 3, 4, 3] {
+            actions = server.receive(0, &format!("MOVE {}", column));
+        }
+        let sent = sent_to(&actions, 0);
+        assert_eq!(
+            sent[2],
+            ServerMessage::Line(vec![(3, 0), (3, 1), (3, 2), (3, 3)])
+        );
+        assert_eq!(
+            sent[3],
+            ServerMessage::GameOver(Some(Player::One), Reason::Connected)
+        );
+        // Leaving a local game part way through abandons it without recording it
+        server.receive(0, "LOCAL Ada\tGrace");
+        server.receive(0, "MOVE 0");
+        let actions = server.disconnect(0);
+        assert!(!actions
+            .iter()
+            .any(|action| matches!(action, Action::Finished(_))));
+    }
+
+    #[test]
+    fn players_open_games_in_the_lobby_for_others_to_accept() {
+        let mut server = Server::new(
+            Ruleset::default(),
+            Stats::default(),
+            true,
+            DEFAULT_GRACE,
+            None,
+        );
+        assert_eq!(
+            sent_to(&server.receive(2, "LOBBY"), 2),
+            [ServerMessage::Lobby(0)]
+        );
+        assert_eq!(
+            sent_to(
+                &server.receive(0, "CREATE Ada\tcolumns=9 win_conditions=vertical"),
+                0
+            ),
+            [ServerMessage::Opened(0)]
+        );
+        let lobby = sent_to(&server.receive(2, "LOBBY"), 2);
+        assert_eq!(lobby[0], ServerMessage::Lobby(1));
+        let ServerMessage::OpenGame {
+            game: 0,
+            rating: 1500,
+            ruleset,
+        } = &lobby[1]
+        else {
+            panic!("Expected Ada's game to be open");
+        };
+        assert_eq!(ruleset.name, "Ada");
+        assert_eq!(ruleset.columns, 9);
+        assert_eq!(ruleset.win_conditions, ["Vertical".to_owned()]);
+        assert!(matches!(
+            sent_to(&server.receive(0, "ACCEPT 0 Ada"), 0)[..],
+            [ServerMessage::Error(_)]
+        ));
+
+        let actions = server.receive(1, "ACCEPT 0 Grace");
+        assert_eq!(
+            sent_to(&actions, 0)[0],
+            ServerMessage::Start {
+                side: Player::One,
+                columns: 9,
+                rows: 6,
+                opponent: "Grace".to_owned()
+            }
+        );
+        assert_eq!(
+            sent_to(&server.receive(2, "ACCEPT 0 Alan"), 2),
+            [ServerMessage::Error("There is no open game 0.".to_owned())]
+        );
+        // The table keeps the open game's number, and only a vertical line wins there
+        assert!(matches!(
+            sent_to(&server.receive(2, "GAMES"), 2)[1],
+            ServerMessage::Game { game: 0, .. }
+        ));
+        for (id, column) in [(0, 0), (1, 1), (0, 1), (1, 2), (0, 2), (1, 3), (0, 3)] {
+            let actions = server.receive(id, &format!("MOVE {}", column));
+            assert!(!actions
+                .iter()
+                .any(|action| matches!(action, Action::Finished(_))));
+        }
+
+        assert!(matches!(
+            sent_to(&server.receive(2, "CREATE Alan\tcolumns=30"), 2)[..],
+            [ServerMessage::Error(_)]
+        ));
+        server.receive(2, "CREATE Alan");
+        assert_eq!(
+            sent_to(&server.receive(2, "CANCEL"), 2),
+            [ServerMessage::Cancelled]
+        );
+        assert_eq!(
+            sent_to(&server.receive(2, "LOBBY"), 2),
+            [ServerMessage::Lobby(0)]
+        );
+    }
+
+    #[test]
+    fn matches_players_with_close_ratings_widening_the_gap_as_they_wait() {
+        let mut stats = Stats::default();
+        for _ in 0..5 {
+            stats.add(&PlayedGame {
+                finished_at: 0,
+                names: ["Ada".to_owned(), "Grace".to_owned()],
+                winner: Some(0),
+                moves: vec![],
+            });
+        }
+        let mut server = Server::new(Ruleset::default(), stats, true, DEFAULT_GRACE, None);
+        server.receive(0, "JOIN Ada");
+        assert_eq!(
+            sent_to(&server.receive(1, "JOIN Grace"), 1),
+            [ServerMessage::Waiting]
+        );
+        // Alan is rated between them, so is matched with Ada, who has waited longest
+        let actions = server.receive(2, "JOIN Alan");
+        assert!(matches!(
+            &sent_to(&actions, 0)[0],
+            ServerMessage::Start { side: Player::One, opponent, .. } if opponent == "Alan"
+        ));
+        assert!(sent_to(&actions, 1).is_empty());
+
+        server.receive(3, "JOIN Ada");
+        assert!(server.tick(Instant::now()).is_empty());
+        // After half a minute the gap is wide enough
+        let actions = server.tick(Instant::now() + Duration::from_secs(30));
+        assert!(matches!(
+            &sent_to(&actions, 1)[0],
+            ServerMessage::Start { side: Player::One, opponent, .. } if opponent == "Ada"
+        ));
+    }
+
+    #[test]
+    fn clears_away_games_nobody_accepts_or_plays() {
+        let mut server = Server::new(
+            Ruleset::default(),
+            Stats::default(),
+            true,
+            DEFAULT_GRACE,
+            None,
+        );
+        server.receive(0, "JOIN Ada");
+        server.receive(1, "JOIN Grace");
+        server.receive(0, "MOVE 3");
+        server.receive(2, "CREATE Alan");
+        server.receive(3, "LOCAL Ada\tGrace");
+        assert!(server.tick(Instant::now()).is_empty());
+
+        let actions = server.tick(Instant::now() + Duration::from_secs(16 * 60));
+        assert_eq!(sent_to(&actions, 2), [ServerMessage::Cancelled]);
+        // Grace was to move, so loses the game
+        assert_eq!(
+            sent_to(&actions, 0),
+            [ServerMessage::GameOver(
+                Some(Player::One),
+                Reason::Abandoned
+            )]
+        );
+        assert_eq!(
+            sent_to(&actions, 3),
+            [ServerMessage::GameOver(None, Reason::Abandoned)]
+        );
+        let finished: Vec<&Action> = actions
+            .iter()
+            .filter(|action| matches!(action, Action::Finished(_)))
+            .collect();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(
+            sent_to(&server.receive(1, "MOVE 3"), 1),
+            [ServerMessage::Error("Join a game first.".to_owned())]
+        );
+    }
+
+    // The token the server gave the connection for its game
+    fn token(actions: &[Action], id: usize) -> String {
+        sent_to(actions, id)
+            .into_iter()
+            .find_map(|message| match message {
+                ServerMessage::Session(token, _) => Some(token),
+                _ => None,
+            })
+            .expect("Expected a session token")
+    }
+
+    #[test]
+    fn dropped_players_can_resume_their_game() {
+        let mut server = Server::new(
+            Ruleset::default(),
+            Stats::default(),
+            true,
+            DEFAULT_GRACE,
+            None,
+        );
+        server.receive(0, "JOIN Ada");
+        let actions = server.receive(1, "JOIN Grace");
+        let (ada, grace) = (token(&actions, 0), token(&actions, 1));
+        assert_ne!(ada, grace);
+        server.receive(0, "MOVE 3");
+        server.receive(2, "WATCH 0");
+
+        let actions = server.disconnect(0);
+        assert_eq!(
+            sent_to(&actions, 1),
+            [ServerMessage::Away(Player::One, DEFAULT_GRACE)]
+        );
+        assert_eq!(
+            sent_to(&actions, 2),
+            [ServerMessage::Away(Player::One, DEFAULT_GRACE)]
+        );
+        // The game carries on while Ada's away
+        assert!(sent_to(&server.receive(1, "MOVE 4"), 0).is_empty());
+        assert_eq!(
+            sent_to(&server.receive(3, "RESUME nonsense"), 3),
+            [ServerMessage::Error(
+                "There's no game to resume with that token. It may have finished.".to_owned()
+            )]
+        );
+
+        let actions = server.receive(3, &format!("RESUME {}", ada));
+        let to_ada = sent_to(&actions, 3);
+        assert_eq!(
+            to_ada[0],
+            ServerMessage::Resumed {
+                game: 0,
+                side: Player::One,
+                columns: 7,
+                rows: 6,
+                names: ["Ada".to_owned(), "Grace".to_owned()]
+            }
+        );
+        assert_eq!(to_ada[1], ServerMessage::History(vec![3, 4]));
+        assert_eq!(to_ada[3], ServerMessage::Turn(Player::One));
+        assert_eq!(sent_to(&actions, 1), [ServerMessage::Back(Player::One)]);
+        assert_eq!(
+            sent_to(&server.receive(3, "MOVE 3"), 1)[0],
+            ServerMessage::Moved(Player::One, 3)
+        );
+
+        // Resuming from a new connection before the old one is noticed as dropped replaces it
+        let actions = server.receive(4, &format!("RESUME {}", grace));
+        assert!(actions.contains(&Action::Close(1)));
+        assert_eq!(sent_to(&actions, 3), [ServerMessage::Back(Player::Two)]);
+        assert!(server.disconnect(1).is_empty());
+        assert_eq!(
+            sent_to(&server.receive(4, "MOVE 4"), 3)[0],
+            ServerMessage::Moved(Player::Two, 4)
+        );
+    }
+
+    #[test]
+    fn players_who_dont_come_back_in_time_lose() {
+        let mut server = Server::new(
+            Ruleset::default(),
+            Stats::default(),
+            true,
+            DEFAULT_GRACE,
+            None,
+        );
+        server.receive(0, "JOIN Ada");
+        let actions = server.receive(1, "JOIN Grace");
+        let grace = token(&actions, 1);
+        server.disconnect(1);
+        assert!(server.tick(Instant::now()).is_empty());
+
+        let actions = server.tick(Instant::now() + DEFAULT_GRACE);
+        assert_eq!(
+            sent_to(&actions, 0),
+            [ServerMessage::GameOver(
+                Some(Player::One),
+                Reason::Disconnected
+            )]
+        );
+        assert!(matches!(actions.last(), Some(Action::Finished(_))));
+        assert!(matches!(
+            sent_to(&server.receive(2, &format!("RESUME {}", grace)), 2)[..],
+            [ServerMessage::Error(_)]
+        ));
+        // Quitting isn't a dropped connection, so it loses straight away
+        server.receive(0, "JOIN Ada");
+        server.receive(1, "JOIN Grace");
+        assert!(
+            sent_to(&server.receive(1, "QUIT"), 0).contains(&ServerMessage::GameOver(
+                Some(Player::One),
+                Reason::Disconnected
+            ))
+        );
+    }
+
+    #[test]
+    fn refuses_games_nobody_could_win_without_stopping() {
+        let mut server = Server::new(
+            Ruleset::default(),
+            Stats::default(),
+            true,
+            DEFAULT_GRACE,
+            None,
+        );
+        assert!(matches!(
+            sent_to(
+                &server.receive(0, "CREATE eve\tconnect=18446744073709551615"),
+                0
+            )[..],
+            [ServerMessage::Error(_)]
+        ));
+        assert_eq!(
+            sent_to(&server.receive(0, "CREATE eve\tconnect=5"), 0),
+            [ServerMessage::Opened(0)]
+        );
+        server.receive(1, "ACCEPT 0 Grace");
+        assert_eq!(
+            sent_to(&server.receive(0, "MOVE 3"), 1)[0],
+            ServerMessage::Moved(Player::One, 3)
+        );
+    }
+
+    #[test]
+    fn players_lose_when_their_clock_runs_out() {
+        let control = TimeControl::parse("1+5").unwrap();
+        let mut server = Server::new(
+            Ruleset::default(),
+            Stats::default(),
+            true,
+            DEFAULT_GRACE,
+            Some(control),
+        );
+        server.receive(0, "JOIN Ada");
+        let sent = sent_to(&server.receive(1, "JOIN Grace"), 0);
+        assert_eq!(
+            sent[sent.len() - 2..],
+            [
+                ServerMessage::Clock([Duration::from_secs(60); 2]),
+                ServerMessage::Turn(Player::One)
+            ]
+        );
+
+        // The increment is added after the move, and the opponent's time starts
+        let sent = sent_to(&server.receive(0, "MOVE 3"), 1);
+        let Some(ServerMessage::Clock([one, two])) = sent.iter().rev().nth(1).cloned() else {
+            panic!("Expected the clocks before the turn");
+        };
+        assert!(one > Duration::from_secs(60));
+        assert_eq!(two, Duration::from_secs(60));
+
+        assert!(server
+            .tick(Instant::now() + Duration::from_secs(59))
+            .is_empty());
+        let actions = server.tick(Instant::now() + Duration::from_secs(61));
+        assert_eq!(
+            sent_to(&actions, 0),
+            [ServerMessage::GameOver(Some(Player::One), Reason::Timeout)]
+        );
+        assert!(matches!(actions.last(), Some(Action::Finished(_))));
+    }
+
+    #[test]
+    fn ticks_even_while_lines_keep_arriving() {
+        let control = TimeControl::parse("1+0").unwrap();
+        let mut server = Server::new(
+            Ruleset::default(),
+            Stats::default(),
+            true,
+            DEFAULT_GRACE,
+            Some(control),
+        );
+        server.receive(0, "JOIN Ada");
+        server.receive(1, "JOIN Grace");
+
+        // Grace asks for the board every half a second, far more often than the server ticks
+        let start = Instant::now();
+        let mut last_tick = start;
+        let mut actions = vec![];
+        for step in 1..=130 {
+            let now = start + TICK / 2 * step;
+            actions.extend(server.receive(1, "BOARD"));
+            actions.extend(tick_if_due(&mut server, &mut last_tick, now));
+        }
+        assert!(sent_to(&actions, 0)
+            .contains(&ServerMessage::GameOver(Some(Player::Two), Reason::Timeout)));
+    }
+
+    #[test]
+    fn drops_terminals_sending_lines_that_are_too_long() {
+        let mut lines = "JOIN Ada\r\nMOVE 3\nQUIT".as_bytes();
+        assert_eq!(read_line(&mut lines).unwrap().as_deref(), Some("JOIN Ada"));
+        assert_eq!(read_line(&mut lines).unwrap().as_deref(), Some("MOVE 3"));
+        assert_eq!(read_line(&mut lines).unwrap().as_deref(), Some("QUIT"));
+        assert_eq!(read_line(&mut lines).unwrap(), None);
+
+        let longest = format!("{}\n", "A".repeat(MAX_LINE_LENGTH));
+        assert!(read_line(&mut longest.as_bytes()).unwrap().is_some());
+        let endless = "A".repeat(MAX_LINE_LENGTH + 1);
+        assert!(read_line(&mut endless.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+use std::fmt::Write;
+
+use super::game::{Game, Player};
+
+pub struct SvgColours {
+    pub background: String,
+    pub board: String,
+    pub empty: String,
+    pub player_one: String,
+    pub player_two: String,
+    pub highlight: String,
+    pub text: String,
+}
+
+impl Default for SvgColours {
+    fn default() -> Self {
+        Self {
+            background: "#ffffff".to_owned(),
+            board: "#1f4fbf".to_owned(),
+            empty: "#f4f4f4".to_owned(),
+            player_one: "#d62828".to_owned(),
+            player_two: "#f7c600".to_owned(),
+            highlight: "#2bd96b".to_owned(),
+            text: "#222222".to_owned(),
+        }
+    }
+}
+
+pub struct SvgOptions {
+    // The width and height of a single cell, in pixels
+    pub cell_size: usize,
+    pub column_labels: bool,
+    pub highlight_winning_line: bool,
+    pub move_numbers: bool,
+    pub colours: SvgColours,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            cell_size: 60,
+            column_labels: true,
+            highlight_winning_line: true,
+            move_numbers: false,
+            colours: SvgColours::default(),
+        }
+    }
+}
+
+const MARGIN: usize = 10;
+
+// Renders the game as a standalone SVG document.
+// The board is drawn the same way up as the `Display` impl - row 0 at the bottom.
+pub fn render(game: &Game, options: &SvgOptions) -> String {
+    let board = game.board();
+    let columns = board.len();
+    let rows = board.first().map_or(0, |column| column.len());
+    let cell = options.cell_size;
+    let label_height = if options.column_labels { cell / 2 } else { 0 };
+    let width = columns * cell + 2 * MARGIN;
+    let height = rows * cell + 2 * MARGIN + label_height;
+    let colours = &options.colours;
+
+    let centre = |column: usize, row: usize| {
+        (
+            MARGIN + column * cell + cell / 2,
+            MARGIN + (rows - 1 - row) * cell + cell / 2,
+        )
+    };
+
+    let mut output = String::new();
+    output += "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n";
+    writeln!(
+        output,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">"
+    )
+    .unwrap();
+    writeln!(
+        output,
+        "<rect x=\"0\" y=\"0\" width=\"{width}\" height=\"{height}\" fill=\"{}\"/>",
+        escape(&colours.background)
+    )
+    .unwrap();
+    writeln!(
+        output,
+        "<rect x=\"{MARGIN}\" y=\"{MARGIN}\" width=\"{}\" height=\"{}\" rx=\"{}\" fill=\"{}\"/>",
+        columns * cell,
+        rows * cell,
+        cell / 6,
+        escape(&colours.board)
+    )
+    .unwrap();
+
+    let radius = cell * 2 / 5;
+    for (column, cells) in board.iter().enumerate() {
+        for (row, player) in cells.iter().enumerate() {
+            let (x, y) = centre(column, row);
+            let fill = match player {
+                Player::None => &colours.empty,
+                Player::One => &colours.player_one,
+                Player::Two => &colours.player_two,
+            };
+            writeln!(
+                output,
+                "<circle class=\"disc\" cx=\"{x}\" cy=\"{y}\" r=\"{radius}\" fill=\"{}\"/>",
+                escape(fill)
+            )
+            .unwrap();
+        }
+    }
+
+    if options.highlight_winning_line {
+        if let Some(line) = game.winning_line() {
+            let stroke_width = (cell / 12).max(1);
+            for &(column, row) in &line {
+                let (x, y) = centre(column, row);
+                writeln!(
+                    output,
+                    "<circle class=\"highlight\" cx=\"{x}\" cy=\"{y}\" r=\"{radius}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{stroke_width}\"/>",
+                    escape(&colours.highlight)
+                )
+                .unwrap();
+            }
+            let (x1, y1) = centre(line[0].0, line[0].1);
+            let (x2, y2) = centre(line[line.len() - 1].0, line[line.len() - 1].1);
+            writeln!(
+                output,
+                "<line class=\"highlight\" x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"{}\" stroke-width=\"{stroke_width}\" stroke-linecap=\"round\"/>",
+                escape(&colours.highlight)
+            )
+            .unwrap();
+        }
+    }
+
+    if options.move_numbers {
+        let mut heights = vec![0; columns];
+        for (index, &column) in game.moves().iter().enumerate() {
+            let (x, y) = centre(column, heights[column]);
+            heights[column] += 1;
+            writeln!(
+                output,
+                "<text class=\"move-number\" x=\"{x}\" y=\"{y}\" font-family=\"sans-serif\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{}\">{}</text>",
+                cell / 3,
+                escape(&colours.text),
+                index + 1
+            )
+            .unwrap();
+        }
+    }
+
+    if options.column_labels {
+        let y = MARGIN + rows * cell + label_height / 2 + MARGIN / 2;
+        for column in 0..columns {
+            let x = MARGIN + column * cell + cell / 2;
+            writeln!(
+                output,
+                "<text class=\"label\" x=\"{x}\" y=\"{y}\" font-family=\"sans-serif\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{}\">{column}</text>",
+                cell / 3,
+                escape(&colours.text)
+            )
+            .unwrap();
+        }
+    }
+
+    output += "</svg>\n";
+    output
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remove_consts::{
+        game::{Game, DEFAULT_COLUMNS, DEFAULT_ROWS},
+        win_conditions::default_win_conditions,
+    };
+
+    use super::{render, SvgOptions};
+
+    #[test]
+    fn draws_a_disc_for_every_cell_on_the_board() {
+        let win_conditions = default_win_conditions();
+        let game = Game::initialise(DEFAULT_COLUMNS, DEFAULT_ROWS, &win_conditions);
+        let svg = render(&game, &SvgOptions::default());
+        assert!(svg.starts_with("<?xml"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("class=\"disc\"").count(), 42);
+    }
+
+    #[test]
+    fn colours_discs_by_player() {
+        let win_conditions = default_win_conditions();
+        let mut game = Game::initialise(DEFAULT_COLUMNS, DEFAULT_ROWS, &win_conditions);
+        game = game.play_on_column(3).unwrap();
+        game = game.play_on_column(3).unwrap();
+        let mut options = SvgOptions::default();
+        options.colours.player_one = "red".to_owned();
+        options.colours.player_two = "yellow".to_owned();
+        let svg = render(&game, &options);
+        assert_eq!(svg.matches("fill=\"red\"").count(), 1);
+        assert_eq!(svg.matches("fill=\"yellow\"").count(), 1);
+    }
+
+    #[test]
+    fn labels_every_column_on_wide_boards() {
+        let win_conditions = default_win_conditions();
+        let game = Game::initialise(12, 3, &win_conditions);
+        let svg = render(&game, &SvgOptions::default());
+        assert_eq!(svg.matches("class=\"label\"").count(), 12);
+        assert!(svg.contains(">11</text>"));
+
+        let options = SvgOptions {
+            column_labels: false,
+            ..Default::default()
+        };
+        let svg = render(&game, &options);
+        assert_eq!(svg.matches("class=\"label\"").count(), 0);
+    }
+
+    /*
+    o . . .
+    o . . .
+    o . . .
+    x x x x
+    */
+    #[test]
+    fn highlights_the_winning_line() {
+        let win_conditions = default_win_conditions();
+        let mut game = Game::initialise(4, 4, &win_conditions);
+        for column in [0, 0, 1, 0, 2, 0, 3] {
+            game = game.play_on_column(column).unwrap();
+        }
+        let svg = render(&game, &SvgOptions::default());
+        assert_eq!(svg.matches("<circle class=\"highlight\"").count(), 4);
+        assert_eq!(svg.matches("<line class=\"highlight\"").count(), 1);
+
+        let options = SvgOptions {
+            highlight_winning_line: false,
+            ..Default::default()
+        };
+        let svg = render(&game, &options);
+        assert!(!svg.contains("class=\"highlight\""));
+    }
+
+    #[test]
+    fn numbers_the_discs_in_the_order_they_were_played() {
+        let win_conditions = default_win_conditions();
+        let mut game = Game::initialise(DEFAULT_COLUMNS, DEFAULT_ROWS, &win_conditions);
+        game = game.play_on_column(0).unwrap();
+        game = game.play_on_column(0).unwrap();
+        let options = SvgOptions {
+            move_numbers: true,
+            cell_size: 60,
+            ..Default::default()
+        };
+        let svg = render(&game, &options);
+        assert!(svg.contains("x=\"40\" y=\"340\"") && svg.contains(">1</text>"));
+        assert!(svg.contains("x=\"40\" y=\"280\"") && svg.contains(">2</text>"));
+    }
+
+    #[test]
+    fn escapes_colours_so_the_document_stays_well_formed() {
+        let win_conditions = default_win_conditions();
+        let game = Game::initialise(1, 1, &win_conditions);
+        let mut options = SvgOptions::default();
+        options.colours.board = "\"><script>".to_owned();
+        let svg = render(&game, &options);
+        assert!(!svg.contains("<script>"));
+    }
+}
@@ -0,0 +1,234 @@
+use std::{
+    io::{self, Read, Write},
+    panic,
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, Once,
+    },
+};
+
+const ESCAPE: u8 = 0x1b;
+const CONTROL_C: u8 = 0x03;
+
+// Switch to the alternate screen, hide the cursor and report mouse clicks in SGR form
+const ENTER_SEQUENCE: &str = "\x1b[?1049h\x1b[?25l\x1b[?1000h\x1b[?1006h\x1b[2J";
+const LEAVE_SEQUENCE: &str = "\x1b[?1006l\x1b[?1000l\x1b[?25h\x1b[?1049l";
+
+// The terminal settings to put back, shared with the panic hook
+static SAVED_SETTINGS: Mutex<String> = Mutex::new(String::new());
+static RAW_MODE: AtomicBool = AtomicBool::new(false);
+static PANIC_HOOK: Once = Once::new();
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Key {
+    Left,
+    Right,
+    // Enter, space or the down arrow
+    Drop,
+    NewGame,
+    Quit,
+    // Zero indexed cell the left mouse button was pressed on
+    Click { x: usize, y: usize },
+}
+
+/* Puts the terminal in raw mode on the alternate screen until it's dropped.
+Raw mode is switched with `stty`, which keeps the crate free of dependencies on platform bindings.
+Reads time out after a tenth of a second so the caller can animate and watch for resizes between key presses.
+The terminal is put back on drop, and by a panic hook so that a panic message isn't lost on the alternate screen.
+*/
+pub struct Terminal;
+
+impl Terminal {
+    pub fn enter() -> io::Result<Self> {
+        let saved = stty(&["-g"])
+            .map_err(|_| io::Error::other("The full-screen mode needs an interactive terminal."))?;
+        *SAVED_SETTINGS.lock().unwrap_or_else(|x| x.into_inner()) = saved.trim().to_owned();
+        stty(&["raw", "-echo", "min", "0", "time", "1"])?;
+        RAW_MODE.store(true, Ordering::SeqCst);
+        PANIC_HOOK.call_once(|| {
+            let previous_hook = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                restore();
+                previous_hook(info);
+            }));
+        });
+        let mut stdout = io::stdout();
+        stdout.write_all(ENTER_SEQUENCE.as_bytes())?;
+        stdout.flush()?;
+        Ok(Terminal)
+    }
+
+    // Whatever bytes arrive within a tenth of a second, which may be none
+    pub fn read(&mut self) -> io::Result<Vec<u8>> {
+        let mut buffer = [0; 64];
+        let count = io::stdin().read(&mut buffer)?;
+        Ok(buffer[..count].to_vec())
+    }
+
+    // Replaces the whole screen with the lines
+    pub fn draw(&mut self, lines: &[String], clear: bool) -> io::Result<()> {
+        let mut output = String::new();
+        if clear {
+            output += "\x1b[2J";
+        }
+        output += "\x1b[H";
+        output += &lines.join("\r\n");
+        let mut stdout = io::stdout();
+        stdout.write_all(output.as_bytes())?;
+        stdout.flush()
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+// The terminal's size as (columns, rows)
+pub fn size() -> Option<(usize, usize)> {
+    parse_size(&stty(&["size"]).ok()?)
+}
+
+// Pulls every complete key press out of the bytes, leaving any partial escape sequence for the next read
+pub fn parse_keys(bytes: &mut Vec<u8>) -> Vec<Key> {
+    let mut keys = vec![];
+    let mut index = 0;
+    while index < bytes.len() {
+        let (key, length) = match bytes[index] {
+            ESCAPE => match parse_escape(&bytes[index..]) {
+                Some(parsed) => parsed,
+                None => break,
+            },
+            b'\r' | b'\n' | b' ' => (Some(Key::Drop), 1),
+            b'a' | b'h' => (Some(Key::Left), 1),
+            b'd' | b'l' => (Some(Key::Right), 1),
+            b'n' => (Some(Key::NewGame), 1),
+            b'q' | CONTROL_C => (Some(Key::Quit), 1),
+            _ => (None, 1),
+        };
+        keys.extend(key);
+        index += length;
+    }
+    bytes.drain(..index);
+    keys
+}
+
+// Parses an escape sequence at the start of the bytes, giving the key and its length, or nothing if it's incomplete
+fn parse_escape(bytes: &[u8]) -> Option<(Option<Key>, usize)> {
+    match bytes.get(1) {
+        None => return None,
+        Some(b'[') | Some(b'O') => (),
+        // A lone escape key press
+        Some(_) => return Some((None, 1)),
+    }
+    if bytes.get(2) == Some(&b'<') {
+        // SGR mouse reports look like ESC [ < button ; x ; y M, with M for press and m for release
+        let end = bytes.iter().position(|x| *x == b'M' || *x == b'm')?;
+        let fields: Vec<usize> = String::from_utf8_lossy(&bytes[3..end])
+            .split(';')
+            .filter_map(|x| x.parse().ok())
+            .collect();
+        let key = match (&fields[..], bytes[end]) {
+            (&[0, x, y], b'M') if x > 0 && y > 0 => Some(Key::Click { x: x - 1, y: y - 1 }),
+            _ => None,
+        };
+        return Some((key, end + 1));
+    }
+    // Other sequences end with the first byte in the range '@' to '~'
+    let end = bytes[2..].iter().position(|x| (b'@'..=b'~').contains(x))? + 2;
+    let key = match bytes[end] {
+        b'D' => Some(Key::Left),
+        b'C' => Some(Key::Right),
+        b'B' => Some(Key::Drop),
+        _ => None,
+    };
+    Some((key, end + 1))
+}
+
+fn parse_size(output: &str) -> Option<(usize, usize)> {
+    let mut numbers = output.split_whitespace().map(|x| x.parse::<usize>());
+    let rows = numbers.next()?.ok()?;
+    let columns = numbers.next()?.ok()?;
+    Some((columns, rows))
+}
+
+fn restore() {
+    if !RAW_MODE.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    let mut stdout = io::stdout();
+    let _ = stdout.write_all(LEAVE_SEQUENCE.as_bytes());
+    let _ = stdout.flush();
+    let saved = SAVED_SETTINGS
+        .lock()
+        .unwrap_or_else(|x| x.into_inner())
+        .clone();
+    if stty(&[&saved]).is_err() {
+        let _ = stty(&["sane"]);
+    }
+}
+
+// Runs stty on the terminal attached to stdin
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_keys, parse_size, Key};
+
+    #[test]
+    fn parses_arrows_letters_and_clicks() {
+        let mut bytes = b"\x1b[D\x1b[Cq \x1bOD\x1b[<0;12;5M\x1b[<0;12;5m\x1b[<2;1;1Mn".to_vec();
+        assert_eq!(
+            parse_keys(&mut bytes),
+            vec![
+                Key::Left,
+                Key::Right,
+                Key::Quit,
+                Key::Drop,
+                Key::Left,
+                Key::Click { x: 11, y: 4 },
+                Key::NewGame
+            ]
+        );
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn keeps_partial_escape_sequences_for_the_next_read() {
+        let mut bytes = b"l\x1b[".to_vec();
+        assert_eq!(parse_keys(&mut bytes), vec![Key::Right]);
+        assert_eq!(bytes, b"\x1b[");
+        bytes.extend(b"D");
+        assert_eq!(parse_keys(&mut bytes), vec![Key::Left]);
+        assert!(bytes.is_empty());
+
+        let mut bytes = b"\x1b[<0;3".to_vec();
+        assert_eq!(parse_keys(&mut bytes), vec![]);
+        assert_eq!(bytes.len(), 6);
+    }
+
+    #[test]
+    fn ignores_unknown_keys_and_sequences() {
+        let mut bytes = b"z\x1b[A\x1b[15~\x1bx\r".to_vec();
+        assert_eq!(parse_keys(&mut bytes), vec![Key::Drop]);
+    }
+
+    #[test]
+    fn reads_the_size_reported_by_stty() {
+        assert_eq!(parse_size("24 80\n"), Some((80, 24)));
+        assert_eq!(parse_size(""), None);
+    }
+}
@@ -0,0 +1,276 @@
+use crate::submission::{
+    self,
+    game::{DEFAULT_COLUMNS, DEFAULT_ROWS},
+    solver::{Solver, Verdict},
+};
+
+use super::{
+    archive::{GameRecord, Outcome},
+    game::{Game, GameStatus, Player},
+    random::Random,
+    ruleset::Ruleset,
+};
+
+pub const DEFAULT_TRAINING_PATH: &str = "training.csv";
+
+pub const HEADER: &str =
+    "game,columns,rows,board,side_to_move,move_number,move_played,result,solver";
+
+/* Labels positions with the solver's verdict for the side to move.
+The solver only handles the standard board and rules, and early positions take a long time to solve,
+so positions before `from_move` are left unlabelled.
+*/
+pub struct SolverLabels {
+    solver: Solver<DEFAULT_COLUMNS, DEFAULT_ROWS>,
+    from_move: usize,
+}
+
+impl SolverLabels {
+    pub fn new(from_move: usize) -> Self {
+        SolverLabels {
+            solver: Solver::new(),
+            from_move,
+        }
+    }
+
+    // Archives take any moves that fit on the board, so a position can still be one the solver can't reach
+    fn label(
+        &mut self,
+        record: &GameRecord,
+        moves_played: usize,
+        board: &[Vec<Player>],
+    ) -> Result<String, String> {
+        if moves_played < self.from_move || !record.has_standard_rules() {
+            return Ok(String::new());
+        }
+        let mut cells = [[submission::game::Player::None; DEFAULT_ROWS]; DEFAULT_COLUMNS];
+        for (cells, column) in cells.iter_mut().zip(board) {
+            for (cell, player) in cells.iter_mut().zip(column) {
+                *cell = match player {
+                    Player::One => submission::game::Player::One,
+                    Player::Two => submission::game::Player::Two,
+                    Player::None => submission::game::Player::None,
+                };
+            }
+        }
+        let game = submission::game::Game::from_board(cells)?;
+        Ok(match self.solver.verdict(&game) {
+            Verdict::Win => "win",
+            Verdict::Loss => "loss",
+            Verdict::Draw => "draw",
+        }
+        .to_owned())
+    }
+}
+
+/* Writes a row for every position in which a move was played, in the format of `HEADER`.
+Boards are encoded a column at a time from the left, each column going up from the bottom,
+with `x` for Player One, `o` for Player Two and `b` for blank. Columns are separated by `/`.
+The result is the final result of the whole game, and the solver label is for the side to move.
+Unfinished games have no result, so they are skipped.
+*/
+pub fn export(
+    records: &[GameRecord],
+    mut labels: Option<&mut SolverLabels>,
+) -> Result<String, String> {
+    let mut csv = format!("{}\n", HEADER);
+    for (number, record) in records.iter().enumerate() {
+        if record.outcome == Outcome::Unfinished {
+            continue;
+        }
+        let positions = record.positions()?;
+        for (index, (board, column)) in positions.iter().zip(&record.moves).enumerate() {
+            let side_to_move = match index % 2 {
+                0 => "one",
+                _ => "two",
+            };
+            let label = match labels.as_deref_mut() {
+                Some(labels) => labels.label(record, index, board).map_err(|error| {
+                    format!(
+                        "Move {} of game {} could not be labelled: {}",
+                        index + 1,
+                        number + 1,
+                        error
+                    )
+                })?,
+                None => String::new(),
+            };
+            csv += &format!(
+                "{},{},{},{},{},{},{},{},{}\n",
+                number + 1,
+                record.columns,
+                record.rows,
+                encode(board),
+                side_to_move,
+                index + 1,
+                column,
+                record.outcome,
+                label
+            );
+        }
+    }
+    Ok(csv)
+}
+
+pub fn encode(board: &[Vec<Player>]) -> String {
+    let columns: Vec<String> = board
+        .iter()
+        .map(|column| {
+            column
+                .iter()
+                .map(|cell| match cell {
+                    Player::One => 'x',
+                    Player::Two => 'o',
+                    Player::None => 'b',
+                })
+                .collect()
+        })
+        .collect();
+    columns.join("/")
+}
+
+// Reads a board written by encode
+pub fn decode(encoded: &str) -> Result<Vec<Vec<Player>>, String> {
+    let board: Vec<Vec<Player>> = encoded
+        .split('/')
+        .map(|column| {
+            column
+                .chars()
+                .map(|cell| match cell {
+                    'x' => Ok(Player::One),
+                    'o' => Ok(Player::Two),
+                    'b' => Ok(Player::None),
+                    other => Err(format!("<{}> is not a cell in an encoded board.", other)),
+                })
+                .collect()
+        })
+        .collect::<Result<_, _>>()?;
+    let rows = board.first().map_or(0, Vec::len);
+    match rows > 0 && board.iter().all(|column| column.len() == rows) {
+        true => Ok(board),
+        false => Err(format!(
+            "The board <{}> doesn't have columns of the same height.",
+            encoded
+        )),
+    }
+}
+
+// Plays games of random moves under the ruleset. The same seed always gives the same games.
+pub fn self_play(ruleset: &Ruleset, count: usize, seed: u64) -> Vec<GameRecord> {
+    let win_conditions = ruleset.win_conditions();
+    let mut random = Random::new(seed);
+    (0..count)
+        .map(|_| {
+            let mut game = Game::initialise(ruleset.columns, ruleset.rows, &win_conditions)
+                .with_variants(&ruleset.variants);
+            while game.status == GameStatus::Started {
+                let open_columns: Vec<usize> = (0..ruleset.columns)
+                    .filter(|&column| game.board()[column].contains(&Player::None))
+                    .collect();
+                let column = open_columns[random.below(open_columns.len())];
+                game = game
+                    .play_on_column(column)
+                    .expect("Only open columns are played");
+            }
+            GameRecord::from_game(&game)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remove_consts::{
+        archive::{GameRecord, Outcome},
+        game::Player,
+        ruleset::Ruleset,
+        win_conditions::default_win_conditions,
+    };
+
+    use super::{decode, encode, export, self_play, SolverLabels, HEADER};
+
+    fn record(columns: usize, rows: usize, moves: &[usize], outcome: Outcome) -> GameRecord {
+        GameRecord {
+            columns,
+            rows,
+            rules: default_win_conditions()
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            moves: moves.to_vec(),
+            outcome,
+            variants: vec![],
+        }
+    }
+
+    #[test]
+    fn encodes_boards_of_any_size_a_column_at_a_time() {
+        let board = vec![
+            vec![Player::One, Player::Two, Player::None],
+            vec![Player::None; 3],
+            vec![Player::Two, Player::None, Player::None],
+            vec![Player::None; 3],
+        ];
+        assert_eq!(encode(&board), "xob/bbb/obb/bbb");
+        assert_eq!(decode("xob/bbb/obb/bbb"), Ok(board));
+        assert!(decode("xob/bb").is_err());
+        assert!(decode("xqb").is_err());
+    }
+
+    #[test]
+    fn writes_a_row_for_every_move_played() {
+        let records = [
+            record(9, 3, &[0, 8, 0], Outcome::Draw),
+            record(7, 6, &[3], Outcome::Unfinished),
+        ];
+        let csv = export(&records, None).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                HEADER,
+                "1,9,3,bbb/bbb/bbb/bbb/bbb/bbb/bbb/bbb/bbb,one,1,0,draw,",
+                "1,9,3,xbb/bbb/bbb/bbb/bbb/bbb/bbb/bbb/bbb,two,2,8,draw,",
+                "1,9,3,xbb/bbb/bbb/bbb/bbb/bbb/bbb/bbb/obb,one,3,0,draw,",
+            ]
+        );
+    }
+
+    #[test]
+    fn labels_standard_positions_from_the_chosen_move() {
+        // Player One stacks three in column 0 and can win straight away from the seventh move
+        let records = [
+            record(7, 6, &[0, 1, 0, 1, 0, 1, 0], Outcome::Won(Player::One)),
+            record(8, 6, &[0, 1, 0, 1, 0, 1, 0], Outcome::Won(Player::One)),
+        ];
+        let mut labels = SolverLabels::new(6);
+        let csv = export(&records, Some(&mut labels)).unwrap();
+        let labelled: Vec<&str> = csv.lines().skip(1).filter(|x| !x.ends_with(',')).collect();
+        assert_eq!(labelled.len(), 1);
+        assert!(labelled[0].ends_with(",one,7,0,one,win"), "{}", labelled[0]);
+
+        // Both sides have four in a row before the last move, which no game can reach
+        let records = [record(
+            7,
+            6,
+            &[0, 1, 0, 1, 0, 1, 0, 1, 2],
+            Outcome::Won(Player::One),
+        )];
+        let error = export(&records, Some(&mut SolverLabels::new(6))).unwrap_err();
+        assert!(error.starts_with("Move 9 of game 1"), "{}", error);
+    }
+
+    #[test]
+    fn self_play_finishes_every_game_and_repeats_with_the_same_seed() {
+        let ruleset = Ruleset {
+            columns: 5,
+            rows: 4,
+            ..Default::default()
+        };
+        let games = self_play(&ruleset, 20, 7);
+        assert_eq!(games.len(), 20);
+        assert!(games.iter().all(|x| x.outcome != Outcome::Unfinished));
+        assert!(games.iter().all(|x| x.positions().is_ok()));
+        assert_eq!(games, self_play(&ruleset, 20, 7));
+        assert_ne!(games, self_play(&ruleset, 20, 8));
+    }
+}
@@ -0,0 +1,98 @@
+use std::{fs, io};
+
+use crate::remove_consts::{
+    archive::{Archive, GameRecord, DEFAULT_ARCHIVE_PATH},
+    ruleset::Ruleset,
+    training::{export, self_play, SolverLabels, DEFAULT_TRAINING_PATH},
+};
+
+// Solving is slow near the start of a game, so by default only positions this deep are labelled
+const DEFAULT_LABEL_FROM_MOVE: usize = 12;
+
+pub fn export_dataset() {
+    let stdin = io::stdin();
+    let input = &mut String::new();
+
+    println!("<<Training Data Export>>");
+    println!(
+        "Would you like to export the games in the archive? Y/n
+Otherwise, random games will be self-played."
+    );
+    input.clear();
+    stdin.read_line(input).expect("Error reading from stdio");
+    let records: Vec<GameRecord> = match input.trim().to_lowercase().as_str() {
+        "n" | "no" => {
+            let ruleset = Ruleset::default();
+            let count = ask_for_number(input, "How many games should be self-played?");
+            let seed = ask_for_number(input, "Which seed should the random moves use?");
+            self_play(&ruleset, count, seed as u64)
+        }
+        _ => match Archive::open(DEFAULT_ARCHIVE_PATH) {
+            Ok(archive) => archive.records().to_vec(),
+            Err(error) => {
+                eprintln!(
+                    "Could not open the archive <{}>: {}",
+                    DEFAULT_ARCHIVE_PATH, error
+                );
+                return;
+            }
+        },
+    };
+
+    println!(
+        "Would you like to label positions with the solver? y/N
+Only the standard board and rules can be labelled, and only from move {} onwards.",
+        DEFAULT_LABEL_FROM_MOVE + 1
+    );
+    input.clear();
+    stdin.read_line(input).expect("Error reading from stdio");
+    let mut labels = match input.trim().to_lowercase().as_str() {
+        "y" | "yes" => Some(SolverLabels::new(DEFAULT_LABEL_FROM_MOVE)),
+        _ => None,
+    };
+
+    println!(
+        "Where should the dataset be saved? Leave it blank for <{}>.",
+        DEFAULT_TRAINING_PATH
+    );
+    input.clear();
+    stdin.read_line(input).expect("Error reading from stdio");
+    let path = match input.trim() {
+        "" => DEFAULT_TRAINING_PATH,
+        path => path,
+    };
+
+    let csv = match export(&records, labels.as_mut()) {
+        Ok(csv) => csv,
+        Err(message) => {
+            eprintln!("{}", message);
+            return;
+        }
+    };
+    match fs::write(path, &csv) {
+        Ok(_) => println!(
+            "Saved {} positions from {} games to <{}>.\n",
+            csv.lines().count() - 1,
+            records.len(),
+            path
+        ),
+        Err(error) => eprintln!("Could not save to <{}>: {}", path, error),
+    }
+}
+
+fn ask_for_number(input: &mut String, question: &str) -> usize {
+    loop {
+        println!("{}", question);
+        input.clear();
+        io::stdin()
+            .read_line(input)
+            .expect("Error reading from stdio");
+        match input.trim().parse::<usize>() {
+            Ok(number) => return number,
+            Err(_) => println!(
+                "The input <{}> could not be parsed as a usize. Please try again.",
+                input.trim()
+            ),
+        }
+    }
+}
@@ -0,0 +1,649 @@
+use std::{
+    io, thread,
+    time::{Duration, Instant},
+};
+
+use crate::{labels::ColumnLabels, locale, scoreboard::DEFAULT_NAMES, theme::Theme};
+
+use super::{
+    ai::{PlayerType, Players},
+    clock::{self, format_time, Clock, TimeControl},
+    game::{Game, GameStatus, Player},
+    ruleset::Ruleset,
+    terminal::{self, parse_keys, Key, Terminal},
+};
+
+const FALL_FRAME: Duration = Duration::from_millis(40);
+const RESIZE_CHECK: Duration = Duration::from_millis(250);
+const FALLBACK_SIZE: (usize, usize) = (80, 24);
+
+const LEFT_PANEL_WIDTH: usize = 30;
+const RIGHT_PANEL_WIDTH: usize = 16;
+const GAP: usize = 2;
+const TOP: usize = 2;
+// Each cell is drawn three characters wide
+const CELL_WIDTH: usize = 3;
+
+// Everything shown on the screen
+pub struct View<'a> {
+    pub board: &'a [Vec<Player>],
+    pub cursor: usize,
+    // A disc part way through its fall, as (column, row, player)
+    pub falling: Option<(usize, usize, Player)>,
+    pub status: Vec<String>,
+    pub settings: Vec<String>,
+    pub moves: &'a [usize],
+    pub theme: &'a Theme,
+    pub labels: ColumnLabels,
+}
+
+// Where the board was drawn, so that clicks can be matched to columns
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Layout {
+    pub board_left: usize,
+    pub board_top: usize,
+    pub columns: usize,
+    pub rows: usize,
+}
+
+impl Layout {
+    // The column under a click anywhere above, on or below the board
+    pub fn column_at(&self, x: usize, y: usize) -> Option<usize> {
+        let inside_x = x.checked_sub(self.board_left + 1)?;
+        let column = inside_x / CELL_WIDTH;
+        let height = self.rows + 4;
+        match column < self.columns && y >= self.board_top && y < self.board_top + height {
+            true => Some(column),
+            false => None,
+        }
+    }
+}
+
+/* Lays out the screen as exactly `height` lines of `width` characters.
+The board sits in the middle, with status and settings panels to its left and the move list to its right.
+Panels are left out, right first, when the terminal is too narrow for them.
+Gives no layout when the board itself doesn't fit.
+*/
+pub fn render(view: &View, width: usize, height: usize) -> (Vec<String>, Option<Layout>) {
+    let mut screen = Screen::new(width, height);
+    let columns = view.board.len();
+    let rows = view.board.first().map_or(0, |x| x.len());
+    let board_width = columns * CELL_WIDTH + 2;
+    let board_height = rows + 4;
+
+    if width < board_width || height < TOP + board_height {
+        screen.put(
+            0,
+            0,
+            &locale::text(
+                "tui.too_small",
+                &[("columns", &board_width), ("rows", &(TOP + board_height))],
+            ),
+        );
+        screen.put(0, 1, &locale::text("tui.quit", &[]));
+        return (screen.lines(), None);
+    }
+
+    let show_left = width >= LEFT_PANEL_WIDTH + GAP + board_width;
+    let show_right = width >= LEFT_PANEL_WIDTH + RIGHT_PANEL_WIDTH + GAP * 2 + board_width;
+    let board_left = match show_left {
+        true => LEFT_PANEL_WIDTH + GAP,
+        false => 0,
+    };
+    screen.put(0, 0, &format!(" {} ", locale::text("tui.title", &[])));
+
+    let layout = Layout {
+        board_left,
+        board_top: TOP,
+        columns,
+        rows,
+    };
+    draw_board(&mut screen, view, &layout);
+
+    if show_left {
+        let status_height = view.status.len() + 2;
+        screen.draw_box(
+            0,
+            TOP,
+            LEFT_PANEL_WIDTH,
+            status_height,
+            &locale::text("tui.status", &[]),
+            &view.status,
+        );
+        screen.draw_box(
+            0,
+            TOP + status_height,
+            LEFT_PANEL_WIDTH,
+            view.settings.len() + 2,
+            &locale::text("tui.settings", &[]),
+            &view.settings,
+        );
+    } else {
+        // Without the panels the status goes under the board, as far as it fits
+        for (index, line) in view.status.iter().enumerate() {
+            screen.put(0, TOP + board_height + index, line);
+        }
+    }
+
+    if show_right {
+        let panel_height = height - TOP;
+        let visible = panel_height.saturating_sub(2);
+        let moves: Vec<String> = view
+            .moves
+            .iter()
+            .enumerate()
+            .map(|(index, column)| {
+                let disc = match index % 2 {
+                    0 => view.theme.one.glyph,
+                    _ => view.theme.two.glyph,
+                };
+                format!("{:>3}. {} {}", index + 1, disc, view.labels.label(*column))
+            })
+            .collect();
+        let shown = &moves[moves.len().saturating_sub(visible)..];
+        screen.draw_box(
+            board_left + board_width + GAP,
+            TOP,
+            RIGHT_PANEL_WIDTH,
+            panel_height,
+            &locale::text("tui.moves", &[]),
+            shown,
+        );
+    }
+    (screen.lines(), Some(layout))
+}
+
+fn draw_board(screen: &mut Screen, view: &View, layout: &Layout) {
+    let left = layout.board_left;
+    let top = layout.board_top;
+    let inner_width = layout.columns * CELL_WIDTH;
+    screen.put(left + 1 + view.cursor * CELL_WIDTH + 1, top, "▼");
+    screen.put(left, top + 1, &format!("┌{}┐", "─".repeat(inner_width)));
+    for row in 0..layout.rows {
+        let y = top + 2 + (layout.rows - 1 - row);
+        screen.put(left, y, &format!("│{}│", " ".repeat(inner_width)));
+        for column in 0..layout.columns {
+            let player = match view.falling {
+                Some((falling_column, falling_row, player))
+                    if (falling_column, falling_row) == (column, row) =>
+                {
+                    player
+                }
+                _ => view.board[column][row],
+            };
+            let x = left + 1 + column * CELL_WIDTH + 1;
+            let style = view.theme.style(player);
+            screen.put_styled(x, y, &style.glyph.to_string(), style.colour);
+        }
+    }
+    screen.put(
+        left,
+        top + 2 + layout.rows,
+        &format!("└{}┘", "─".repeat(inner_width)),
+    );
+    let labels: String = (0..layout.columns)
+        .map(|column| format!("{:^3}", view.labels.label(column)))
+        .collect();
+    screen.put(left + 1, top + 3 + layout.rows, &labels);
+}
+
+// A grid of characters that's written to piece by piece, ignoring anything that falls off the edge.
+// Each character has the ANSI SGR parameters for its colour, which are empty for the default colour.
+struct Screen {
+    cells: Vec<Vec<(char, &'static str)>>,
+}
+
+impl Screen {
+    fn new(width: usize, height: usize) -> Self {
+        Screen {
+            cells: vec![vec![(' ', ""); width]; height],
+        }
+    }
+
+    fn put(&mut self, x: usize, y: usize, text: &str) {
+        self.put_styled(x, y, text, "");
+    }
+
+    fn put_styled(&mut self, x: usize, y: usize, text: &str, colour: &'static str) {
+        if let Some(line) = self.cells.get_mut(y) {
+            for (cell, character) in line.iter_mut().skip(x).zip(text.chars()) {
+                *cell = (character, colour);
+            }
+        }
+    }
+
+    fn draw_box(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        title: &str,
+        lines: &[String],
+    ) {
+        if width < 2 || height < 2 {
+            return;
+        }
+        let inner_width = width - 2;
+        let title: String = format!("─{}", title).chars().take(inner_width).collect();
+        let fill = "─".repeat(inner_width - title.chars().count());
+        self.put(x, y, &format!("┌{}{}┐", title, fill));
+        for row in 0..height - 2 {
+            let text: String = lines
+                .get(row)
+                .map_or("", |x| x)
+                .chars()
+                .take(inner_width)
+                .collect();
+            self.put(x, y + 1 + row, &format!("│{:<inner_width$}│", text));
+        }
+        self.put(x, y + height - 1, &format!("└{}┘", "─".repeat(inner_width)));
+    }
+
+    // Colours are switched only where they change, and reset at the end of each line
+    fn lines(self) -> Vec<String> {
+        self.cells
+            .into_iter()
+            .map(|cells| {
+                let mut line = String::new();
+                let mut current = "";
+                for (character, colour) in cells {
+                    if colour != current {
+                        line += match colour {
+                            "" => "\x1b[0m".to_owned(),
+                            colour => format!("\x1b[0;{}m", colour),
+                        }
+                        .as_str();
+                        current = colour;
+                    }
+                    line.push(character);
+                }
+                if !current.is_empty() {
+                    line += "\x1b[0m";
+                }
+                line
+            })
+            .collect()
+    }
+}
+
+// Plays games in the full-screen mode until the player quits. The clocks, if there are any, count down live.
+pub fn play(
+    ruleset: &Ruleset,
+    players: Players,
+    time_control: Option<TimeControl>,
+    theme: &Theme,
+    labels: ColumnLabels,
+) -> io::Result<()> {
+    let mut bot = players.bot();
+    let mut session = Session {
+        terminal: Terminal::enter()?,
+        ruleset,
+        players,
+        theme,
+        labels,
+        size: terminal::size().unwrap_or(FALLBACK_SIZE),
+    };
+    let win_conditions = ruleset.win_conditions();
+    let new_game = || {
+        Game::initialise(ruleset.columns, ruleset.rows, &win_conditions)
+            .with_variants(&ruleset.variants)
+    };
+    let mut game = new_game();
+    let mut clock = time_control.map(Clock::new);
+    let mut cursor = ruleset.columns / 2;
+    let mut message = String::new();
+    let mut last_resize_check = Instant::now();
+    let mut pending = vec![];
+    let mut layout = session.redraw(&game, clock.as_ref(), cursor, None, &message, true)?;
+    // The status lines as last drawn, so the screen is only redrawn as the clocks tick over
+    let mut shown_status = vec![];
+
+    loop {
+        let bytes = session.terminal.read()?;
+        // An escape on its own is a key press rather than the start of a sequence
+        if bytes.is_empty() && pending == [0x1b] {
+            pending.clear();
+        }
+        pending.extend(bytes);
+        let mut changed = false;
+        let mut drop_disc = false;
+        for key in parse_keys(&mut pending) {
+            changed = true;
+            message.clear();
+            match key {
+                Key::Quit => return Ok(()),
+                Key::Left => cursor = cursor.saturating_sub(1),
+                Key::Right => cursor = (cursor + 1).min(ruleset.columns - 1),
+                Key::Drop => drop_disc = true,
+                Key::NewGame => {
+                    game = new_game();
+                    clock = time_control.map(Clock::new);
+                    cursor = ruleset.columns / 2;
+                }
+                Key::Click { x, y } => {
+                    // Clicking a column chooses it, and clicking the chosen column drops a disc
+                    if let Some(column) = layout.and_then(|layout| layout.column_at(x, y)) {
+                        drop_disc = column == cursor;
+                        cursor = column;
+                    }
+                }
+            }
+        }
+
+        if game.status == GameStatus::Started {
+            if let Some(clock) = &mut clock {
+                clock.start(game.current, Instant::now());
+                bot.limit_time(Some(clock.remaining(game.current, Instant::now())));
+            }
+            changed |= flag_fell(&mut game, &clock, &mut message);
+        }
+
+        let player_type = session.players.for_player(game.current);
+        let human_to_move = player_type == PlayerType::Human;
+        let column = match game.status {
+            GameStatus::Started if human_to_move && drop_disc => Some(cursor),
+            GameStatus::Started if !human_to_move => bot.choose(player_type, &game),
+            _ => None,
+        };
+        if let Some(error) = bot.take_engine_error() {
+            message = error;
+            changed = true;
+        }
+        // A computer player's flag can fall while it thinks
+        if column.is_some() && flag_fell(&mut game, &clock, &mut message) {
+            changed = true;
+        } else if let Some(column) = column {
+            cursor = column;
+            match landing_row(game.board(), column) {
+                Some(row) => {
+                    if let Some(clock) = &mut clock {
+                        let _ = clock.stop(Instant::now());
+                    }
+                    for falling_row in (row..ruleset.rows).rev() {
+                        session.redraw(
+                            &game,
+                            clock.as_ref(),
+                            cursor,
+                            Some((column, falling_row, game.current)),
+                            &message,
+                            false,
+                        )?;
+                        thread::sleep(FALL_FRAME);
+                    }
+                    game = game
+                        .play_on_column(column)
+                        .expect("The column has room for the disc");
+                }
+                None => {
+                    message = locale::text(
+                        "tui.column_full",
+                        &[("column", &session.labels.label(column))],
+                    )
+                }
+            }
+            changed = true;
+        }
+
+        let mut resized = false;
+        if last_resize_check.elapsed() >= RESIZE_CHECK {
+            last_resize_check = Instant::now();
+            let size = terminal::size().unwrap_or(session.size);
+            resized = size != session.size;
+            session.size = size;
+        }
+        let status = status_lines(&game, &session.players, clock.as_ref(), &message);
+        if changed || resized || status != shown_status {
+            layout = session.redraw(&game, clock.as_ref(), cursor, None, &message, resized)?;
+            shown_status = status;
+        }
+    }
+}
+
+// What stays the same for every game played in one sitting
+struct Session<'a> {
+    terminal: Terminal,
+    ruleset: &'a Ruleset,
+    players: Players,
+    theme: &'a Theme,
+    labels: ColumnLabels,
+    // The terminal's (columns, rows)
+    size: (usize, usize),
+}
+
+impl Session<'_> {
+    fn redraw(
+        &mut self,
+        game: &Game,
+        clock: Option<&Clock>,
+        cursor: usize,
+        falling: Option<(usize, usize, Player)>,
+        message: &str,
+        clear: bool,
+    ) -> io::Result<Option<Layout>> {
+        let view = View {
+            board: game.board(),
+            cursor,
+            falling,
+            status: status_lines(game, &self.players, clock, message),
+            settings: settings_lines(self.ruleset),
+            moves: game.moves(),
+            theme: self.theme,
+            labels: self.labels,
+        };
+        let (lines, layout) = render(&view, self.size.0, self.size.1);
+        self.terminal.draw(&lines, clear)?;
+        Ok(layout)
+    }
+}
+
+// Ends the game if the side to move has run out of time, saying so. Gives whether they had.
+fn flag_fell(game: &mut Game, clock: &Option<Clock>, message: &mut String) -> bool {
+    let Some(clock) = clock else {
+        return false;
+    };
+    match clock::end_on_flag(game, clock, Instant::now()) {
+        Some((flagged, winner)) => {
+            *message = clock::describe_flag(DEFAULT_NAMES, flagged, winner);
+            true
+        }
+        None => false,
+    }
+}
+
+// Each player's time follows their type when there's a clock
+fn status_lines(
+    game: &Game,
+    players: &Players,
+    clock: Option<&Clock>,
+    message: &str,
+) -> Vec<String> {
+    let headline = match game.status {
+        GameStatus::Started => locale::text("tui.to_move", &[("name", &name(game.current))]),
+        GameStatus::Completed => locale::text(
+            "game.wins",
+            &[(
+                "name",
+                &name(
+                    game.winner
+                        .expect("Game has been win with no winner. Invalid state."),
+                ),
+            )],
+        ),
+        GameStatus::Draw => locale::text("game.draw", &[]),
+    };
+    let time = |side: Player| match clock {
+        Some(clock) => format!("  {}", format_time(clock.remaining(side, Instant::now()))),
+        None => String::new(),
+    };
+    vec![
+        headline,
+        locale::text(
+            "tui.seat_one",
+            &[("player", &players.one), ("time", &time(Player::One))],
+        ),
+        locale::text(
+            "tui.seat_two",
+            &[("player", &players.two), ("time", &time(Player::Two))],
+        ),
+        message.to_owned(),
+        locale::text("tui.choose_keys", &[]),
+        locale::text("tui.drop_keys", &[]),
+        locale::text("tui.game_keys", &[]),
+    ]
+}
+
+fn name(side: Player) -> &'static str {
+    match side {
+        Player::Two => DEFAULT_NAMES[1],
+        _ => DEFAULT_NAMES[0],
+    }
+}
+
+fn settings_lines(ruleset: &Ruleset) -> Vec<String> {
+    let mut lines = vec![
+        locale::text("tui.rules", &[("name", &ruleset.name)]),
+        locale::text(
+            "tui.board",
+            &[("columns", &ruleset.columns), ("rows", &ruleset.rows)],
+        ),
+        locale::text("tui.connect", &[("length", &ruleset.connect_length)]),
+    ];
+    lines.extend(ruleset.win_conditions.iter().map(|x| format!("  {}", x)));
+    if !ruleset.variants.is_empty() {
+        let variants: Vec<String> = ruleset.variants.iter().map(|x| x.to_string()).collect();
+        lines.push(locale::text(
+            "tui.variants",
+            &[("variants", &variants.join(", "))],
+        ));
+    }
+    lines
+}
+
+fn landing_row(board: &[Vec<Player>], column: usize) -> Option<usize> {
+    board[column].iter().position(|x| *x == Player::None)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        labels::ColumnLabels,
+        remove_consts::game::Player,
+        theme::{CLASSIC, PLAIN},
+    };
+
+    use super::{render, Layout, View};
+
+    fn view<'a>(board: &'a [Vec<Player>], moves: &'a [usize]) -> View<'a> {
+        View {
+            board,
+            cursor: 1,
+            falling: None,
+            status: vec!["Player One to move".to_owned()],
+            settings: vec!["Rules: Standard".to_owned()],
+            moves,
+            theme: &PLAIN,
+            labels: ColumnLabels::default(),
+        }
+    }
+
+    #[test]
+    fn fills_the_screen_exactly() {
+        let board = vec![vec![Player::None; 6]; 7];
+        for (width, height) in [(100, 30), (40, 12), (23, 10), (10, 5)] {
+            let (lines, _) = render(&view(&board, &[]), width, height);
+            assert_eq!(lines.len(), height);
+            assert!(lines.iter().all(|x| x.chars().count() == width));
+        }
+    }
+
+    #[test]
+    fn draws_the_board_discs_and_cursor() {
+        let mut board = vec![vec![Player::None; 2]; 3];
+        board[0][0] = Player::One;
+        board[2][0] = Player::Two;
+        let mut view = view(&board, &[0, 2]);
+        view.falling = Some((1, 1, Player::One));
+        let (lines, layout) = render(&view, 11, 8);
+        assert_eq!(
+            layout,
+            Some(Layout {
+                board_left: 0,
+                board_top: 2,
+                columns: 3,
+                rows: 2
+            })
+        );
+        assert_eq!(lines[2], "     ▼     ");
+        assert_eq!(lines[3], "┌─────────┐");
+        assert_eq!(lines[4], "│ .  x  . │");
+        assert_eq!(lines[5], "│ x  .  o │");
+        assert_eq!(lines[6], "└─────────┘");
+        assert_eq!(lines[7], "  0  1  2  ");
+
+        view.labels = ColumnLabels::Letters;
+        let (lines, _) = render(&view, 11, 8);
+        assert_eq!(lines[7], "  a  b  c  ");
+    }
+
+    #[test]
+    fn colours_the_discs_with_the_theme() {
+        let mut board = vec![vec![Player::None; 2]; 3];
+        board[0][0] = Player::One;
+        let mut view = view(&board, &[0]);
+        view.theme = &CLASSIC;
+        let (lines, _) = render(&view, 11, 8);
+        assert_eq!(
+            lines[5],
+            "│ \x1b[0;1;31mx\x1b[0m  \x1b[0;2m.\x1b[0m  \x1b[0;2m.\x1b[0m │"
+        );
+    }
+
+    #[test]
+    fn shows_the_panels_when_there_is_room() {
+        let board = vec![vec![Player::None; 6]; 7];
+        let (lines, layout) = render(&view(&board, &[3, 3, 4]), 100, 20);
+        let screen = lines.join("\n");
+        assert!(screen.contains("Status"));
+        assert!(screen.contains("Player One to move"));
+        assert!(screen.contains("Rules: Standard"));
+        assert!(screen.contains("Moves"));
+        assert!(screen.contains("  3. x 4"));
+        assert_eq!(layout.unwrap().board_left, 32);
+
+        let mut lettered = view(&board, &[3, 3, 4]);
+        lettered.labels = ColumnLabels::Letters;
+        let (lines, _) = render(&lettered, 100, 20);
+        assert!(lines.join("\n").contains("  3. x e"));
+
+        let (lines, layout) = render(&view(&board, &[3]), 30, 20);
+        assert!(!lines.join("\n").contains("Moves"));
+        assert!(lines.join("\n").contains("Player One to move"));
+        assert_eq!(layout.unwrap().board_left, 0);
+    }
+
+    #[test]
+    fn asks_for_a_larger_terminal_when_the_board_does_not_fit() {
+        let board = vec![vec![Player::None; 6]; 7];
+        let (lines, layout) = render(&view(&board, &[]), 60, 8);
+        assert!(lines[0].starts_with("Please make the terminal at least 23x12."));
+        assert_eq!(layout, None);
+    }
+
+    #[test]
+    fn finds_the_column_under_a_click() {
+        let layout = Layout {
+            board_left: 10,
+            board_top: 2,
+            columns: 7,
+            rows: 6,
+        };
+        assert_eq!(layout.column_at(11, 5), Some(0));
+        assert_eq!(layout.column_at(13, 2), Some(0));
+        assert_eq!(layout.column_at(14, 8), Some(1));
+        assert_eq!(layout.column_at(31, 11), Some(6));
+        assert_eq!(layout.column_at(32, 5), None);
+        assert_eq!(layout.column_at(10, 5), None);
+        assert_eq!(layout.column_at(12, 12), None);
+    }
+}
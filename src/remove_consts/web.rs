@@ -0,0 +1,145 @@
+use std::{
+    io::{BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::Sender,
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::{
+    api::read_request,
+    server::{Event, Writer, WRITE_TIMEOUT},
+    websocket::{self, Message, MessageReader},
+};
+
+pub const DEFAULT_WEB_PORT: u16 = 8000;
+
+// The page talks to the server on this path
+const WEBSOCKET_PATH: &str = "/ws";
+
+// How long a browser has to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// The whole front-end is built into the binary, so it works without a network connection
+const ASSETS: [(&str, &str, &str); 3] = [
+    (
+        "/",
+        "text/html; charset=utf-8",
+        include_str!("../../web/index.html"),
+    ),
+    (
+        "/app.js",
+        "text/javascript; charset=utf-8",
+        include_str!("../../web/app.js"),
+    ),
+    (
+        "/style.css",
+        "text/css; charset=utf-8",
+        include_str!("../../web/style.css"),
+    ),
+];
+
+/* Answers browsers, each connection on its own thread. Requests for the page are served from the
+built-in assets, and a WebSocket on /ws becomes a connection to the game server,
+sending and receiving protocol lines as text messages.
+*/
+pub fn accept(listener: TcpListener, events: Sender<Event>, ids: Arc<AtomicUsize>) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let (events, ids) = (events.clone(), Arc::clone(&ids));
+        thread::spawn(move || handle(stream, &events, &ids));
+    }
+}
+
+fn handle(stream: TcpStream, events: &Sender<Event>, ids: &AtomicUsize) {
+    let _ = stream.set_read_timeout(Some(REQUEST_TIMEOUT));
+    let Ok(reader) = stream.try_clone() else {
+        return;
+    };
+    let mut reader = BufReader::new(reader);
+    let request = match read_request(&mut reader) {
+        Ok(request) => request,
+        Err(response) => {
+            let _ = (&stream).write_all(response.to_http().as_bytes());
+            return;
+        }
+    };
+    let path = request.path.split('?').next().unwrap_or_default();
+    let key = request.header("Sec-WebSocket-Key");
+    match (request.method.as_str(), path, key) {
+        ("GET", WEBSOCKET_PATH, Some(key)) => {
+            let handshake = websocket::handshake_response(key);
+            // Players can take as long as they like over a move, but a browser that stops reading is dropped
+            if (&stream).write_all(handshake.as_bytes()).is_err()
+                || stream.set_read_timeout(None).is_err()
+                || stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_err()
+            {
+                return;
+            }
+            let id = ids.fetch_add(1, Ordering::Relaxed);
+            if events
+                .send(Event::Connected(id, Writer::WebSocket(stream)))
+                .is_ok()
+            {
+                read_messages(id, reader, events);
+            }
+        }
+        ("GET", path, _) => serve_asset(&stream, path),
+        _ => respond(&stream, "405 Method Not Allowed", "text/plain", "GET only."),
+    }
+}
+
+// Every line of every text message is sent on, until the browser closes the socket
+fn read_messages(id: usize, reader: BufReader<TcpStream>, events: &Sender<Event>) {
+    let _disconnect = Disconnect { id, events };
+    let mut messages = MessageReader::new(reader);
+    while let Ok(message) = messages.next_message() {
+        match message {
+            Message::Text(text) => {
+                for line in text.lines() {
+                    if events.send(Event::Line(id, line.to_owned())).is_err() {
+                        return;
+                    }
+                }
+            }
+            Message::Control => (),
+            Message::Close => break,
+        }
+    }
+}
+
+// Tells the server a browser has gone when reading from it stops, however it stops, so its seat is freed
+struct Disconnect<'a> {
+    id: usize,
+    events: &'a Sender<Event>,
+}
+
+impl Drop for Disconnect<'_> {
+    fn drop(&mut self) {
+        let _ = self.events.send(Event::Disconnected(self.id));
+    }
+}
+
+fn serve_asset(stream: &TcpStream, path: &str) {
+    match ASSETS.iter().find(|(asset, _, _)| *asset == path) {
+        Some((_, content_type, body)) => respond(stream, "200 OK", content_type, body),
+        None => respond(stream, "404 Not Found", "text/plain", "Not found."),
+    }
+}
+
+fn respond(mut stream: &TcpStream, status: &str, content_type: &str, body: &str) {
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+}
@@ -0,0 +1,281 @@
+use std::io::{self, Read, Write};
+
+// Added to the client's key before hashing, as RFC 6455 requires
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+// Messages are single protocol lines, so anything longer is refused
+const MAX_MESSAGE_LENGTH: usize = 64 * 1024;
+
+const CONTINUATION: u8 = 0x0;
+const TEXT: u8 = 0x1;
+const BINARY: u8 = 0x2;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xA;
+
+// What a client sent, once any fragments are put back together
+#[derive(PartialEq, Debug)]
+pub enum Message {
+    Text(String),
+    // Pings and pongs, which need nothing doing
+    Control,
+    Close,
+}
+
+// The response that upgrades an HTTP request with the key to a WebSocket
+pub fn handshake_response(key: &str) -> String {
+    format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    )
+}
+
+fn accept_key(key: &str) -> String {
+    base64(&sha1(
+        format!("{}{}", key.trim(), HANDSHAKE_GUID).as_bytes(),
+    ))
+}
+
+/* Reads the next message from a client. Clients mask everything they send, and can split a message
+across frames with control frames between them, so the fragments so far are kept here.
+*/
+pub struct MessageReader<R> {
+    reader: R,
+    fragments: Vec<u8>,
+}
+
+impl<R: Read> MessageReader<R> {
+    pub fn new(reader: R) -> Self {
+        MessageReader {
+            reader,
+            fragments: vec![],
+        }
+    }
+
+    pub fn next_message(&mut self) -> io::Result<Message> {
+        loop {
+            let mut header = [0; 2];
+            self.reader.read_exact(&mut header)?;
+            let finished = header[0] & 0x80 != 0;
+            let opcode = header[0] & 0x0F;
+            let masked = header[1] & 0x80 != 0;
+            let length = match header[1] & 0x7F {
+                126 => {
+                    let mut length = [0; 2];
+                    self.reader.read_exact(&mut length)?;
+                    u16::from_be_bytes(length) as usize
+                }
+                127 => {
+                    let mut length = [0; 8];
+                    self.reader.read_exact(&mut length)?;
+                    usize::try_from(u64::from_be_bytes(length)).unwrap_or(usize::MAX)
+                }
+                length => length as usize,
+            };
+            if !masked {
+                return Err(invalid("Clients must mask what they send."));
+            }
+            // The fragments so far are never over the limit, so this can't overflow whatever the frame claims
+            if length > MAX_MESSAGE_LENGTH - self.fragments.len() {
+                return Err(invalid("The message is too long."));
+            }
+            let mut mask = [0; 4];
+            self.reader.read_exact(&mut mask)?;
+            let mut payload = vec![0; length];
+            self.reader.read_exact(&mut payload)?;
+            for (index, byte) in payload.iter_mut().enumerate() {
+                *byte ^= mask[index % 4];
+            }
+
+            match opcode {
+                CLOSE => return Ok(Message::Close),
+                PING | PONG => return Ok(Message::Control),
+                TEXT | BINARY | CONTINUATION => self.fragments.extend(payload),
+                other => return Err(invalid(&format!("<{}> is not an opcode.", other))),
+            }
+            if finished {
+                let text = String::from_utf8(std::mem::take(&mut self.fragments))
+                    .map_err(|_| invalid("Text messages must be UTF-8."))?;
+                return Ok(Message::Text(text));
+            }
+        }
+    }
+}
+
+// Servers send everything unmasked, in a single frame
+pub fn write_text(writer: &mut impl Write, text: &str) -> io::Result<()> {
+    write_frame(writer, TEXT, text.as_bytes())
+}
+
+pub fn write_close(writer: &mut impl Write) -> io::Result<()> {
+    write_frame(writer, CLOSE, &[])
+}
+
+fn write_frame(writer: &mut impl Write, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        length if length < 126 => frame.push(length as u8),
+        length if length <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend((length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(127);
+            frame.extend((length as u64).to_be_bytes());
+        }
+    }
+    frame.extend(payload);
+    writer.write_all(&frame)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend((data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut words = [0u32; 80];
+        for (word, bytes) in words.iter_mut().zip(block.chunks(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for index in 16..80 {
+            words[index] =
+                (words[index - 3] ^ words[index - 8] ^ words[index - 14] ^ words[index - 16])
+                    .rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (index, word) in words.iter().enumerate() {
+            let (f, k) = match index {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let next = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            (e, d, c, b, a) = (d, c, b.rotate_left(30), a, next);
+        }
+        for (total, value) in state.iter_mut().zip([a, b, c, d, e]) {
+            *total = total.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (bytes, value) in digest.chunks_mut(4).zip(state) {
+        bytes.copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for index in 0..4 {
+            match index <= chunk.len() {
+                true => encoded.push(ALPHABET[(bits >> (18 - 6 * index) & 0x3F) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{accept_key, base64, sha1, write_text, Message, MessageReader, MAX_MESSAGE_LENGTH};
+
+    // A masked frame, as a browser would send it
+    fn client_frame(opcode_and_fin: u8, payload: &[u8]) -> Vec<u8> {
+        let mask = [0x12, 0x34, 0x56, 0x78];
+        let mut frame = vec![opcode_and_fin, 0x80 | payload.len() as u8];
+        frame.extend(mask);
+        frame.extend(payload.iter().enumerate().map(|(i, x)| x ^ mask[i % 4]));
+        frame
+    }
+
+    #[test]
+    fn accepts_the_handshake_from_the_rfc() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+        assert_eq!(
+            sha1(b"abc"),
+            [
+                0xa9, 0x99, 0x3e, 0x36, 0x47, 0x06, 0x81, 0x6a, 0xba, 0x3e, 0x25, 0x71, 0x78, 0x50,
+                0xc2, 0x6c, 0x9c, 0xd0, 0xd8, 0x9d
+            ]
+        );
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+    }
+
+    #[test]
+    fn reads_masked_and_fragmented_messages() {
+        let mut bytes = client_frame(0x81, b"MOVE 3");
+        bytes.extend(client_frame(0x01, b"JOIN "));
+        bytes.extend(client_frame(0x89, b""));
+        bytes.extend(client_frame(0x80, b"Ada"));
+        bytes.extend(client_frame(0x88, b""));
+        let mut reader = MessageReader::new(&bytes[..]);
+        assert_eq!(
+            reader.next_message().unwrap(),
+            Message::Text("MOVE 3".to_owned())
+        );
+        assert_eq!(reader.next_message().unwrap(), Message::Control);
+        assert_eq!(
+            reader.next_message().unwrap(),
+            Message::Text("JOIN Ada".to_owned())
+        );
+        assert_eq!(reader.next_message().unwrap(), Message::Close);
+        assert!(reader.next_message().is_err());
+        // Unmasked frames come from servers, not clients
+        assert!(MessageReader::new(&[0x81, 0x01, b'x'][..])
+            .next_message()
+            .is_err());
+    }
+
+    #[test]
+    fn refuses_messages_that_are_too_long() {
+        let mut bytes = client_frame(0x01, b"x");
+        bytes.extend([0x80, 0x80 | 127]);
+        bytes.extend(u64::MAX.to_be_bytes());
+        let mut reader = MessageReader::new(&bytes[..]);
+        assert!(reader.next_message().is_err());
+
+        let mut bytes = client_frame(0x01, &[b'x'; 100]);
+        bytes.extend([0x80, 0x80 | 126]);
+        bytes.extend(((MAX_MESSAGE_LENGTH - 99) as u16).to_be_bytes());
+        assert!(MessageReader::new(&bytes[..]).next_message().is_err());
+    }
+
+    #[test]
+    fn writes_unmasked_frames() {
+        let mut frame = vec![];
+        write_text(&mut frame, "TURN one").unwrap();
+        assert_eq!(frame[..2], [0x81, 8]);
+        assert_eq!(&frame[2..], b"TURN one");
+        let mut frame = vec![];
+        write_text(&mut frame, &"x".repeat(300)).unwrap();
+        assert_eq!(frame[..4], [0x81, 126, 1, 44]);
+    }
+}
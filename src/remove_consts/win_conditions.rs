@@ -2,122 +2,221 @@ use std::fmt::Display;
 
 use super::game::Player;
 
+pub const DEFAULT_CONNECT_LENGTH: usize = 4;
+
 pub fn default_win_conditions() -> Vec<Box<dyn WinCondition>> {
+    win_conditions_with_length(DEFAULT_CONNECT_LENGTH)
+}
+
+pub fn win_conditions_with_length(length: usize) -> Vec<Box<dyn WinCondition>> {
     vec![
-        VerticalWinCondition::boxed(),
-        HorizontalWinCondition::boxed(),
-        DiagonalWinCondition::boxed(),
-        ReverseDiagonalWinCondition::boxed(),
+        VerticalWinCondition::boxed_with_length(length),
+        HorizontalWinCondition::boxed_with_length(length),
+        DiagonalWinCondition::boxed_with_length(length),
+        ReverseDiagonalWinCondition::boxed_with_length(length),
     ]
 }
 
+// Looks a win condition up by its display name, ignoring case and treating '-' and '_' as spaces.
+// Names may end with a connect length in brackets, e.g. `Vertical (5)`, otherwise the default length is used.
+pub fn win_condition_from_name(name: &str) -> Option<Box<dyn WinCondition>> {
+    match name.trim().strip_suffix(')') {
+        Some(named) => {
+            let (name, length) = named.rsplit_once('(')?;
+            let length = length.trim().parse().ok().filter(|x| *x > 0)?;
+            win_condition_with_length(name, length)
+        }
+        None => win_condition_with_length(name, DEFAULT_CONNECT_LENGTH),
+    }
+}
+
+// The length must be at least 1
+pub fn win_condition_with_length(name: &str, length: usize) -> Option<Box<dyn WinCondition>> {
+    match name.trim().to_lowercase().replace(['-', '_'], " ").as_str() {
+        "vertical" => Some(VerticalWinCondition::boxed_with_length(length)),
+        "horizontal" => Some(HorizontalWinCondition::boxed_with_length(length)),
+        "forward diagonal" => Some(DiagonalWinCondition::boxed_with_length(length)),
+        "reverse diagonal" => Some(ReverseDiagonalWinCondition::boxed_with_length(length)),
+        _ => None,
+    }
+}
+
+// Names the win condition, adding the connect length when it isn't the default
+fn write_name(f: &mut std::fmt::Formatter<'_>, name: &str, length: usize) -> std::fmt::Result {
+    match length {
+        DEFAULT_CONNECT_LENGTH => f.write_str(name),
+        length => write!(f, "{} ({})", name, length),
+    }
+}
+
+// Whether a line of the length starting at the index stays within the size, however long the line is
+fn fits(start: usize, length: usize, size: usize) -> bool {
+    start.checked_add(length - 1).is_some_and(|end| end < size)
+}
+
 pub trait WinCondition: Display {
-    fn is_met(&self, board: &Vec<Vec<Player>>, column: usize, row: usize) -> bool;
+    fn is_met(&self, board: &[Vec<Player>], column: usize, row: usize) -> bool;
+    // The cells checked by `is_met`, starting from the given cell
+    fn line(&self, column: usize, row: usize) -> Vec<(usize, usize)>;
 }
 
-pub struct VerticalWinCondition {}
+pub struct VerticalWinCondition {
+    length: usize,
+}
 
 impl VerticalWinCondition {
     const NAME: &'static str = "Vertical";
 
     pub fn boxed() -> Box<Self> {
-        Box::new(Self {})
+        Self::boxed_with_length(DEFAULT_CONNECT_LENGTH)
+    }
+
+    pub fn boxed_with_length(length: usize) -> Box<Self> {
+        assert!(length > 0, "Cannot connect fewer than 1 piece.");
+        Box::new(Self { length })
     }
 }
 
 impl Display for VerticalWinCondition {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(Self::NAME)
+        write_name(f, Self::NAME, self.length)
     }
 }
 
 impl WinCondition for VerticalWinCondition {
-    fn is_met(&self, board: &Vec<Vec<Player>>, column: usize, row: usize) -> bool {
-        row + 3 < board[column].len()
+    fn is_met(&self, board: &[Vec<Player>], column: usize, row: usize) -> bool {
+        fits(row, self.length, board[column].len())
             && board[column][row] != Player::None
-            && board[column][row] == board[column][row + 1]
-            && board[column][row] == board[column][row + 2]
-            && board[column][row] == board[column][row + 3]
+            && (1..self.length).all(|i| board[column][row] == board[column][row + i])
+    }
+
+    fn line(&self, column: usize, row: usize) -> Vec<(usize, usize)> {
+        (0..self.length).map(|i| (column, row + i)).collect()
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct HorizontalWinCondition {}
+pub struct HorizontalWinCondition {
+    length: usize,
+}
 
 impl HorizontalWinCondition {
     const NAME: &'static str = "Horizontal";
     pub fn boxed() -> Box<Self> {
-        Box::new(Self {})
+        Self::boxed_with_length(DEFAULT_CONNECT_LENGTH)
+    }
+
+    pub fn boxed_with_length(length: usize) -> Box<Self> {
+        assert!(length > 0, "Cannot connect fewer than 1 piece.");
+        Box::new(Self { length })
     }
 }
 
 impl Display for HorizontalWinCondition {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(Self::NAME)
+        write_name(f, Self::NAME, self.length)
     }
 }
 
 impl WinCondition for HorizontalWinCondition {
-    fn is_met(&self, board: &Vec<Vec<Player>>, column: usize, row: usize) -> bool {
-        column + 3 < board.len()
+    fn is_met(&self, board: &[Vec<Player>], column: usize, row: usize) -> bool {
+        fits(column, self.length, board.len())
             && board[column][row] != Player::None
-            && board[column][row] == board[column + 1][row]
-            && board[column][row] == board[column + 2][row]
-            && board[column][row] == board[column + 3][row]
+            && (1..self.length).all(|i| board[column][row] == board[column + i][row])
+    }
+
+    fn line(&self, column: usize, row: usize) -> Vec<(usize, usize)> {
+        (0..self.length).map(|i| (column + i, row)).collect()
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct DiagonalWinCondition {}
+pub struct DiagonalWinCondition {
+    length: usize,
+}
 
 impl DiagonalWinCondition {
     const NAME: &'static str = "Forward Diagonal";
     pub fn boxed() -> Box<Self> {
-        Box::new(Self {})
+        Self::boxed_with_length(DEFAULT_CONNECT_LENGTH)
+    }
+
+    pub fn boxed_with_length(length: usize) -> Box<Self> {
+        assert!(length > 0, "Cannot connect fewer than 1 piece.");
+        Box::new(Self { length })
     }
 }
 
 impl Display for DiagonalWinCondition {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(Self::NAME)
+        write_name(f, Self::NAME, self.length)
     }
 }
 
 impl WinCondition for DiagonalWinCondition {
-    fn is_met(&self, board: &Vec<Vec<Player>>, column: usize, row: usize) -> bool {
-        column + 3 < board.len()
-            && row + 3 < board[column].len()
+    fn is_met(&self, board: &[Vec<Player>], column: usize, row: usize) -> bool {
+        fits(column, self.length, board.len())
+            && fits(row, self.length, board[column].len())
             && board[column][row] != Player::None
-            && board[column][row] == board[column + 1][row + 1]
-            && board[column][row] == board[column + 2][row + 2]
-            && board[column][row] == board[column + 3][row + 3]
+            && (1..self.length).all(|i| board[column][row] == board[column + i][row + i])
+    }
+
+    fn line(&self, column: usize, row: usize) -> Vec<(usize, usize)> {
+        (0..self.length).map(|i| (column + i, row + i)).collect()
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct ReverseDiagonalWinCondition {}
+pub struct ReverseDiagonalWinCondition {
+    length: usize,
+}
 
 impl ReverseDiagonalWinCondition {
     const NAME: &'static str = "Reverse Diagonal";
 
     pub fn boxed() -> Box<Self> {
-        Box::new(Self {})
+        Self::boxed_with_length(DEFAULT_CONNECT_LENGTH)
+    }
+
+    pub fn boxed_with_length(length: usize) -> Box<Self> {
+        assert!(length > 0, "Cannot connect fewer than 1 piece.");
+        Box::new(Self { length })
     }
 }
 
 impl Display for ReverseDiagonalWinCondition {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(Self::NAME)
+        write_name(f, Self::NAME, self.length)
     }
 }
 
 impl WinCondition for ReverseDiagonalWinCondition {
-    fn is_met(&self, board: &Vec<Vec<Player>>, column: usize, row: usize) -> bool {
-        column >= 3
-            && row + 3 < board[column].len()
+    fn is_met(&self, board: &[Vec<Player>], column: usize, row: usize) -> bool {
+        column + 1 >= self.length
+            && fits(row, self.length, board[column].len())
             && board[column][row] != Player::None
-            && board[column][row] == board[column - 1][row + 1]
-            && board[column][row] == board[column - 2][row + 2]
-            && board[column][row] == board[column - 3][row + 3]
+            && (1..self.length).all(|i| board[column][row] == board[column - i][row + i])
+    }
+
+    fn line(&self, column: usize, row: usize) -> Vec<(usize, usize)> {
+        (0..self.length).map(|i| (column - i, row + i)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remove_consts::game::Player;
+
+    use super::win_conditions_with_length;
+
+    #[test]
+    fn lines_longer_than_the_board_are_never_met() {
+        let board = vec![vec![Player::One; 6]; 7];
+        for win_condition in win_conditions_with_length(usize::MAX) {
+            assert!(!win_condition.is_met(&board, 3, 2), "{}", win_condition);
+        }
+        for win_condition in win_conditions_with_length(4) {
+            assert!(win_condition.is_met(&board, 3, 2), "{}", win_condition);
+        }
     }
 }
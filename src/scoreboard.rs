@@ -0,0 +1,185 @@
+use crate::{locale, theme::Disc};
+
+pub const DEFAULT_NAMES: [&str; 2] = ["Player One", "Player Two"];
+
+/* The running score for a sitting of games between two named players.
+In a best-of-n match the players take turns to go first, so the second named player plays as player one
+in every other game. Otherwise the first named player always goes first.
+*/
+#[derive(PartialEq, Debug, Clone)]
+pub struct Scoreboard {
+    pub names: [String; 2],
+    pub wins: [usize; 2],
+    pub draws: usize,
+    pub best_of: Option<usize>,
+}
+
+impl Default for Scoreboard {
+    fn default() -> Self {
+        Scoreboard::new(DEFAULT_NAMES.map(str::to_owned), None)
+    }
+}
+
+impl Scoreboard {
+    pub fn new(names: [String; 2], best_of: Option<usize>) -> Self {
+        Scoreboard {
+            names,
+            wins: [0, 0],
+            draws: 0,
+            best_of,
+        }
+    }
+
+    pub fn games_played(&self) -> usize {
+        self.wins[0] + self.wins[1] + self.draws
+    }
+
+    // Whether the second named player goes first in the next game
+    pub fn swapped(&self) -> bool {
+        self.best_of.is_some() && self.games_played() % 2 == 1
+    }
+
+    // The name of whoever plays the side in the next game
+    pub fn name(&self, side: impl Into<Disc>) -> &str {
+        self.index(side.into()).map_or("Nobody", |x| &self.names[x])
+    }
+
+    // Scores a game. A game without a winner is a draw.
+    pub fn record<P: Into<Disc>>(&mut self, winner: Option<P>) {
+        match winner.and_then(|x| self.index(x.into())) {
+            Some(index) => self.wins[index] += 1,
+            None => self.draws += 1,
+        }
+    }
+
+    // Whether the match has been played out or one player can no longer be caught
+    pub fn is_over(&self) -> bool {
+        let Some(best_of) = self.best_of else {
+            return false;
+        };
+        let remaining = best_of.saturating_sub(self.games_played());
+        remaining == 0
+            || self.wins[0] > self.wins[1] + remaining
+            || self.wins[1] > self.wins[0] + remaining
+    }
+
+    // Such as "Game 2 of 5", for the start of each game in a match
+    pub fn next_game(&self) -> Option<String> {
+        self.best_of.map(|total| {
+            locale::text(
+                "game.number",
+                &[("number", &(self.games_played() + 1)), ("total", &total)],
+            )
+        })
+    }
+
+    // Such as "Score: Ada 2, Grace 1, 1 draw"
+    pub fn standings(&self) -> String {
+        locale::text(
+            "score.standings",
+            &[
+                ("one", &self.names[0]),
+                ("one_wins", &self.wins[0]),
+                ("two", &self.names[1]),
+                ("two_wins", &self.wins[1]),
+                ("draws", &locale::plural("score.draws", self.draws, &[])),
+            ],
+        )
+    }
+
+    pub fn summary(&self) -> String {
+        let (key, name) = match self.wins[0].cmp(&self.wins[1]) {
+            std::cmp::Ordering::Greater => ("score.winner", self.names[0].as_str()),
+            std::cmp::Ordering::Less => ("score.winner", self.names[1].as_str()),
+            std::cmp::Ordering::Equal => ("score.tie", ""),
+        };
+        let (most, fewest) = match self.wins[0] >= self.wins[1] {
+            true => (self.wins[0], self.wins[1]),
+            false => (self.wins[1], self.wins[0]),
+        };
+        locale::text(
+            key,
+            &[
+                ("name", &name),
+                ("most", &most),
+                ("fewest", &fewest),
+                (
+                    "games",
+                    &locale::plural("score.games", self.games_played(), &[]),
+                ),
+                ("draws", &locale::plural("score.draws", self.draws, &[])),
+            ],
+        )
+    }
+
+    fn index(&self, side: Disc) -> Option<usize> {
+        match (side, self.swapped()) {
+            (Disc::Empty, _) => None,
+            (Disc::One, false) | (Disc::Two, true) => Some(0),
+            (Disc::Two, false) | (Disc::One, true) => Some(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remove_consts::game::Player;
+
+    use super::Scoreboard;
+
+    fn players() -> [String; 2] {
+        ["Ada".to_owned(), "Grace".to_owned()]
+    }
+
+    #[test]
+    fn keeps_a_running_score_with_draws() {
+        let mut scoreboard = Scoreboard::new(players(), None);
+        scoreboard.record(Some(Player::One));
+        scoreboard.record(Some(Player::One));
+        scoreboard.record(None::<Player>);
+        scoreboard.record(Some(Player::Two));
+        assert_eq!(scoreboard.name(Player::One), "Ada");
+        assert_eq!(scoreboard.standings(), "Score: Ada 2, Grace 1, 1 draw");
+        assert_eq!(
+            scoreboard.summary(),
+            "Ada wins 2-1 after 4 games, with 1 draw."
+        );
+        assert!(!scoreboard.is_over());
+        assert_eq!(scoreboard.next_game(), None);
+    }
+
+    #[test]
+    fn alternates_the_first_player_in_a_match() {
+        let mut scoreboard = Scoreboard::new(players(), Some(3));
+        assert_eq!(scoreboard.next_game(), Some("Game 1 of 3".to_owned()));
+        assert_eq!(scoreboard.name(Player::One), "Ada");
+        scoreboard.record(Some(Player::One));
+        assert!(scoreboard.swapped());
+        assert_eq!(scoreboard.name(Player::One), "Grace");
+        assert_eq!(scoreboard.name(Player::Two), "Ada");
+        // Ada wins again, this time as player two
+        scoreboard.record(Some(Player::Two));
+        assert_eq!(scoreboard.wins, [2, 0]);
+        assert!(scoreboard.is_over());
+        assert_eq!(
+            scoreboard.summary(),
+            "Ada wins 2-0 after 2 games, with 0 draws."
+        );
+    }
+
+    #[test]
+    fn plays_every_game_of_a_close_match() {
+        let mut scoreboard = Scoreboard::new(players(), Some(4));
+        scoreboard.record(Some(Player::One));
+        scoreboard.record(Some(Player::One));
+        assert_eq!(scoreboard.wins, [1, 1]);
+        scoreboard.record(None::<Player>);
+        assert!(!scoreboard.is_over());
+        scoreboard.record(None::<Player>);
+        assert!(scoreboard.is_over());
+        assert_eq!(
+            scoreboard.summary(),
+            "It's a tie 1-1 after 4 games, with 2 draws."
+        );
+    }
+}
@@ -0,0 +1,457 @@
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{scoreboard::Scoreboard, theme::Disc};
+
+pub const DEFAULT_STATS_PATH: &str = "stats.log";
+
+// Elo ratings, replayed from the first game recorded
+const STARTING_RATING: f64 = 1500.0;
+const RATING_CHANGE: f64 = 32.0;
+
+// Writers wait this long for another writer to finish, and treat older locks as left behind by a crash
+const LOCK_RETRY: Duration = Duration::from_millis(10);
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+const STALE_LOCK: Duration = Duration::from_secs(30);
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct PlayedGame {
+    // Seconds since the Unix epoch
+    pub finished_at: u64,
+    // Who played as player one, then player two
+    pub names: [String; 2],
+    // The index into names, or nothing for a draw
+    pub winner: Option<usize>,
+    // Zero indexed columns, in the order they were played
+    pub moves: Vec<usize>,
+}
+
+impl PlayedGame {
+    // A finished game, named by the scoreboard before it records the result
+    pub fn new<P: Into<Disc>>(scoreboard: &Scoreboard, winner: Option<P>, moves: &[usize]) -> Self {
+        PlayedGame {
+            finished_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |x| x.as_secs()),
+            names: [Disc::One, Disc::Two].map(|side| clean_name(scoreboard.name(side))),
+            winner: match winner.map(Into::into) {
+                Some(Disc::One) => Some(0),
+                Some(Disc::Two) => Some(1),
+                _ => None,
+            },
+            moves: moves.to_vec(),
+        }
+    }
+
+    /* Games are stored one per line as tab separated time, names, result and moves. For example:
+    1700000000	Ada	Grace	one	3,3,4,2
+    */
+    pub fn to_line(&self) -> String {
+        let result = match self.winner {
+            Some(0) => "one",
+            Some(_) => "two",
+            None => "draw",
+        };
+        let moves: Vec<String> = self.moves.iter().map(|x| x.to_string()).collect();
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.finished_at,
+            self.names[0],
+            self.names[1],
+            result,
+            moves.join(",")
+        )
+    }
+
+    pub fn from_line(line: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [finished_at, one, two, result, moves] = fields[..] else {
+            return Err(format!(
+                "Expected 5 tab separated fields but found {}.",
+                fields.len()
+            ));
+        };
+        let winner = match result {
+            "one" => Some(0),
+            "two" => Some(1),
+            "draw" => None,
+            other => return Err(format!("Did not recognise <{}> as a result.", other)),
+        };
+        Ok(PlayedGame {
+            finished_at: finished_at
+                .parse()
+                .map_err(|_| format!("The time <{}> could not be parsed.", finished_at))?,
+            names: [one.to_owned(), two.to_owned()],
+            winner,
+            moves: moves
+                .split(',')
+                .filter(|x| !x.is_empty())
+                .map(|x| {
+                    x.parse()
+                        .map_err(|_| format!("The move <{}> could not be parsed as a usize.", x))
+                })
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct PlayerStats {
+    pub name: String,
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+    pub rating: f64,
+    total_moves: usize,
+    // How often the player chose each column for their first move
+    openings: HashMap<usize, usize>,
+}
+
+impl PlayerStats {
+    fn new(name: &str) -> Self {
+        PlayerStats {
+            name: name.to_owned(),
+            wins: 0,
+            losses: 0,
+            draws: 0,
+            rating: STARTING_RATING,
+            total_moves: 0,
+            openings: HashMap::new(),
+        }
+    }
+
+    pub fn games(&self) -> usize {
+        self.wins + self.losses + self.draws
+    }
+
+    // Draws count as half a win
+    pub fn win_rate(&self) -> f64 {
+        match self.games() {
+            0 => 0.0,
+            games => (self.wins as f64 + self.draws as f64 / 2.0) / games as f64,
+        }
+    }
+
+    // The average number of moves in the player's games, counting both players' moves
+    pub fn average_length(&self) -> f64 {
+        match self.games() {
+            0 => 0.0,
+            games => self.total_moves as f64 / games as f64,
+        }
+    }
+
+    // The column the player most often opens with, the leftmost if there's a tie
+    pub fn favourite_opening(&self) -> Option<usize> {
+        self.openings
+            .iter()
+            .max_by_key(|(column, count)| (**count, std::cmp::Reverse(**column)))
+            .map(|(column, _)| *column)
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum SortBy {
+    WinRate,
+    #[default]
+    Rating,
+}
+
+impl SortBy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().replace(['_', ' '], "-").as_str() {
+            "win-rate" | "wins" => Some(SortBy::WinRate),
+            "rating" => Some(SortBy::Rating),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Stats {
+    players: HashMap<String, PlayerStats>,
+    // Lines that couldn't be read, such as one cut short by a crash
+    pub skipped: usize,
+}
+
+impl Stats {
+    // Loads the stats at the path, or starts with none if the file doesn't exist yet
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(error) => return Err(error),
+        };
+        let mut stats = Stats::default();
+        for line in contents.lines().filter(|x| !x.trim().is_empty()) {
+            match PlayedGame::from_line(line) {
+                Ok(game) => stats.add(&game),
+                Err(_) => stats.skipped += 1,
+            }
+        }
+        Ok(stats)
+    }
+
+    // Best first, with ties in name order
+    pub fn leaderboard(&self, sort: SortBy) -> Vec<&PlayerStats> {
+        let mut players: Vec<&PlayerStats> = self.players.values().collect();
+        players.sort_by(|a, b| {
+            let (a_key, b_key) = match sort {
+                SortBy::WinRate => (a.win_rate(), b.win_rate()),
+                SortBy::Rating => (a.rating, b.rating),
+            };
+            b_key.total_cmp(&a_key).then_with(|| a.name.cmp(&b.name))
+        });
+        players
+    }
+
+    // Players who haven't finished a game yet have the starting rating
+    pub fn rating(&self, name: &str) -> f64 {
+        self.players
+            .get(name)
+            .map_or(STARTING_RATING, |player| player.rating)
+    }
+
+    pub fn add(&mut self, game: &PlayedGame) {
+        for (side, name) in game.names.iter().enumerate() {
+            let player = self
+                .players
+                .entry(name.clone())
+                .or_insert_with(|| PlayerStats::new(name));
+            match game.winner {
+                None => player.draws += 1,
+                Some(winner) if winner == side => player.wins += 1,
+                Some(_) => player.losses += 1,
+            }
+            player.total_moves += game.moves.len();
+            if let Some(&column) = game.moves.get(side) {
+                *player.openings.entry(column).or_default() += 1;
+            }
+        }
+
+        // Someone playing themselves keeps their rating
+        if game.names[0] == game.names[1] {
+            return;
+        }
+        let ratings = game.names.clone().map(|name| self.players[&name].rating);
+        let expected = 1.0 / (1.0 + 10f64.powf((ratings[1] - ratings[0]) / 400.0));
+        let score = match game.winner {
+            Some(0) => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        };
+        let change = RATING_CHANGE * (score - expected);
+        if let Some(player) = self.players.get_mut(&game.names[0]) {
+            player.rating += change;
+        }
+        if let Some(player) = self.players.get_mut(&game.names[1]) {
+            player.rating -= change;
+        }
+    }
+}
+
+/* Appends the game to the stats file.
+Writers take turns through a lock file next to it, so games finished at the same time in different
+processes are written whole, one after the other.
+*/
+pub fn record(path: impl AsRef<Path>, game: &PlayedGame) -> io::Result<()> {
+    let _lock = Lock::acquire(path.as_ref())?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(format!("{}\n", game.to_line()).as_bytes())
+}
+
+// Such as
+//  # Player  Games  Wins  Losses  Draws  Win rate  Rating  Avg moves  Opening
+//  1 Ada         3     2       0      1     83%      1530       17.3        3
+pub fn render_leaderboard(stats: &Stats, sort: SortBy) -> String {
+    let players = stats.leaderboard(sort);
+    if players.is_empty() {
+        return "No games have been recorded yet.\n".to_owned();
+    }
+    let width = players
+        .iter()
+        .map(|x| x.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Player".len());
+    let mut output = format!(
+        "{:>3} {:<width$}  Games  Wins  Losses  Draws  Win rate  Rating  Avg moves  Opening\n",
+        "#", "Player"
+    );
+    for (rank, player) in players.iter().enumerate() {
+        output += &format!(
+            "{:>3} {:<width$}  {:>5}  {:>4}  {:>6}  {:>5}  {:>7.0}%  {:>6.0}  {:>9.1}  {:>7}\n",
+            rank + 1,
+            player.name,
+            player.games(),
+            player.wins,
+            player.losses,
+            player.draws,
+            player.win_rate() * 100.0,
+            player.rating,
+            player.average_length(),
+            player
+                .favourite_opening()
+                .map_or("-".to_owned(), |x| x.to_string())
+        );
+    }
+    output
+}
+
+// Tabs and line breaks would break the line format
+fn clean_name(name: &str) -> String {
+    name.replace(['\t', '\n', '\r'], " ")
+}
+
+// Held while writing, and removed when dropped
+struct Lock {
+    path: PathBuf,
+}
+
+impl Lock {
+    fn acquire(stats_path: &Path) -> io::Result<Self> {
+        let mut path = stats_path.as_os_str().to_owned();
+        path.push(".lock");
+        let path = PathBuf::from(path);
+        let started = SystemTime::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Lock { path }),
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => (),
+                Err(error) => return Err(error),
+            }
+            let age = fs::metadata(&path)
+                .and_then(|x| x.modified())
+                .ok()
+                .and_then(|x| x.elapsed().ok());
+            if age.is_some_and(|x| x > STALE_LOCK) {
+                let _ = fs::remove_file(&path);
+                continue;
+            }
+            if started.elapsed().unwrap_or_default() > LOCK_TIMEOUT {
+                return Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    format!("Timed out waiting for the lock <{}>.", path.display()),
+                ));
+            }
+            thread::sleep(LOCK_RETRY);
+        }
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, thread};
+
+    use crate::{remove_consts::game::Player, scoreboard::Scoreboard};
+
+    use super::{record, render_leaderboard, PlayedGame, SortBy, Stats};
+
+    fn game(one: &str, two: &str, winner: Option<usize>, moves: &[usize]) -> PlayedGame {
+        PlayedGame {
+            finished_at: 1700000000,
+            names: [one.to_owned(), two.to_owned()],
+            winner,
+            moves: moves.to_vec(),
+        }
+    }
+
+    fn stats_from(games: &[PlayedGame]) -> Stats {
+        let path = std::env::temp_dir().join(format!(
+            "connect-4-stats-test-{}-{}.log",
+            std::process::id(),
+            games.len()
+        ));
+        let lines: Vec<String> = games.iter().map(|x| x.to_line() + "\n").collect();
+        fs::write(&path, lines.concat() + "not a game\n").unwrap();
+        let stats = Stats::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        stats
+    }
+
+    #[test]
+    fn names_the_sides_from_the_scoreboard() {
+        let mut scoreboard = Scoreboard::new(["Ada".to_owned(), "Gr\tace".to_owned()], Some(3));
+        scoreboard.record(None::<Player>);
+        let played = PlayedGame::new(&scoreboard, Some(Player::Two), &[3, 4]);
+        assert_eq!(played.names, ["Gr ace", "Ada"]);
+        assert_eq!(played.winner, Some(1));
+        assert_eq!(PlayedGame::from_line(&played.to_line()), Ok(played));
+        assert!(PlayedGame::from_line("1\tAda\tGrace\tboth\t3").is_err());
+    }
+
+    #[test]
+    fn totals_each_players_games() {
+        let stats = stats_from(&[
+            game("Ada", "Grace", Some(0), &[3, 2, 3, 2, 3, 2, 3]),
+            game("Grace", "Ada", None, &[2, 3, 4, 3, 1]),
+            game("Ada", "Grace", Some(1), &[4, 2, 4, 2]),
+        ]);
+        assert_eq!(stats.skipped, 1);
+        let ada = &stats.players["Ada"];
+        assert_eq!((ada.wins, ada.losses, ada.draws), (1, 1, 1));
+        assert_eq!(ada.average_length(), 16.0 / 3.0);
+        assert_eq!(ada.win_rate(), 0.5);
+        // Ada opened with 3, 3 and 4
+        assert_eq!(ada.favourite_opening(), Some(3));
+        assert_eq!(stats.players["Grace"].favourite_opening(), Some(2));
+    }
+
+    #[test]
+    fn sorts_the_leaderboard_by_win_rate_or_rating() {
+        let mut games = vec![game("Ada", "Grace", Some(0), &[3])];
+        games.extend((0..4).map(|_| game("Grace", "Linus", Some(0), &[3])));
+        games.push(game("Linus", "Grace", Some(0), &[3]));
+        let stats = stats_from(&games);
+        let names = |sort| -> Vec<String> {
+            stats
+                .leaderboard(sort)
+                .iter()
+                .map(|x| x.name.clone())
+                .collect()
+        };
+        assert_eq!(names(SortBy::WinRate), ["Ada", "Grace", "Linus"]);
+        assert_eq!(names(SortBy::Rating), ["Grace", "Ada", "Linus"]);
+        let table = render_leaderboard(&stats, SortBy::Rating);
+        assert!(table.lines().nth(1).unwrap().starts_with("  1 Grace "));
+        assert_eq!(SortBy::from_name("Win Rate"), Some(SortBy::WinRate));
+    }
+
+    #[test]
+    fn keeps_every_game_from_concurrent_writers() {
+        let path = std::env::temp_dir().join(format!(
+            "connect-4-stats-test-{}-concurrent.log",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let writers: Vec<_> = (0..8)
+            .map(|writer| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    for _ in 0..20 {
+                        let name = format!("Writer {}", writer);
+                        record(&path, &game(&name, "Host", Some(0), &[writer; 30])).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        let stats = Stats::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(stats.skipped, 0);
+        assert_eq!(stats.players["Host"].losses, 160);
+    }
+}
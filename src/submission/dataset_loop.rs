@@ -0,0 +1,72 @@
+use std::{fs, io, time::Instant};
+
+use crate::submission::{
+    solver::Solver,
+    uci_dataset::{import, verify, DEFAULT_DATASET_PATH},
+};
+
+// How many of each kind of problem to print before summarising the rest
+const REPORT_LIMIT: usize = 10;
+
+pub fn verify_solver() {
+    let stdin = io::stdin();
+    let input = &mut String::new();
+
+    println!("<<Solver Verification>>");
+    println!(
+        "Which copy of the UCI connect-4 dataset should be checked? Leave it blank for <{}>.",
+        DEFAULT_DATASET_PATH
+    );
+    input.clear();
+    stdin.read_line(input).expect("Error reading from stdio");
+    let path = match input.trim() {
+        "" => DEFAULT_DATASET_PATH,
+        path => path,
+    };
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) => {
+            eprintln!("Could not read <{}>: {}", path, error);
+            return;
+        }
+    };
+
+    let (examples, errors) = import(&contents);
+    for error in errors.iter().take(REPORT_LIMIT) {
+        eprintln!("{}", error);
+    }
+    if errors.len() > REPORT_LIMIT {
+        eprintln!("... and {} more invalid rows.", errors.len() - REPORT_LIMIT);
+    }
+    println!(
+        "Imported {} positions, skipping {} invalid rows.",
+        examples.len(),
+        errors.len()
+    );
+
+    let started = Instant::now();
+    let report = verify(&examples, &mut Solver::new(), |checked| {
+        if checked % 1000 == 0 {
+            println!("Checked {} of {} positions.", checked, examples.len());
+        }
+    });
+    for mismatch in report.mismatches.iter().take(REPORT_LIMIT) {
+        println!(
+            "Line {}: the dataset says {:?} but the solver says {:?}.",
+            mismatch.line, mismatch.expected, mismatch.actual
+        );
+    }
+    if report.mismatches.len() > REPORT_LIMIT {
+        println!(
+            "... and {} more mismatches.",
+            report.mismatches.len() - REPORT_LIMIT
+        );
+    }
+    println!(
+        "Checked {} positions in {:.1}s. The solver agreed with {} and disagreed with {}.\n",
+        report.checked,
+        started.elapsed().as_secs_f64(),
+        report.checked - report.mismatches.len(),
+        report.mismatches.len()
+    );
+}
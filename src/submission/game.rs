@@ -1,4 +1,9 @@
-use std::fmt::{Display, Error};
+use std::fmt::Display;
+
+use crate::{
+    labels::ColumnLabels,
+    theme::{render_board, PLAIN},
+};
 
 pub const DEFAULT_COLUMNS: usize = 7;
 pub const DEFAULT_ROWS: usize = 6;
@@ -52,22 +57,11 @@ pub struct Game<const COLUMNS: usize, const ROWS: usize> {
 
 impl<const COLUMNS: usize, const ROWS: usize> std::fmt::Display for Game<COLUMNS, ROWS> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut output: String = "\n".to_owned();
-        for j in (0..self.game_board[0].len()).rev() {
-            for i in 0..self.game_board.len() {
-                output += match self.game_board[i][j] {
-                    Player::None => ".",
-                    Player::One => "x",
-                    Player::Two => "o",
-                }
-            }
-            output += "\n"
-        }
-        for i in 0..self.game_board.len() {
-            output += &i.to_string();
-        }
-        output += "\n";
-        f.write_str(&output)
+        f.write_str(&render_board(
+            &self.game_board,
+            &PLAIN,
+            ColumnLabels::default(),
+        ))
     }
 }
 
@@ -81,6 +75,81 @@ impl<const COLUMNS: usize, const ROWS: usize> Game<COLUMNS, ROWS> {
         }
     }
 
+    // Builds a game from a position, checking that it could have been reached by alternating plays
+    pub fn from_board(game_board: [[Player; ROWS]; COLUMNS]) -> Result<Self, String> {
+        let mut player_one_count = 0;
+        let mut player_two_count = 0;
+        for (column, cells) in game_board.iter().enumerate() {
+            for row in 0..ROWS {
+                match cells[row] {
+                    Player::None => {
+                        if cells[row..].iter().any(|cell| *cell != Player::None) {
+                            return Err(format!(
+                                "Column {} has a piece floating above an empty space.",
+                                column
+                            ));
+                        }
+                    }
+                    Player::One => player_one_count += 1,
+                    Player::Two => player_two_count += 1,
+                }
+            }
+        }
+
+        let mut game = Game {
+            current: Player::One,
+            game_board,
+            winner: None,
+            status: GameStatus::Started,
+        };
+        game.current = match player_one_count as isize - player_two_count as isize {
+            0 => Player::One,
+            1 => Player::Two,
+            _ => {
+                return Err(format!(
+                    "Player One has {} pieces and Player Two has {}, which cannot happen when taking turns.",
+                    player_one_count, player_two_count
+                ))
+            }
+        };
+
+        let mut winners = vec![];
+        for (column, cells) in game_board.iter().enumerate() {
+            for (row, cell) in cells.iter().enumerate() {
+                if game.has_four_connected(column, row) && !winners.contains(cell) {
+                    winners.push(*cell);
+                }
+            }
+        }
+        match winners[..] {
+            [] if player_one_count + player_two_count == COLUMNS * ROWS => {
+                game.status = GameStatus::Draw;
+            }
+            [] => (),
+            [winner] => {
+                let last_player = match game.current {
+                    Player::One => Player::Two,
+                    _ => Player::One,
+                };
+                if winner != last_player {
+                    return Err(format!(
+                        "Player {} has four connected but Player {} played last.",
+                        winner, last_player
+                    ));
+                }
+                game.current = winner;
+                game.winner = Some(winner);
+                game.status = GameStatus::Completed;
+            }
+            _ => return Err("Both players have four connected.".to_owned()),
+        }
+        Ok(game)
+    }
+
+    pub fn board(&self) -> &[[Player; ROWS]; COLUMNS] {
+        &self.game_board
+    }
+
     // Plays on the column - zero indexed
     pub fn play_on_column(self, column: usize) -> Result<Self, GameError<COLUMNS, ROWS>> {
         let mut active_state = self.clone();
@@ -333,4 +402,73 @@ mod tests {
         let result = game.play_on_column(0);
         assert!(result.is_ok())
     }
+
+    /*
+    . . . .
+    . . . .
+    o . . .
+    x x . .
+    */
+    #[test]
+    fn can_be_built_from_a_board() {
+        let mut board = [[Player::None; 4]; 4];
+        board[0][0] = Player::One;
+        board[0][1] = Player::Two;
+        board[1][0] = Player::One;
+        let game = Game::from_board(board).unwrap();
+        assert_eq!(game.status, GameStatus::Started);
+        assert_eq!(game.current, Player::Two);
+        assert_eq!(game.board(), &board);
+    }
+
+    #[test]
+    fn cannot_be_built_from_a_board_with_floating_pieces() {
+        let mut board = [[Player::None; 4]; 4];
+        board[0][1] = Player::One;
+        assert!(Game::from_board(board).is_err());
+    }
+
+    #[test]
+    fn cannot_be_built_from_a_board_where_players_did_not_take_turns() {
+        let mut board = [[Player::None; 4]; 4];
+        board[0][0] = Player::One;
+        board[1][0] = Player::One;
+        assert!(Game::from_board(board).is_err());
+        board[0][0] = Player::Two;
+        board[1][0] = Player::None;
+        assert!(Game::from_board(board).is_err());
+    }
+
+    /*
+    o . . .
+    o . . .
+    o . . .
+    x x x x
+    */
+    #[test]
+    fn recognises_a_win_when_built_from_a_board() {
+        let mut board = [[Player::None; 4]; 4];
+        for column in board.iter_mut() {
+            column[0] = Player::One;
+        }
+        board[0][1..].fill(Player::Two);
+        let game = Game::from_board(board).unwrap();
+        assert_eq!(game.status, GameStatus::Completed);
+        assert_eq!(game.winner, Some(Player::One));
+    }
+
+    /*
+    o . . .
+    o x . .
+    o x x .
+    o x x .
+    */
+    #[test]
+    fn cannot_be_built_from_a_board_where_play_continued_after_a_win() {
+        let mut board = [[Player::None; 4]; 4];
+        board[0] = [Player::Two; 4];
+        board[1] = [Player::One, Player::One, Player::One, Player::None];
+        board[2] = [Player::One, Player::One, Player::None, Player::None];
+        assert!(Game::from_board(board).is_err());
+    }
 }
@@ -1,67 +1,314 @@
-use std::io;
+use std::{io, time::Instant};
 
-use crate::submission::game::GameStatus;
+use crate::{
+    accessible,
+    labels::ColumnLabels,
+    locale,
+    remove_consts::{
+        self,
+        ai::{self, DEFAULT_DEPTH},
+        clock::{self, Clock, TimeControl},
+        ruleset::Ruleset,
+    },
+    scoreboard::Scoreboard,
+    stats::{self, PlayedGame, DEFAULT_STATS_PATH},
+    submission::game::GameStatus,
+    theme::Theme,
+    turn::{self, TurnCommand},
+};
 
-use super::game::{Game, DEFAULT_COLUMNS, DEFAULT_ROWS};
+use super::game::{Game, Player, DEFAULT_COLUMNS, DEFAULT_ROWS};
 
-pub fn play() {
+/* With a time control, a player's clock runs from their turn starting until they move, and running out
+loses the game. Nothing interrupts the prompt, so a player's flag falls when they answer it.
+*/
+pub fn play(
+    theme: &Theme,
+    labels: ColumnLabels,
+    time_control: Option<TimeControl>,
+    mut scoreboard: Scoreboard,
+) {
     let stdin = io::stdin();
     let input = &mut String::new();
 
     loop {
-        println!("<<Normal Mode>>");
+        if !accessible::enabled() {
+            println!("{}", locale::text("mode.normal", &[]));
+        }
+        if let Some(heading) = scoreboard.next_game() {
+            println!("{}", heading);
+        }
 
         let mut game: Game<DEFAULT_COLUMNS, DEFAULT_ROWS> = Game::initialise();
+        let mut moves = vec![];
+        let mut clock = time_control.map(Clock::new);
+        let names = [scoreboard.name(Player::One), scoreboard.name(Player::Two)];
+        // The positions before each move, for undoing them
+        let mut earlier = vec![];
+        let mut show_board = true;
 
         loop {
-            println!("{}", game);
+            if show_board {
+                println!(
+                    "{}",
+                    accessible::present_board(game.board(), theme, labels, names)
+                );
+                if let Some(clock) = &clock {
+                    println!("{}", clock.describe(names, Instant::now()));
+                }
+                show_board = false;
+            }
             match game.status {
                 GameStatus::Started => (),
                 GameStatus::Completed => {
+                    let winner = game
+                        .winner
+                        .expect("Game has been win with no winner. Invalid state.");
                     println!(
-                        "Player {} wins!",
-                        game.winner
-                            .expect("Game has been win with no winner. Invalid state.")
+                        "{}",
+                        accessible::result(Some(scoreboard.name(winner)), moves.len())
                     );
                     break;
                 }
                 GameStatus::Draw => {
-                    println!("It's a draw!");
+                    println!("{}", accessible::result(None, moves.len()));
                     break;
                 }
             }
+            if let Some(clock) = &mut clock {
+                clock.start(clock_side(game.current), Instant::now());
+            }
             println!(
-                "Player {}'s turn. Which column would you like to play in? 0-{}",
-                game.current,
-                DEFAULT_COLUMNS - 1
+                "{}",
+                accessible::turn_prompt(
+                    scoreboard.name(game.current),
+                    theme,
+                    game.current,
+                    &labels.range(DEFAULT_COLUMNS)
+                )
             );
             input.clear();
             stdin.read_line(input).expect("Error reading from stdio");
-            let column: usize = match input.trim().parse() {
-                Ok(column) => column,
-                Err(_) => {
-                    eprintln!(
-                        "The input <{}> could not be parsed as a usize. Please try again.",
-                        input
-                    );
+            if flag_fell(&mut game, &clock, &Ruleset::default(), &moves, names) {
+                continue;
+            }
+            let command = match turn::parse(input, labels, DEFAULT_COLUMNS) {
+                Ok(command) => command,
+                Err(message) => {
+                    eprintln!("{}", message);
                     continue;
                 }
             };
-            game = match game.play_on_column(column) {
-                Ok(game) => game,
-                Err(error) => {
-                    eprintln!("{}", error.message);
-                    error.previous_state
+            let opponent = match game.current {
+                Player::One => Player::Two,
+                _ => Player::One,
+            };
+            match command {
+                TurnCommand::Play(column) => {
+                    let previous = game.clone();
+                    game = match game.play_on_column(column) {
+                        Ok(game) => {
+                            earlier.push(previous);
+                            moves.push(column);
+                            stop_clock(&mut clock);
+                            show_board = announce_move(&game, column, labels, names);
+                            game
+                        }
+                        Err(error) => {
+                            eprintln!("{}", error.message);
+                            error.previous_state
+                        }
+                    }
+                }
+                TurnCommand::Undo => match earlier.pop() {
+                    Some(previous) => {
+                        game = previous;
+                        moves.pop();
+                        show_board = true;
+                    }
+                    None => eprintln!("{}", locale::text("game.nothing_to_undo", &[])),
+                },
+                TurnCommand::Hint => {
+                    match ai::suggest(&Ruleset::default(), &moves, DEFAULT_DEPTH) {
+                        Some(column) => println!(
+                            "{}",
+                            locale::text("game.hint", &[("column", &labels.label(column))])
+                        ),
+                        None => eprintln!("{}", locale::text("game.no_hint", &[])),
+                    }
+                }
+                TurnCommand::Save(path) => match turn::save_moves(&path, &moves) {
+                    Ok(()) => println!("{}", locale::text("game.saved_moves", &[("path", &path)])),
+                    Err(message) => eprintln!("{}", message),
+                },
+                TurnCommand::Load(path) => {
+                    let loaded = turn::load_moves(&path).and_then(|loaded| {
+                        let replayed =
+                            turn::replay(Game::initialise(), &loaded, |game, column| {
+                                game.play_on_column(column).map_err(|error| error.message)
+                            })?;
+                        Ok((loaded, replayed))
+                    });
+                    match loaded {
+                        Ok((loaded, (replayed, replayed_earlier))) => {
+                            game = replayed;
+                            earlier = replayed_earlier;
+                            moves = loaded;
+                            show_board = true;
+                            println!("{}", locale::text("game.loaded_moves", &[("path", &path)]));
+                        }
+                        Err(message) => eprintln!("{}", message),
+                    }
+                }
+                TurnCommand::Resign => {
+                    println!(
+                        "{}",
+                        locale::text("game.resigns", &[("name", &scoreboard.name(game.current))])
+                    );
+                    game.winner = Some(opponent);
+                    game.status = GameStatus::Completed;
+                }
+                TurnCommand::OfferDraw => {
+                    println!(
+                        "{}",
+                        locale::text("game.offer_draw", &[("name", &scoreboard.name(opponent))])
+                    );
+                    input.clear();
+                    stdin.read_line(input).expect("Error reading from stdio");
+                    match locale::is_yes(input) {
+                        true => game.status = GameStatus::Draw,
+                        false => println!("{}", locale::text("game.draw_declined", &[])),
+                    }
+                }
+                TurnCommand::Board => show_board = true,
+                TurnCommand::Column(column) => println!(
+                    "{}",
+                    accessible::describe_column(game.board(), column, labels, names)
+                ),
+                TurnCommand::Row(row) => match accessible::describe_row(game.board(), row, names) {
+                    Ok(description) => println!("{}", description),
+                    Err(message) => eprintln!("{}", message),
+                },
+                TurnCommand::History => println!("{}", turn::history(&moves, names, labels)),
+                TurnCommand::Rules => println!(
+                    "{}",
+                    locale::text(
+                        "game.standard_rules",
+                        &[("columns", &DEFAULT_COLUMNS), ("rows", &DEFAULT_ROWS)]
+                    )
+                ),
+                TurnCommand::Help => println!("{}", turn::help()),
+                TurnCommand::Quit => {
+                    println!("{}", locale::text("game.quit", &[]));
+                    return;
                 }
             }
         }
 
-        println!("Would you like to play again? Y/n");
+        let played = PlayedGame::new(&scoreboard, game.winner, &moves);
+        if let Err(error) = stats::record(DEFAULT_STATS_PATH, &played) {
+            eprintln!(
+                "{}",
+                locale::text(
+                    "game.stats_failed",
+                    &[("path", &DEFAULT_STATS_PATH), ("error", &error)]
+                )
+            );
+        }
+        scoreboard.record(game.winner);
+        println!("{}", scoreboard.standings());
+        if scoreboard.is_over() {
+            println!("{}", scoreboard.summary());
+            println!("{}", locale::text("game.return_to_menu", &[]));
+            break;
+        }
+        if scoreboard.best_of.is_some() {
+            continue;
+        }
+
+        println!("{}", locale::text("game.play_again", &[]));
         input.clear();
         stdin.read_line(input).expect("Error reading from stdio");
-        if input.trim() == "n" {
-            println!("Returning to the main menu.");
+        if locale::is_no(input) {
+            println!("{}", scoreboard.summary());
+            println!("{}", locale::text("game.return_to_menu", &[]));
             break;
         }
     }
 }
+
+// Ends the game if the side to move has run out of time. Gives whether they had.
+fn flag_fell(
+    game: &mut Game<DEFAULT_COLUMNS, DEFAULT_ROWS>,
+    clock: &Option<Clock>,
+    ruleset: &Ruleset,
+    moves: &[usize],
+    names: [&str; 2],
+) -> bool {
+    let Some(clock) = clock else {
+        return false;
+    };
+    match clock::flag_after_moves(clock, ruleset, moves, Instant::now()) {
+        Some((flagged, winner)) => {
+            println!("{}", clock::describe_flag(names, flagged, winner));
+            game.winner = winner.map(|x| match x {
+                remove_consts::game::Player::Two => Player::Two,
+                _ => Player::One,
+            });
+            game.status = match winner {
+                Some(_) => GameStatus::Completed,
+                None => GameStatus::Draw,
+            };
+            true
+        }
+        None => false,
+    }
+}
+
+// Adds the increment for the move just made. The flag has been checked since the move was chosen.
+fn stop_clock(clock: &mut Option<Clock>) {
+    if let Some(clock) = clock {
+        let _ = clock.stop(Instant::now());
+    }
+}
+
+// The clock counts sides with the customisable version's players
+fn clock_side(player: Player) -> remove_consts::game::Player {
+    match player {
+        Player::Two => remove_consts::game::Player::Two,
+        _ => remove_consts::game::Player::One,
+    }
+}
+
+// Announces a move in the accessible mode. Gives whether the board should be drawn instead.
+fn announce_move(
+    game: &Game<DEFAULT_COLUMNS, DEFAULT_ROWS>,
+    column: usize,
+    labels: ColumnLabels,
+    names: [&str; 2],
+) -> bool {
+    if !accessible::enabled() {
+        return true;
+    }
+    let threats = (game.status == GameStatus::Started).then(|| winning_columns(game));
+    println!(
+        "{}",
+        accessible::announce_move(game.board(), column, labels, names, game.current, threats)
+    );
+    false
+}
+
+// The columns each player could win in with their next disc
+fn winning_columns(game: &Game<DEFAULT_COLUMNS, DEFAULT_ROWS>) -> [Vec<usize>; 2] {
+    [Player::One, Player::Two].map(|player| {
+        (0..DEFAULT_COLUMNS)
+            .filter(|&column| {
+                let mut trial = game.clone();
+                trial.current = player;
+                trial
+                    .play_on_column(column)
+                    .is_ok_and(|x| x.winner == Some(player))
+            })
+            .collect()
+    })
+}
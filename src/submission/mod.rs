@@ -1,2 +1,5 @@
+pub mod dataset_loop;
 pub mod game;
 pub mod game_loop;
+pub mod solver;
+pub mod uci_dataset;
@@ -0,0 +1,389 @@
+use super::game::{Game, GameStatus, Player};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Verdict {
+    Win,
+    Loss,
+    Draw,
+}
+
+// Entries in the transposition table. Keys are stored in full so any board that fits in 64 bits is safe.
+const TABLE_SIZE: usize = 8_388_593;
+// Bounds are stored offset so that 0 can mean an empty entry. Upper bounds come first, then lower bounds.
+const EMPTY: u8 = 0;
+
+/* Solves positions with perfect play, following Pascal Pons' connect 4 solver.
+The board is held as a pair of bitboards, one bit per cell with an extra spare bit at the top of each column.
+Scores count how early a player can win - the sooner the win, the higher the score. A draw scores 0.
+*/
+pub struct Solver<const COLUMNS: usize, const ROWS: usize> {
+    keys: Vec<u64>,
+    bounds: Vec<u8>,
+    column_order: Vec<usize>,
+}
+
+impl<const COLUMNS: usize, const ROWS: usize> Solver<COLUMNS, ROWS> {
+    pub fn new() -> Self {
+        assert!(
+            COLUMNS * (ROWS + 1) <= 64,
+            "The board is too large for the solver."
+        );
+        // Central columns take part in more lines so they are searched first
+        let mut column_order: Vec<usize> = (0..COLUMNS).collect();
+        column_order.sort_by_key(|&column| (2 * column).abs_diff(COLUMNS - 1));
+        Solver {
+            keys: vec![0; TABLE_SIZE],
+            bounds: vec![EMPTY; TABLE_SIZE],
+            column_order,
+        }
+    }
+
+    // The result of the game with perfect play, for the player whose turn it is.
+    // When the game is already won, `current` is the winner, so the verdict is a win.
+    pub fn verdict(&mut self, game: &Game<COLUMNS, ROWS>) -> Verdict {
+        match game.status {
+            GameStatus::Completed => return Verdict::Win,
+            GameStatus::Draw => return Verdict::Draw,
+            GameStatus::Started => (),
+        }
+        let position = Position::<COLUMNS, ROWS>::from_game(game);
+        // Null window searches prune the most, so ask whether the score is above 0 and then whether it's below
+        if position.can_win_next() || self.negamax(&position, 0, 1) > 0 {
+            Verdict::Win
+        } else if self.negamax(&position, -1, 0) < 0 {
+            Verdict::Loss
+        } else {
+            Verdict::Draw
+        }
+    }
+
+    fn negamax(
+        &mut self,
+        position: &Position<COLUMNS, ROWS>,
+        mut alpha: i32,
+        mut beta: i32,
+    ) -> i32 {
+        let cells = (COLUMNS * ROWS) as i32;
+        let moves = position.moves as i32;
+        let next = position.possible_non_losing_moves();
+        if next == 0 {
+            return -(cells - moves) / 2;
+        }
+        if moves >= cells - 2 {
+            return 0;
+        }
+
+        let min = -(cells - 2 - moves) / 2;
+        if alpha < min {
+            alpha = min;
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+        let max = (cells - 1 - moves) / 2;
+        if beta > max {
+            beta = max;
+            if alpha >= beta {
+                return beta;
+            }
+        }
+
+        let lowest = -(cells / 2);
+        let highest = (cells + 1) / 2;
+        let key = position.key();
+        let slot = (key % TABLE_SIZE as u64) as usize;
+        if self.keys[slot] == key && self.bounds[slot] != EMPTY {
+            let bound = self.bounds[slot] as i32;
+            if bound > highest - lowest + 1 {
+                let min = bound - highest + 2 * lowest - 2;
+                if alpha < min {
+                    alpha = min;
+                    if alpha >= beta {
+                        return alpha;
+                    }
+                }
+            } else {
+                let max = bound + lowest - 1;
+                if beta > max {
+                    beta = max;
+                    if alpha >= beta {
+                        return beta;
+                    }
+                }
+            }
+        }
+
+        // Insertion sorted by score, so columns with equal scores stay in central order
+        let mut candidates = [(0u64, 0u32); COLUMNS];
+        let mut candidate_count = 0;
+        for &column in &self.column_order {
+            let candidate = next & Position::<COLUMNS, ROWS>::column_mask(column);
+            if candidate == 0 {
+                continue;
+            }
+            let score = position.move_score(candidate);
+            let mut index = candidate_count;
+            while index > 0 && candidates[index - 1].1 < score {
+                candidates[index] = candidates[index - 1];
+                index -= 1;
+            }
+            candidates[index] = (candidate, score);
+            candidate_count += 1;
+        }
+
+        for &(candidate, _) in &candidates[..candidate_count] {
+            let mut child = position.clone();
+            child.play(candidate);
+            let score = -self.negamax(&child, -beta, -alpha);
+            if score >= beta {
+                self.keys[slot] = key;
+                self.bounds[slot] = (score + highest - 2 * lowest + 2) as u8;
+                return score;
+            }
+            if score > alpha {
+                alpha = score;
+            }
+        }
+
+        self.keys[slot] = key;
+        self.bounds[slot] = (alpha - lowest + 1) as u8;
+        alpha
+    }
+}
+
+#[derive(Clone)]
+struct Position<const COLUMNS: usize, const ROWS: usize> {
+    // The pieces of the player whose turn it is
+    current: u64,
+    // Every piece on the board
+    mask: u64,
+    moves: usize,
+}
+
+impl<const COLUMNS: usize, const ROWS: usize> Position<COLUMNS, ROWS> {
+    const HEIGHT: usize = ROWS + 1;
+
+    fn from_game(game: &Game<COLUMNS, ROWS>) -> Self {
+        let mut position = Position {
+            current: 0,
+            mask: 0,
+            moves: 0,
+        };
+        for (column, cells) in game.board().iter().enumerate() {
+            for (row, cell) in cells.iter().enumerate() {
+                let bit = 1 << (column * Self::HEIGHT + row);
+                if *cell != Player::None {
+                    position.mask |= bit;
+                    position.moves += 1;
+                }
+                if *cell == game.current {
+                    position.current |= bit;
+                }
+            }
+        }
+        position
+    }
+
+    fn bottom_mask() -> u64 {
+        (0..COLUMNS).fold(0, |mask, column| mask | 1 << (column * Self::HEIGHT))
+    }
+
+    fn board_mask() -> u64 {
+        Self::bottom_mask() * ((1 << ROWS) - 1)
+    }
+
+    fn column_mask(column: usize) -> u64 {
+        ((1 << ROWS) - 1) << (column * Self::HEIGHT)
+    }
+
+    // Unique for every position, as `mask + bottom_mask` marks the top of each column
+    fn key(&self) -> u64 {
+        self.current + self.mask
+    }
+
+    fn play(&mut self, candidate: u64) {
+        self.current ^= self.mask;
+        self.mask |= candidate;
+        self.moves += 1;
+    }
+
+    fn possible(&self) -> u64 {
+        (self.mask + Self::bottom_mask()) & Self::board_mask()
+    }
+
+    fn can_win_next(&self) -> bool {
+        self.winning_positions() & self.possible() != 0
+    }
+
+    fn winning_positions(&self) -> u64 {
+        winning_positions(self.current, self.mask, Self::HEIGHT, Self::board_mask())
+    }
+
+    fn opponent_winning_positions(&self) -> u64 {
+        winning_positions(
+            self.current ^ self.mask,
+            self.mask,
+            Self::HEIGHT,
+            Self::board_mask(),
+        )
+    }
+
+    // Moves that don't hand the opponent an immediate win, or nothing if the opponent can't be stopped
+    fn possible_non_losing_moves(&self) -> u64 {
+        let mut possible = self.possible();
+        let opponent_wins = self.opponent_winning_positions();
+        let forced = possible & opponent_wins;
+        if forced != 0 {
+            if forced & (forced - 1) != 0 {
+                return 0;
+            }
+            possible = forced;
+        }
+        possible & !(opponent_wins >> 1)
+    }
+
+    // The number of cells the move would leave the current player one piece away from winning on
+    fn move_score(&self, candidate: u64) -> u32 {
+        winning_positions(
+            self.current | candidate,
+            self.mask,
+            Self::HEIGHT,
+            Self::board_mask(),
+        )
+        .count_ones()
+    }
+}
+
+// The empty cells that would complete a line of four for the given pieces
+fn winning_positions(pieces: u64, mask: u64, height: usize, board_mask: u64) -> u64 {
+    let shl = |bits: u64, shift: usize| bits.checked_shl(shift as u32).unwrap_or(0);
+    let shr = |bits: u64, shift: usize| bits.checked_shr(shift as u32).unwrap_or(0);
+
+    // Vertical lines can only be completed from above
+    let mut result = shl(pieces, 1) & shl(pieces, 2) & shl(pieces, 3);
+
+    // Horizontal, then both diagonals
+    for step in [height, height - 1, height + 1] {
+        let pair = shl(pieces, step) & shl(pieces, 2 * step);
+        result |= pair & shl(pieces, 3 * step);
+        result |= pair & shr(pieces, step);
+        let pair = shr(pieces, step) & shr(pieces, 2 * step);
+        result |= pair & shl(pieces, step);
+        result |= pair & shr(pieces, 3 * step);
+    }
+
+    result & (board_mask ^ mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::submission::game::{Game, GameError, GameStatus, DEFAULT_COLUMNS, DEFAULT_ROWS};
+
+    use super::{Solver, Verdict};
+
+    fn play<const COLUMNS: usize, const ROWS: usize>(moves: &[usize]) -> Game<COLUMNS, ROWS> {
+        try_play(moves).unwrap()
+    }
+
+    fn try_play<const COLUMNS: usize, const ROWS: usize>(
+        moves: &[usize],
+    ) -> Result<Game<COLUMNS, ROWS>, GameError<COLUMNS, ROWS>> {
+        let mut game = Game::initialise();
+        for &column in moves {
+            game = game.play_on_column(column)?;
+        }
+        Ok(game)
+    }
+
+    #[test]
+    fn sees_an_immediate_win() {
+        let game = play::<DEFAULT_COLUMNS, DEFAULT_ROWS>(&[0, 1, 0, 1, 0, 1]);
+        assert_eq!(Solver::new().verdict(&game), Verdict::Win);
+    }
+
+    #[test]
+    fn sees_a_loss_that_cannot_be_blocked() {
+        // Player Two has to block on one side of Player One's open three, and loses on the other
+        let game = play::<DEFAULT_COLUMNS, DEFAULT_ROWS>(&[2, 2, 3, 3, 4]);
+        assert_eq!(Solver::new().verdict(&game), Verdict::Loss);
+    }
+
+    #[test]
+    fn small_boards_where_four_cannot_be_connected_are_draws() {
+        let game = play::<3, 3>(&[]);
+        assert_eq!(Solver::new().verdict(&game), Verdict::Draw);
+    }
+
+    #[test]
+    fn the_four_by_four_board_is_a_draw() {
+        let game = play::<4, 4>(&[]);
+        assert_eq!(Solver::new().verdict(&game), Verdict::Draw);
+    }
+
+    // Plays every line to the end, remembering positions it has already seen
+    fn brute_force<const COLUMNS: usize, const ROWS: usize>(
+        game: &Game<COLUMNS, ROWS>,
+        seen: &mut HashMap<String, Verdict>,
+    ) -> Verdict {
+        match game.status {
+            GameStatus::Completed => return Verdict::Win,
+            GameStatus::Draw => return Verdict::Draw,
+            GameStatus::Started => (),
+        }
+        let key = format!("{}", game);
+        if let Some(verdict) = seen.get(&key) {
+            return *verdict;
+        }
+        let mut best = Verdict::Loss;
+        for column in 0..COLUMNS {
+            let Ok(next) = game.clone().play_on_column(column) else {
+                continue;
+            };
+            let verdict = match (next.status.clone(), brute_force(&next, seen)) {
+                (GameStatus::Completed, _) => Verdict::Win,
+                (_, Verdict::Win) => Verdict::Loss,
+                (_, Verdict::Loss) => Verdict::Win,
+                (_, Verdict::Draw) => Verdict::Draw,
+            };
+            if verdict == Verdict::Win || (verdict == Verdict::Draw && best == Verdict::Loss) {
+                best = verdict;
+            }
+        }
+        seen.insert(key, best);
+        best
+    }
+
+    #[test]
+    fn agrees_with_playing_out_every_line_on_a_small_board() {
+        let mut seen = HashMap::new();
+        let mut solver = Solver::<4, 4>::new();
+        let mut verdicts = vec![];
+        for opening in 0..4usize.pow(6) {
+            let moves: Vec<usize> = (0..6).map(|i| opening / 4usize.pow(i) % 4).collect();
+            let Ok(game) = try_play::<4, 4>(&moves) else {
+                continue;
+            };
+            let verdict = solver.verdict(&game);
+            assert_eq!(
+                verdict,
+                brute_force(&game, &mut seen),
+                "after playing {:?}",
+                moves
+            );
+            verdicts.push(verdict);
+        }
+        assert!(verdicts.contains(&Verdict::Win));
+        assert!(verdicts.contains(&Verdict::Loss));
+        assert!(verdicts.contains(&Verdict::Draw));
+    }
+
+    #[test]
+    fn finished_games_report_the_result_for_the_current_player() {
+        let game = play::<DEFAULT_COLUMNS, DEFAULT_ROWS>(&[0, 1, 0, 1, 0, 1, 0]);
+        assert_eq!(Solver::new().verdict(&game), Verdict::Win);
+        let game = play::<1, 1>(&[0]);
+        assert_eq!(Solver::new().verdict(&game), Verdict::Draw);
+    }
+}
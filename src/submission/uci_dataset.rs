@@ -0,0 +1,176 @@
+use super::{
+    game::{Game, Player, DEFAULT_COLUMNS, DEFAULT_ROWS},
+    solver::{Solver, Verdict},
+};
+
+pub const DEFAULT_DATASET_PATH: &str = "connect-4.data";
+
+pub struct Example {
+    // One indexed, to match the line numbers shown by text editors
+    pub line: usize,
+    pub game: Game<DEFAULT_COLUMNS, DEFAULT_ROWS>,
+    // The result of the game with perfect play, for Player One
+    pub label: Verdict,
+}
+
+pub struct Mismatch {
+    pub line: usize,
+    pub expected: Verdict,
+    pub actual: Verdict,
+}
+
+pub struct Report {
+    pub checked: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+/* Parses a row of the UCI Machine Learning Repository's connect-4 dataset.
+Each row is 42 cells followed by a label, all comma separated. The cells go up each column from the bottom,
+one column at a time from the left, and are `x` for Player One, `o` for Player Two and `b` for blank.
+The label is `win`, `loss` or `draw` for Player One.
+*/
+pub fn parse_row(row: &str) -> Result<(Game<DEFAULT_COLUMNS, DEFAULT_ROWS>, Verdict), String> {
+    let fields: Vec<&str> = row.trim().split(',').collect();
+    let cell_count = DEFAULT_COLUMNS * DEFAULT_ROWS;
+    if fields.len() != cell_count + 1 {
+        return Err(format!(
+            "Expected {} cells and a label but found {} fields.",
+            cell_count,
+            fields.len()
+        ));
+    }
+
+    let mut board = [[Player::None; DEFAULT_ROWS]; DEFAULT_COLUMNS];
+    for (index, field) in fields[..cell_count].iter().enumerate() {
+        board[index / DEFAULT_ROWS][index % DEFAULT_ROWS] = match *field {
+            "x" => Player::One,
+            "o" => Player::Two,
+            "b" => Player::None,
+            other => return Err(format!("Did not recognise <{}> as a cell.", other)),
+        };
+    }
+    let label = match fields[cell_count] {
+        "win" => Verdict::Win,
+        "loss" => Verdict::Loss,
+        "draw" => Verdict::Draw,
+        other => return Err(format!("Did not recognise <{}> as a label.", other)),
+    };
+
+    Ok((Game::from_board(board)?, label))
+}
+
+// Imports every row of the dataset, returning the valid examples and a message for each invalid row
+pub fn import(contents: &str) -> (Vec<Example>, Vec<String>) {
+    let mut examples = vec![];
+    let mut errors = vec![];
+    for (index, row) in contents.lines().enumerate() {
+        if row.trim().is_empty() {
+            continue;
+        }
+        match parse_row(row) {
+            Ok((game, label)) => examples.push(Example {
+                line: index + 1,
+                game,
+                label,
+            }),
+            Err(message) => errors.push(format!("Line {}: {}", index + 1, message)),
+        }
+    }
+    (examples, errors)
+}
+
+// Checks the solver's verdict for every example against its label, calling `progress` after each one
+pub fn verify(
+    examples: &[Example],
+    solver: &mut Solver<DEFAULT_COLUMNS, DEFAULT_ROWS>,
+    mut progress: impl FnMut(usize),
+) -> Report {
+    let mut mismatches = vec![];
+    for (index, example) in examples.iter().enumerate() {
+        let actual = for_player_one(solver.verdict(&example.game), example.game.current);
+        if actual != example.label {
+            mismatches.push(Mismatch {
+                line: example.line,
+                expected: example.label,
+                actual,
+            });
+        }
+        progress(index + 1);
+    }
+    Report {
+        checked: examples.len(),
+        mismatches,
+    }
+}
+
+// The solver gives verdicts for the player whose turn it is, but the labels are for Player One
+fn for_player_one(verdict: Verdict, current: Player) -> Verdict {
+    match (current, verdict) {
+        (Player::Two, Verdict::Win) => Verdict::Loss,
+        (Player::Two, Verdict::Loss) => Verdict::Win,
+        (_, verdict) => verdict,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::submission::{
+        game::{GameStatus, Player},
+        solver::{Solver, Verdict},
+    };
+
+    use super::{import, parse_row, verify};
+
+    // Player One has three along the bottom with both ends open
+    const OPEN_THREE: &str =
+        "b,b,b,b,b,b,x,o,b,b,b,b,x,o,b,b,b,b,x,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,win";
+
+    #[test]
+    fn parses_cells_column_by_column_from_the_bottom() {
+        let (game, label) = parse_row(OPEN_THREE).unwrap();
+        assert_eq!(label, Verdict::Win);
+        assert_eq!(game.status, GameStatus::Started);
+        assert_eq!(game.current, Player::Two);
+        assert_eq!(game.board()[1][0], Player::One);
+        assert_eq!(game.board()[1][1], Player::Two);
+        assert_eq!(game.board()[3][0], Player::One);
+        assert_eq!(game.board()[3][1], Player::None);
+    }
+
+    #[test]
+    fn rejects_rows_that_break_gravity_or_turn_order() {
+        let floating = OPEN_THREE.replacen("x,o,b,b,b,b", "b,o,x,b,b,b", 1);
+        assert!(parse_row(&floating).is_err());
+        let extra_turn = OPEN_THREE.replacen("x,b,b,b,b,b", "x,x,b,b,b,b", 1);
+        assert!(parse_row(&extra_turn).is_err());
+        assert!(parse_row("b,b,win").is_err());
+        assert!(parse_row(&OPEN_THREE.replace("win", "maybe")).is_err());
+        assert!(parse_row(&OPEN_THREE.replacen('b', "q", 1)).is_err());
+    }
+
+    #[test]
+    fn reports_invalid_rows_by_line_number() {
+        let contents = format!("{}\n\nnot,a,row\n{}\n", OPEN_THREE, OPEN_THREE);
+        let (examples, errors) = import(&contents);
+        assert_eq!(
+            examples.iter().map(|x| x.line).collect::<Vec<_>>(),
+            vec![1, 4]
+        );
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("Line 3:"));
+    }
+
+    #[test]
+    fn verifies_labels_from_player_one_s_point_of_view() {
+        let contents = format!("{}\n{}\n", OPEN_THREE, OPEN_THREE.replace("win", "loss"));
+        let (examples, _) = import(&contents);
+        let mut checked = 0;
+        let report = verify(&examples, &mut Solver::new(), |count| checked = count);
+        assert_eq!(checked, 2);
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].line, 2);
+        assert_eq!(report.mismatches[0].expected, Verdict::Loss);
+        assert_eq!(report.mismatches[0].actual, Verdict::Win);
+    }
+}
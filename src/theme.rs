@@ -0,0 +1,302 @@
+use std::{
+    env,
+    io::{self, IsTerminal},
+};
+
+use crate::{labels::ColumnLabels, overengineered, remove_consts, submission};
+
+// Picks the theme when there's no --theme flag
+pub const THEME_VARIABLE: &str = "CONNECT4_THEME";
+
+// What can be in a cell, whichever version of the game it comes from
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Disc {
+    Empty,
+    One,
+    Two,
+}
+
+impl From<remove_consts::game::Player> for Disc {
+    fn from(player: remove_consts::game::Player) -> Self {
+        match player {
+            remove_consts::game::Player::None => Disc::Empty,
+            remove_consts::game::Player::One => Disc::One,
+            remove_consts::game::Player::Two => Disc::Two,
+        }
+    }
+}
+
+impl From<submission::game::Player> for Disc {
+    fn from(player: submission::game::Player) -> Self {
+        match player {
+            submission::game::Player::None => Disc::Empty,
+            submission::game::Player::One => Disc::One,
+            submission::game::Player::Two => Disc::Two,
+        }
+    }
+}
+
+impl From<overengineered::game::Player> for Disc {
+    fn from(player: overengineered::game::Player) -> Self {
+        match player {
+            overengineered::game::Player::None => Disc::Empty,
+            overengineered::game::Player::One => Disc::One,
+            overengineered::game::Player::Two => Disc::Two,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Style {
+    pub glyph: char,
+    // ANSI SGR parameters, such as "1;31" for bold red. Empty for no colour.
+    pub colour: &'static str,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Theme {
+    pub name: &'static str,
+    pub empty: Style,
+    pub one: Style,
+    pub two: Style,
+}
+
+// The original characters, without any colour
+pub const PLAIN: Theme = Theme {
+    name: "plain",
+    empty: Style {
+        glyph: '.',
+        colour: "",
+    },
+    one: Style {
+        glyph: 'x',
+        colour: "",
+    },
+    two: Style {
+        glyph: 'o',
+        colour: "",
+    },
+};
+
+pub const CLASSIC: Theme = Theme {
+    name: "classic",
+    empty: Style {
+        glyph: '.',
+        colour: "2",
+    },
+    one: Style {
+        glyph: 'x',
+        colour: "1;31",
+    },
+    two: Style {
+        glyph: 'o',
+        colour: "1;33",
+    },
+};
+
+// Blue and orange from the Okabe-Ito palette, which stay distinct with every common form of colour blindness
+pub const COLOUR_BLIND: Theme = Theme {
+    name: "colour-blind",
+    empty: Style {
+        glyph: '.',
+        colour: "2",
+    },
+    one: Style {
+        glyph: 'x',
+        colour: "1;38;5;32",
+    },
+    two: Style {
+        glyph: 'o',
+        colour: "1;38;5;208",
+    },
+};
+
+pub const UNICODE: Theme = Theme {
+    name: "unicode",
+    empty: Style {
+        glyph: '·',
+        colour: "2",
+    },
+    one: Style {
+        glyph: '●',
+        colour: "31",
+    },
+    two: Style {
+        glyph: '●',
+        colour: "33",
+    },
+};
+
+pub const HIGH_CONTRAST: Theme = Theme {
+    name: "high-contrast",
+    empty: Style {
+        glyph: '.',
+        colour: "97;40",
+    },
+    one: Style {
+        glyph: 'X',
+        colour: "1;97;40",
+    },
+    two: Style {
+        glyph: 'O',
+        colour: "1;30;107",
+    },
+};
+
+pub const THEMES: [Theme; 5] = [PLAIN, CLASSIC, COLOUR_BLIND, UNICODE, HIGH_CONTRAST];
+
+impl Theme {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase().replace(['_', ' '], "-");
+        THEMES
+            .into_iter()
+            .find(|theme| theme.name == name || theme.name.replace("colour", "color") == name)
+    }
+
+    /* The theme to play with. Colour is left out when NO_COLOR is set, the terminal is dumb
+    or the output isn't a terminal, so the board falls back to the plain characters.
+    Otherwise it's the requested theme, then the one named by CONNECT4_THEME, then classic.
+    */
+    pub fn detect(requested: Option<Theme>) -> Self {
+        let no_colour = env::var_os("NO_COLOR").is_some_and(|x| !x.is_empty())
+            || env::var("TERM").is_ok_and(|x| x == "dumb")
+            || !io::stdout().is_terminal();
+        choose(
+            requested,
+            env::var(THEME_VARIABLE).ok().as_deref(),
+            no_colour,
+        )
+    }
+
+    pub fn style(&self, disc: impl Into<Disc>) -> Style {
+        match disc.into() {
+            Disc::Empty => self.empty,
+            Disc::One => self.one,
+            Disc::Two => self.two,
+        }
+    }
+
+    // The disc's glyph, wrapped in the escape codes for its colour
+    pub fn paint(&self, disc: impl Into<Disc>) -> String {
+        let style = self.style(disc);
+        match style.colour {
+            "" => style.glyph.to_string(),
+            colour => format!("\x1b[{}m{}\x1b[0m", colour, style.glyph),
+        }
+    }
+}
+
+fn choose(requested: Option<Theme>, from_environment: Option<&str>, no_colour: bool) -> Theme {
+    if no_colour {
+        return PLAIN;
+    }
+    requested
+        .or_else(|| from_environment.and_then(Theme::from_name))
+        .unwrap_or(CLASSIC)
+}
+
+/* Draws a board the way the games' Display impls do, top row first, with the column labels underneath.
+Works for the fixed size arrays and the resizable Vecs alike.
+Every cell is as wide as the longest label, with a space between cells once the labels need more than one character,
+so that wide boards still line up with their labels. Boards with single character labels are drawn as they always were.
+*/
+pub fn render_board<P, C>(board: &[C], theme: &Theme, labels: ColumnLabels) -> String
+where
+    P: Copy + Into<Disc>,
+    C: AsRef<[P]>,
+{
+    let rows = board.first().map_or(0, |column| column.as_ref().len());
+    let width = labels.width(board.len());
+    let separator = match width {
+        1 => "",
+        _ => " ",
+    };
+    let mut output: String = "\n".to_owned();
+    for row in (0..rows).rev() {
+        let cells: Vec<String> = board
+            .iter()
+            .map(|column| " ".repeat(width - 1) + &theme.paint(column.as_ref()[row]))
+            .collect();
+        output += &cells.join(separator);
+        output += "\n"
+    }
+    let footer: Vec<String> = (0..board.len())
+        .map(|column| format!("{:>width$}", labels.label(column)))
+        .collect();
+    output += &footer.join(separator);
+    output += "\n";
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{labels::ColumnLabels, remove_consts::game::Player};
+
+    use super::{choose, render_board, Theme, CLASSIC, HIGH_CONTRAST, PLAIN, THEMES, UNICODE};
+
+    #[test]
+    fn plain_boards_match_the_original_characters() {
+        let board = vec![
+            vec![Player::One, Player::None],
+            vec![Player::Two, Player::None],
+            vec![Player::None, Player::None],
+        ];
+        assert_eq!(
+            render_board(&board, &PLAIN, ColumnLabels::ZeroIndexed),
+            "\n...\nxo.\n012\n"
+        );
+    }
+
+    #[test]
+    fn lines_wide_boards_up_with_their_labels() {
+        let mut board = vec![vec![Player::None]; 11];
+        board[10][0] = Player::One;
+        assert_eq!(
+            render_board(&board, &PLAIN, ColumnLabels::ZeroIndexed),
+            "\n .  .  .  .  .  .  .  .  .  .  x\n 0  1  2  3  4  5  6  7  8  9 10\n"
+        );
+        assert_eq!(
+            render_board(&board, &PLAIN, ColumnLabels::Letters),
+            "\n..........x\nabcdefghijk\n"
+        );
+        let board = vec![vec![Player::Two]; 28];
+        let rendered = render_board(&board, &CLASSIC, ColumnLabels::Letters);
+        assert!(rendered.starts_with("\n \x1b[1;33mo\x1b[0m  \x1b[1;33mo"));
+        assert!(rendered.ends_with(" y  z aa ab\n"));
+    }
+
+    #[test]
+    fn colours_the_discs() {
+        let board = [[Player::One], [Player::Two]];
+        assert_eq!(
+            render_board(&board, &CLASSIC, ColumnLabels::ZeroIndexed),
+            "\n\x1b[1;31mx\x1b[0m\x1b[1;33mo\x1b[0m\n01\n"
+        );
+        assert_eq!(UNICODE.paint(Player::Two), "\x1b[33m●\x1b[0m");
+    }
+
+    #[test]
+    fn every_theme_tells_the_players_apart() {
+        for theme in THEMES {
+            assert_ne!(theme.style(Player::One), theme.style(Player::Two));
+            assert_eq!(Theme::from_name(theme.name), Some(theme));
+        }
+        assert_eq!(
+            Theme::from_name("Colour Blind"),
+            Theme::from_name("color-blind")
+        );
+        assert_eq!(Theme::from_name("neon"), None);
+    }
+
+    #[test]
+    fn falls_back_to_plain_characters_without_colour() {
+        assert_eq!(choose(Some(HIGH_CONTRAST), Some("unicode"), true), PLAIN);
+        assert_eq!(
+            choose(Some(HIGH_CONTRAST), Some("unicode"), false),
+            HIGH_CONTRAST
+        );
+        assert_eq!(choose(None, Some("unicode"), false), UNICODE);
+        assert_eq!(choose(None, Some("neon"), false), CLASSIC);
+        assert_eq!(choose(None, None, false), CLASSIC);
+    }
+}
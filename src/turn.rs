@@ -0,0 +1,307 @@
+use std::fs;
+
+use crate::{labels::ColumnLabels, locale, remove_consts::archive::parse_moves};
+
+pub const DEFAULT_SAVE_PATH: &str = "saved.game";
+
+// The commands, for the help command
+pub fn help() -> String {
+    locale::text("turn.help", &[("save_path", &DEFAULT_SAVE_PATH)])
+}
+
+// Everything that can be entered at the turn prompt
+#[derive(PartialEq, Debug, Clone)]
+pub enum TurnCommand {
+    // The zero indexed column to play in
+    Play(usize),
+    Undo,
+    Hint,
+    Save(String),
+    Load(String),
+    Resign,
+    OfferDraw,
+    Board,
+    // Describe a column, or a row counting from 1 at the bottom, in words
+    Column(usize),
+    Row(usize),
+    History,
+    Rules,
+    Help,
+    Quit,
+}
+
+const COMMAND_NAMES: [&str; 13] = [
+    "undo", "hint", "save", "load", "resign", "draw?", "board", "column", "row", "history",
+    "rules", "help", "quit",
+];
+
+// Words this far from a command, counting a swapped pair of letters as one change, are taken as typos
+const TYPO_DISTANCE: usize = 2;
+
+/* Reads a turn. Commands come first, so that on boards with letter labels a command is never read as a column.
+`save` and `load` take an optional file, `column` and `row` need one, and the other commands take nothing after them.
+*/
+pub fn parse(input: &str, labels: ColumnLabels, columns: usize) -> Result<TurnCommand, String> {
+    let input = input.trim();
+    let (word, argument) = match input.split_once(char::is_whitespace) {
+        Some((word, argument)) => (word, argument.trim()),
+        None => (input, ""),
+    };
+    let path = || match argument {
+        "" => DEFAULT_SAVE_PATH.to_owned(),
+        path => path.to_owned(),
+    };
+    let command = match word.to_lowercase().as_str() {
+        "undo" => TurnCommand::Undo,
+        "hint" => TurnCommand::Hint,
+        "save" => TurnCommand::Save(path()),
+        "load" => TurnCommand::Load(path()),
+        "resign" => TurnCommand::Resign,
+        "draw?" | "draw" => TurnCommand::OfferDraw,
+        "board" => TurnCommand::Board,
+        "column" => match argument {
+            "" => return Err(needs_argument(word, "turn.a_column")),
+            column => TurnCommand::Column(labels.parse(column, columns)?),
+        },
+        "row" => match argument.parse::<usize>() {
+            Ok(row) => TurnCommand::Row(row),
+            Err(_) => return Err(needs_argument(word, "turn.a_row")),
+        },
+        "history" => TurnCommand::History,
+        "rules" => TurnCommand::Rules,
+        "help" | "?" => TurnCommand::Help,
+        "quit" | "exit" => TurnCommand::Quit,
+        _ => return parse_column(input, labels, columns),
+    };
+    match command {
+        TurnCommand::Save(_)
+        | TurnCommand::Load(_)
+        | TurnCommand::Column(_)
+        | TurnCommand::Row(_) => (),
+        _ if !argument.is_empty() => {
+            return Err(locale::text("turn.no_argument", &[("command", &word)]));
+        }
+        _ => (),
+    }
+    Ok(command)
+}
+
+fn needs_argument(command: &str, what: &str) -> String {
+    locale::text(
+        "turn.needs_argument",
+        &[("command", &command), ("what", &locale::text(what, &[]))],
+    )
+}
+
+// The moves so far, one per line, such as "  1. Player One plays 3"
+pub fn history(moves: &[usize], names: [&str; 2], labels: ColumnLabels) -> String {
+    if moves.is_empty() {
+        return locale::text("turn.no_moves", &[]);
+    }
+    let lines: Vec<String> = moves
+        .iter()
+        .enumerate()
+        .map(|(index, &column)| {
+            locale::text(
+                "turn.move",
+                &[
+                    ("number", &format!("{:>3}", index + 1)),
+                    ("name", &names[index % 2]),
+                    ("column", &labels.label(column)),
+                ],
+            )
+        })
+        .collect();
+    lines.join("\n")
+}
+
+// Saved games are a single line of zero indexed moves, in the same form as the command line takes them
+pub fn save_moves(path: &str, moves: &[usize]) -> Result<(), String> {
+    let moves: Vec<String> = moves.iter().map(|x| x.to_string()).collect();
+    fs::write(path, moves.join(",") + "\n")
+        .map_err(|error| format!("Could not save the game to <{}>: {}", path, error))
+}
+
+pub fn load_moves(path: &str) -> Result<Vec<usize>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|error| format!("Could not load a game from <{}>: {}", path, error))?;
+    parse_moves(contents.trim())
+}
+
+/* Replays the moves from the start of a game, with the game's own play_on_column.
+Gives the position reached along with every earlier position, so that the moves can be undone.
+*/
+pub fn replay<G: Clone>(
+    start: G,
+    moves: &[usize],
+    play: impl Fn(G, usize) -> Result<G, String>,
+) -> Result<(G, Vec<G>), String> {
+    let mut game = start;
+    let mut earlier = vec![];
+    for (index, &column) in moves.iter().enumerate() {
+        earlier.push(game.clone());
+        game = play(game, column)
+            .map_err(|message| format!("Move {} in column {}: {}", index + 1, column, message))?;
+    }
+    Ok((game, earlier))
+}
+
+fn parse_column(input: &str, labels: ColumnLabels, columns: usize) -> Result<TurnCommand, String> {
+    labels
+        .parse(input, columns)
+        .map(TurnCommand::Play)
+        .map_err(|message| {
+            if let Some(command) = suggest(input) {
+                return locale::text(
+                    "turn.did_you_mean",
+                    &[("input", &input), ("command", &command)],
+                );
+            }
+            let looks_like_a_column = !input.is_empty()
+                && match labels {
+                    ColumnLabels::Letters => input.chars().all(|x| x.is_ascii_alphabetic()),
+                    _ => input.chars().all(|x| x.is_ascii_digit()),
+                };
+            match looks_like_a_column {
+                true => message,
+                false => locale::text("turn.unrecognised", &[("input", &input)]),
+            }
+        })
+}
+
+// The closest command to a mistyped word, if there's one close enough
+fn suggest(word: &str) -> Option<&'static str> {
+    let word = word.to_lowercase();
+    if word.chars().count() < 3 {
+        return None;
+    }
+    COMMAND_NAMES
+        .into_iter()
+        .map(|command| (edit_distance(&word, command), command))
+        .filter(|(distance, _)| *distance <= TYPO_DISTANCE)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, command)| command)
+}
+
+// Insertions, deletions, substitutions and swaps of neighbouring letters each count as one edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + substitution);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::labels::ColumnLabels;
+
+    use super::{history, parse, replay, TurnCommand, DEFAULT_SAVE_PATH};
+
+    fn parse_numbers(input: &str) -> Result<TurnCommand, String> {
+        parse(input, ColumnLabels::ZeroIndexed, 7)
+    }
+
+    #[test]
+    fn reads_columns_and_commands() {
+        assert_eq!(parse_numbers("3\n"), Ok(TurnCommand::Play(3)));
+        assert_eq!(parse_numbers(" Undo "), Ok(TurnCommand::Undo));
+        assert_eq!(parse_numbers("draw?"), Ok(TurnCommand::OfferDraw));
+        assert_eq!(parse_numbers("quit"), Ok(TurnCommand::Quit));
+        assert_eq!(
+            parse_numbers("save"),
+            Ok(TurnCommand::Save(DEFAULT_SAVE_PATH.to_owned()))
+        );
+        assert_eq!(
+            parse_numbers("load my game.txt"),
+            Ok(TurnCommand::Load("my game.txt".to_owned()))
+        );
+        assert!(parse_numbers("hint 3").is_err());
+        assert_eq!(
+            parse("column c", ColumnLabels::Letters, 7),
+            Ok(TurnCommand::Column(2))
+        );
+        assert_eq!(parse_numbers("row 1"), Ok(TurnCommand::Row(1)));
+        assert_eq!(
+            parse_numbers("row"),
+            Err("<row> needs to be followed by a row.".to_owned())
+        );
+        assert!(parse_numbers("column 7").is_err());
+        // Commands win over letter labels
+        assert_eq!(
+            parse("help", ColumnLabels::Letters, 100_000),
+            Ok(TurnCommand::Help)
+        );
+        assert_eq!(
+            parse("c", ColumnLabels::Letters, 7),
+            Ok(TurnCommand::Play(2))
+        );
+    }
+
+    #[test]
+    fn suggests_commands_for_typos() {
+        assert_eq!(
+            parse_numbers("hlep"),
+            Err(
+                "Did not recognise <hlep>. Did you mean <help>? Enter help to see the commands."
+                    .to_owned()
+            )
+        );
+        assert!(parse_numbers("udno").unwrap_err().contains("<undo>"));
+        assert!(parse_numbers("resing").unwrap_err().contains("<resign>"));
+        assert!(parse_numbers("histroy").unwrap_err().contains("<history>"));
+        assert!(parse("bord", ColumnLabels::Letters, 7)
+            .unwrap_err()
+            .contains("<board>"));
+        assert_eq!(
+            parse_numbers("banana"),
+            Err("Did not recognise <banana> as a column or a command. Enter help to see the commands.".to_owned())
+        );
+        assert_eq!(
+            parse_numbers("9"),
+            Err("There is no column <9>. Please choose from 0-6.".to_owned())
+        );
+    }
+
+    #[test]
+    fn lists_the_moves_by_player() {
+        assert_eq!(
+            history(&[3, 2, 3], ["Ada", "Grace"], ColumnLabels::Letters),
+            "  1. Ada plays d\n  2. Grace plays c\n  3. Ada plays d"
+        );
+        assert_eq!(
+            history(&[], ["Ada", "Grace"], ColumnLabels::Letters),
+            "No moves have been played yet."
+        );
+    }
+
+    #[test]
+    fn replays_moves_keeping_every_position() {
+        let play = |total: usize, column: usize| match column {
+            9 => Err("Column out of bounds".to_owned()),
+            column => Ok(total * 10 + column),
+        };
+        assert_eq!(replay(0, &[1, 2, 3], play), Ok((123, vec![0, 1, 12])));
+        assert_eq!(
+            replay(0, &[1, 9], play),
+            Err("Move 2 in column 9: Column out of bounds".to_owned())
+        );
+    }
+}
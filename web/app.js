@@ -0,0 +1,457 @@
+// The browser board. It speaks the same line protocol as the terminal client, one line per WebSocket message.
+"use strict";
+
+const PROTOCOL_VERSION = 6;
+
+const $ = (id) => document.getElementById(id);
+
+// Everything the page knows about the game, all of it learnt from the server
+const state = {
+  socket: null,
+  // "local", "online" or "watch"
+  mode: null,
+  // The side this page plays, or null when watching. A local game plays whoever's turn it is.
+  side: null,
+  names: ["", ""],
+  columns: 0,
+  rows: 0,
+  // A column at a time from the left, each from the bottom up, holding "one", "two" or null
+  board: [],
+  turn: null,
+  over: false,
+  // The column of the last move, until the board after it arrives
+  lastMove: null,
+  // Games listed so far, and how many the server said there would be
+  listing: [],
+  listed: 0,
+  // The token for the online game in progress, and until when the server holds the seat
+  session: null,
+  resumeBy: 0,
+  // Whether RESUME has been sent and not yet answered
+  resuming: false,
+  // Each side's time left in milliseconds as of clockAt, in games with a clock
+  clock: null,
+  clockAt: 0,
+};
+
+// How long to wait between attempts to reconnect
+const RECONNECT_INTERVAL = 2000;
+
+function connect(onOpen) {
+  if (state.socket && state.socket.readyState === WebSocket.OPEN) {
+    onOpen();
+    return;
+  }
+  const scheme = location.protocol === "https:" ? "wss" : "ws";
+  const socket = new WebSocket(`${scheme}://${location.host}/ws`);
+  state.socket = socket;
+  socket.onmessage = (event) => {
+    for (const line of event.data.split("\n")) {
+      if (line) {
+        receive(line, onOpen);
+      }
+    }
+  };
+  socket.onclose = () => {
+    if (state.socket !== socket) {
+      return;
+    }
+    state.socket = null;
+    if (state.session && !state.over && Date.now() < state.resumeBy) {
+      setStatus("Lost the connection to the server. Trying to reconnect.");
+      setTimeout(resume, RECONNECT_INTERVAL);
+    } else {
+      showError("The connection to the server was closed.");
+    }
+  };
+}
+
+// Takes the seat back in the online game, for as long as the server holds it
+function resume() {
+  connect(() => {
+    state.resuming = true;
+    send(`RESUME ${state.session}`);
+  });
+}
+
+function send(line) {
+  if (state.socket && state.socket.readyState === WebSocket.OPEN) {
+    state.socket.send(line);
+  }
+}
+
+function receive(line, onHello) {
+  const space = line.indexOf(" ");
+  const keyword = space < 0 ? line : line.slice(0, space);
+  const rest = space < 0 ? "" : line.slice(space + 1);
+  const fields = rest.split(" ");
+  switch (keyword) {
+    case "HELLO":
+      if (Number(rest) !== PROTOCOL_VERSION) {
+        showError(`The server speaks version ${rest} of the protocol, but this page speaks version ${PROTOCOL_VERSION}.`);
+        state.socket.close();
+        return;
+      }
+      onHello();
+      break;
+    case "WAITING":
+      setStatus("Waiting for an opponent to join.");
+      break;
+    case "START": {
+      const side = fields[0];
+      const opponent = fields.slice(3).join(" ");
+      const own = $("name-one").value.trim() || "Player One";
+      state.side = state.mode === "local" ? null : side;
+      state.names = state.mode === "local" || side === "one" ? [own, opponent] : [opponent, own];
+      startTable(Number(fields[1]), Number(fields[2]));
+      break;
+    }
+    case "SESSION":
+      state.session = fields[0];
+      state.resumeBy = Date.now() + Number(fields[1]) * 1000;
+      break;
+    case "RESUMED":
+      state.resuming = false;
+      state.side = fields[1];
+      state.names = fields.slice(4).join(" ").split("\t");
+      startTable(Number(fields[2]), Number(fields[3]));
+      break;
+    case "AWAY": {
+      const seconds = Number(fields[1]);
+      setStatus(`${nameOf(fields[0])} lost their connection, and has ${seconds} ${seconds === 1 ? "second" : "seconds"} to come back.`);
+      break;
+    }
+    case "BACK":
+      setStatus(`${nameOf(rest)} is back.`);
+      break;
+    case "WATCHING": {
+      const names = fields.slice(3).join(" ").split("\t");
+      state.side = null;
+      state.names = names;
+      startTable(Number(fields[1]), Number(fields[2]));
+      break;
+    }
+    case "BOARD":
+      showBoard(decode(rest));
+      break;
+    case "CLOCK":
+      state.clock = fields.map(Number);
+      state.clockAt = Date.now();
+      $("clocks").hidden = false;
+      break;
+    case "TURN":
+      state.turn = rest;
+      updateStatus();
+      showClocks();
+      break;
+    case "MOVED":
+      state.lastMove = Number(fields[1]);
+      break;
+    case "LINE":
+      for (const cell of fields) {
+        const [column, row] = cell.split(",").map(Number);
+        const disc = discAt(column, row);
+        if (disc) {
+          disc.classList.add("win");
+        }
+      }
+      break;
+    case "GAMEOVER":
+      finish(fields[0], fields[1]);
+      break;
+    case "GAMES":
+      state.listing = [];
+      state.listed = Number(rest);
+      if (state.listed === 0) {
+        showGames();
+      }
+      break;
+    case "GAME": {
+      const names = fields.slice(2).join(" ").split("\t");
+      state.listing.push({ game: Number(fields[0]), moves: Number(fields[1]), names });
+      if (state.listing.length === state.listed) {
+        showGames();
+      }
+      break;
+    }
+    case "HISTORY":
+      break;
+    case "SPECTATORS": {
+      const count = Number(rest);
+      $("spectators").textContent = count === 0 ? "" : count === 1 ? "1 person is watching." : `${count} people are watching.`;
+      break;
+    }
+    case "ERROR":
+      showError(rest);
+      // The game ended while the page was away
+      if (state.resuming) {
+        state.resuming = false;
+        state.over = true;
+        state.session = null;
+        disableMoves();
+      }
+      break;
+  }
+}
+
+// The board as the server encodes it: x for one, o for two and b for blank, with / between columns
+function decode(encoded) {
+  const discs = { x: "one", o: "two", b: null };
+  return encoded.split("/").map((column) => [...column].map((cell) => discs[cell]));
+}
+
+function show(section) {
+  for (const id of ["setup", "games", "table"]) {
+    $(id).hidden = id !== section;
+  }
+}
+
+function startTable(columns, rows) {
+  state.columns = columns;
+  state.rows = rows;
+  state.board = Array.from({ length: columns }, () => Array(rows).fill(null));
+  state.turn = null;
+  state.over = false;
+  state.lastMove = null;
+  state.clock = null;
+  $("clocks").hidden = true;
+  showError("");
+  $("spectators").textContent = "";
+  $("players").textContent = `${state.names[0]} (red) against ${state.names[1]} (yellow)`;
+  $("resign").hidden = state.mode === "watch";
+  $("again").hidden = true;
+  document.documentElement.style.setProperty("--columns", columns);
+  buildBoard();
+  show("table");
+}
+
+// Builds the empty grid, a row at a time from the top, with a drop button above each column
+function buildBoard() {
+  const drops = $("drops");
+  const board = $("board");
+  drops.replaceChildren();
+  board.replaceChildren();
+  for (let column = 0; column < state.columns; column++) {
+    const button = document.createElement("button");
+    button.textContent = "▼";
+    button.setAttribute("aria-label", `Drop in column ${column + 1}`);
+    button.addEventListener("click", () => play(column));
+    drops.append(button);
+  }
+  for (let row = state.rows - 1; row >= 0; row--) {
+    for (let column = 0; column < state.columns; column++) {
+      const cell = document.createElement("div");
+      cell.className = "cell";
+      cell.dataset.column = column;
+      cell.dataset.row = row;
+      cell.addEventListener("click", () => play(column));
+      cell.addEventListener("mouseenter", () => hover(column));
+      cell.addEventListener("mouseleave", () => hover(null));
+      board.append(cell);
+    }
+  }
+}
+
+function hover(column) {
+  for (const cell of $("board").children) {
+    cell.classList.toggle("hover", column !== null && Number(cell.dataset.column) === column && canPlay());
+  }
+}
+
+function cellAt(column, row) {
+  return $("board").querySelector(`.cell[data-column="${column}"][data-row="${row}"]`);
+}
+
+function discAt(column, row) {
+  const cell = cellAt(column, row);
+  return cell && cell.querySelector(".disc");
+}
+
+// Draws any discs that are new, dropping the last move in from the top of the board
+function showBoard(board) {
+  for (let column = 0; column < board.length; column++) {
+    for (let row = 0; row < board[column].length; row++) {
+      const side = board[column][row];
+      if (side === state.board[column][row]) {
+        continue;
+      }
+      const cell = cellAt(column, row);
+      cell.querySelector(".disc")?.remove();
+      if (side) {
+        const disc = document.createElement("div");
+        disc.className = `disc ${side}`;
+        if (column === state.lastMove) {
+          disc.classList.add("drop");
+          disc.style.setProperty("--fall", state.rows - row);
+        }
+        cell.append(disc);
+      }
+    }
+  }
+  state.board = board;
+  state.lastMove = null;
+}
+
+function canPlay() {
+  if (state.over || state.mode === "watch" || state.turn === null) {
+    return false;
+  }
+  return state.mode === "local" || state.turn === state.side;
+}
+
+function play(column) {
+  if (canPlay()) {
+    showError("");
+    send(`MOVE ${column}`);
+  }
+}
+
+function nameOf(side) {
+  return side === "two" ? state.names[1] : state.names[0];
+}
+
+function updateStatus() {
+  const mine = canPlay();
+  for (const button of $("drops").children) {
+    button.disabled = !mine;
+  }
+  $("resign").disabled = !mine;
+  if (state.mode === "online" && !mine) {
+    setStatus(`Waiting for ${nameOf(state.turn)} to move.`);
+  } else {
+    setStatus(`${nameOf(state.turn)} to move.`);
+  }
+}
+
+function finish(winner, reason) {
+  // The clocks stop where they are
+  if (state.clock && state.turn) {
+    state.clock[state.turn === "two" ? 1 : 0] -= Date.now() - state.clockAt;
+  }
+  state.over = true;
+  showClocks();
+  state.session = null;
+  const loser = winner === "one" ? "two" : "one";
+  // A game on one screen that's left for too long just ends, with nobody to blame
+  if (winner === "draw" && reason === "abandoned") {
+    setStatus("The game was left for too long.");
+    disableMoves();
+    return;
+  }
+  const result = winner === "draw" ? "It's a draw." : `${nameOf(winner)} wins!`;
+  const why = {
+    resigned: `${nameOf(loser)} resigned. `,
+    disconnected: `${nameOf(loser)} left the game. `,
+    abandoned: `${nameOf(loser)} took too long to move. `,
+    timeout: `${nameOf(state.turn)} ran out of time. `,
+  }[reason] || "";
+  setStatus(why + result);
+  disableMoves();
+}
+
+// The time of the side to move runs down from when the server last sent the clocks, until the game ends
+function showClocks() {
+  if (!state.clock) {
+    return;
+  }
+  const sides = ["one", "two"];
+  sides.forEach((side, index) => {
+    const running = !state.over && state.turn === side;
+    const left = state.clock[index] - (running ? Date.now() - state.clockAt : 0);
+    const element = $(`clock-${side}`);
+    element.textContent = `${state.names[index]} ${formatTime(Math.max(left, 0))}`;
+    element.classList.toggle("running", running);
+  });
+}
+
+// Minutes and seconds, with tenths of a second in the last ten seconds
+function formatTime(milliseconds) {
+  const seconds = Math.floor(milliseconds / 1000);
+  if (seconds < 10) {
+    return `0:0${seconds}.${Math.floor((milliseconds % 1000) / 100)}`;
+  }
+  const minutes = Math.floor(seconds / 60);
+  const hours = Math.floor(minutes / 60);
+  const padded = (x) => String(x).padStart(2, "0");
+  return hours > 0 ? `${hours}:${padded(minutes % 60)}:${padded(seconds % 60)}` : `${minutes}:${padded(seconds % 60)}`;
+}
+
+function disableMoves() {
+  for (const button of $("drops").children) {
+    button.disabled = true;
+  }
+  $("resign").disabled = true;
+  $("again").hidden = false;
+  $("again").textContent = state.mode === "watch" ? "Watch another game" : "Play again";
+}
+
+function showGames() {
+  const list = $("game-list");
+  list.replaceChildren();
+  if (state.listing.length === 0) {
+    const item = document.createElement("li");
+    item.textContent = "No games are being played.";
+    list.append(item);
+  }
+  for (const { game, moves, names } of state.listing) {
+    const item = document.createElement("li");
+    const button = document.createElement("button");
+    button.textContent = `${names[0]} against ${names[1]}, ${moves} ${moves === 1 ? "move" : "moves"} in`;
+    button.addEventListener("click", () => send(`WATCH ${game}`));
+    item.append(button);
+    list.append(item);
+  }
+  show("games");
+}
+
+function setStatus(text) {
+  $("status").textContent = text;
+}
+
+function showError(text) {
+  $("error").textContent = text;
+}
+
+// Tabs separate names in the protocol, so they're taken out of what players type
+function name(id, fallback) {
+  return $(id).value.replace(/\t/g, " ").trim() || fallback;
+}
+
+function start(mode) {
+  state.mode = mode;
+  state.session = null;
+  showError("");
+  connect(() => {
+    if (mode === "local") {
+      send(`LOCAL ${name("name-one", "Player One")}\t${name("name-two", "Player Two")}`);
+    } else if (mode === "online") {
+      send(`JOIN ${name("name-one", "Player One")}`);
+    } else {
+      send("GAMES");
+    }
+  });
+}
+
+$("play-here").addEventListener("click", () => start("local"));
+$("play-online").addEventListener("click", () => start("online"));
+$("watch").addEventListener("click", () => start("watch"));
+$("again").addEventListener("click", () => start(state.mode));
+$("resign").addEventListener("click", () => send("RESIGN"));
+$("back").addEventListener("click", () => show("setup"));
+$("leave").addEventListener("click", () => {
+  // Leaving a game in play gives it to the opponent, and leaving a local one abandons it
+  send("QUIT");
+  state.socket = null;
+  setStatus("");
+  show("setup");
+});
+
+// Number keys drop a disc in that column, counting from 1
+document.addEventListener("keydown", (event) => {
+  const column = Number(event.key) - 1;
+  if (!$("table").hidden && column >= 0 && column < state.columns && event.target.tagName !== "INPUT") {
+    play(column);
+  }
+});
+
+setInterval(showClocks, 100);